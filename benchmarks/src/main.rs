@@ -2,8 +2,15 @@ use clap::{Parser, Subcommand};
 use shared::{
     benchmarks::*,
     models::*,
+    shopify::product_uuid_from_shopify_id,
+    shutdown::shutdown_signal,
 };
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::{CancellationToken, DropGuard};
 use tracing::{info, warn, error};
 
 #[derive(Parser)]
@@ -37,6 +44,10 @@ enum Commands {
         /// Ramp-up time in seconds
         #[arg(short, long, default_value = "10")]
         ramp_up: u64,
+
+        /// Print the scenarios and send each endpoint one request instead of running the load test
+        #[arg(long)]
+        dry_run: bool,
     },
     
     /// Run benchmark against a single framework
@@ -60,20 +71,50 @@ enum Commands {
         /// Ramp-up time in seconds
         #[arg(short, long, default_value = "10")]
         ramp_up: u64,
+
+        /// Print the scenarios and send each endpoint one request instead of running the load test
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Have the server benchmark its own router in-process, measuring handler latency
+        /// without the network. The server runs its built-in scenario with the given
+        /// --users, --duration and --ramp-up
+        #[arg(long)]
+        in_process: bool,
+
+        /// Print the request count, current throughput and errors every second while each
+        /// scenario runs. With --in-process the updates are streamed from the server
+        #[arg(long)]
+        progress: bool,
+
+        /// Fail if any scenario's P99 response time exceeds this many milliseconds
+        #[arg(long)]
+        max_p99_ms: Option<f64>,
+
+        /// Fail if any scenario's throughput falls below this many requests per second
+        #[arg(long)]
+        min_rps: Option<f64>,
     },
     
     /// Generate a comparison report from previous results
     Report {
-        /// Output format (markdown, json, html)
+        /// Output format (markdown, json, html, csv)
         #[arg(short, long, default_value = "markdown")]
         format: String,
         
         /// Output file path
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Saved comparison to load (defaults to the most recent one)
+        #[arg(short, long)]
+        input: Option<String>,
     },
 }
 
+// Where `compare` stores raw results for the `report` command
+const RESULTS_DIR: &str = "benchmark_results";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -85,26 +126,75 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Compare { axum_url, loco_url, users, duration, ramp_up } => {
-            run_comparison(axum_url, loco_url, users, duration, ramp_up).await?;
+        Commands::Compare { axum_url, loco_url, users, duration, ramp_up, dry_run: true } => {
+            dry_run(&[("AXUM", axum_url.as_str()), ("LOCO", loco_url.as_str())], users, duration, ramp_up).await?;
         }
-        Commands::Single { url, framework, users, duration, ramp_up } => {
-            run_single_benchmark(url, framework, users, duration, ramp_up).await?;
+        Commands::Compare { axum_url, loco_url, users, duration, ramp_up, dry_run: false } => {
+            run_comparison(axum_url, loco_url, users, duration, ramp_up, &cancel_on_interrupt()).await?;
         }
-        Commands::Report { format, output } => {
-            generate_report(format, output).await?;
+        Commands::Single { url, framework, users, duration, ramp_up, dry_run: true, .. } => {
+            dry_run(&[(framework.as_str(), url.as_str())], users, duration, ramp_up).await?;
+        }
+        Commands::Single { url, framework, users, duration, ramp_up, dry_run: false, in_process: true, progress, max_p99_ms, min_rps } => {
+            let sla = Sla { max_p99_ms, min_rps };
+            info!("🚀 Starting in-process {} benchmark at {}", framework, url);
+            info!("📊 Configuration: {} users, {}s duration, {}s ramp-up", users, duration, ramp_up);
+            let query = in_process_query(users, duration, ramp_up);
+            let result = if progress {
+                stream_in_process_benchmark(&url, &query).await?
+            } else {
+                run_in_process_benchmark(&url, &query).await?
+            };
+            report_single_results(&framework, &[result], sla)?;
+        }
+        Commands::Single { url, framework, users, duration, ramp_up, dry_run: false, in_process: false, progress, max_p99_ms, min_rps } => {
+            let sla = Sla { max_p99_ms, min_rps };
+            let results = run_single_benchmark(&url, &framework, users, duration, ramp_up, progress, &cancel_on_interrupt()).await?;
+            report_single_results(&framework, &results, sla)?;
+        }
+        Commands::Report { format, output, input } => {
+            generate_report(format, output, input).await?;
         }
     }
 
     Ok(())
 }
 
+/// Cancelled on the first Ctrl-C, so a long run can be cut short and still
+/// report what it measured. A second Ctrl-C exits straight away.
+fn cancel_on_interrupt() -> CancellationToken {
+    let cancel = CancellationToken::new();
+
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            shutdown_signal().await;
+            warn!("🛑 Interrupted, stopping virtual users and keeping partial results (Ctrl-C again to quit)");
+            cancel.cancel();
+
+            shutdown_signal().await;
+            std::process::exit(130);
+        }
+    });
+
+    cancel
+}
+
+/// Sleeps for `duration` unless `cancel` fires first
+async fn pause(duration: Duration, cancel: &CancellationToken) {
+    tokio::select! {
+        _ = cancel.cancelled() => {}
+        _ = tokio::time::sleep(duration) => {}
+    }
+}
+
 async fn run_comparison(
     axum_url: String,
     loco_url: String,
     users: u32,
     duration: u64,
     ramp_up: u64,
+    cancel: &CancellationToken,
 ) -> anyhow::Result<()> {
     info!("🚀 Starting AXUM vs LOCO comparison benchmark");
     info!("📊 Configuration: {} users, {}s duration, {}s ramp-up", users, duration, ramp_up);
@@ -113,7 +203,7 @@ async fn run_comparison(
 
     // Test AXUM
     info!("🔥 Testing AXUM framework at {}", axum_url);
-    match run_framework_benchmark(&axum_url, "AXUM", users, duration, ramp_up).await {
+    match run_framework_benchmark(&axum_url, "AXUM", users, duration, ramp_up, false, cancel).await {
         Ok(results) => {
             for result in results {
                 comparison.add_axum_result(result);
@@ -124,20 +214,24 @@ async fn run_comparison(
         }
     }
 
-    // Wait between tests
-    info!("⏳ Waiting 30 seconds between tests...");
-    tokio::time::sleep(Duration::from_secs(30)).await;
+    if cancel.is_cancelled() {
+        warn!("⏭️ Skipping LOCO, the comparison was interrupted");
+    } else {
+        // Wait between tests
+        info!("⏳ Waiting 30 seconds between tests...");
+        pause(Duration::from_secs(30), cancel).await;
 
-    // Test LOCO
-    info!("🔥 Testing LOCO framework at {}", loco_url);
-    match run_framework_benchmark(&loco_url, "LOCO", users, duration, ramp_up).await {
-        Ok(results) => {
-            for result in results {
-                comparison.add_loco_result(result);
+        // Test LOCO
+        info!("🔥 Testing LOCO framework at {}", loco_url);
+        match run_framework_benchmark(&loco_url, "LOCO", users, duration, ramp_up, false, cancel).await {
+            Ok(results) => {
+                for result in results {
+                    comparison.add_loco_result(result);
+                }
+            }
+            Err(e) => {
+                error!("LOCO benchmark failed: {}", e);
             }
-        }
-        Err(e) => {
-            error!("LOCO benchmark failed: {}", e);
         }
     }
 
@@ -146,28 +240,160 @@ async fn run_comparison(
     println!("\n{}", report);
 
     // Save report to file
-    let filename = format!("benchmark_report_{}.md", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let filename = format!("benchmark_report_{}.md", timestamp);
     tokio::fs::write(&filename, &report).await?;
     info!("📄 Report saved to {}", filename);
 
+    // Keep the raw results so `report` can render them in other formats later
+    std::fs::create_dir_all(RESULTS_DIR)?;
+    let results_path = Path::new(RESULTS_DIR).join(format!("comparison_{}.json", timestamp));
+    comparison.save_to_file(&results_path)?;
+    info!("💾 Results saved to {}", results_path.display());
+
     Ok(())
 }
 
+/// Limits `single` holds each scenario to, so CI can fail on a regression
+#[derive(Debug, Clone, Copy, Default)]
+struct Sla {
+    max_p99_ms: Option<f64>,
+    min_rps: Option<f64>,
+}
+
+impl Sla {
+    fn is_set(&self) -> bool {
+        self.max_p99_ms.is_some() || self.min_rps.is_some()
+    }
+
+    /// Describes each limit `result` breaks; empty when it meets them all
+    fn violations(&self, result: &BenchmarkResult) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let Some(max_p99_ms) = self.max_p99_ms {
+            if result.p99_response_time_ms > max_p99_ms {
+                violations.push(format!(
+                    "{}: P99 response time {:.2}ms is above the {:.2}ms limit",
+                    result.test_name, result.p99_response_time_ms, max_p99_ms
+                ));
+            }
+        }
+        if let Some(min_rps) = self.min_rps {
+            if result.requests_per_second < min_rps {
+                violations.push(format!(
+                    "{}: {:.2} req/s is below the {:.2} req/s minimum",
+                    result.test_name, result.requests_per_second, min_rps
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
 async fn run_single_benchmark(
-    url: String,
-    framework: String,
+    url: &str,
+    framework: &str,
     users: u32,
     duration: u64,
     ramp_up: u64,
-) -> anyhow::Result<()> {
+    show_progress: bool,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Vec<BenchmarkResult>> {
     info!("🚀 Starting {} benchmark at {}", framework, url);
     info!("📊 Configuration: {} users, {}s duration, {}s ramp-up", users, duration, ramp_up);
 
-    let results = run_framework_benchmark(&url, &framework, users, duration, ramp_up).await?;
+    run_framework_benchmark(url, framework, users, duration, ramp_up, show_progress, cancel).await
+}
+
+/// Query string asking for an in-process self-benchmark with the given load
+fn in_process_query(users: u32, duration: u64, ramp_up: u64) -> String {
+    format!("in_process=true&users={}&duration={}&ramp_up={}", users, duration, ramp_up)
+}
+
+/// Asks the server at `base_url` to benchmark itself through its `/benchmark`
+/// endpoint, calling its router directly instead of over TCP
+async fn run_in_process_benchmark(base_url: &str, query: &str) -> anyhow::Result<BenchmarkResult> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/benchmark?{}", base_url, query))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body: ApiResponse<BenchmarkResult> = response.json().await?;
+    body.data.ok_or_else(|| {
+        anyhow::anyhow!("In-process benchmark failed: {}", body.error.unwrap_or_else(|| status.to_string()))
+    })
+}
+
+/// Like `run_in_process_benchmark`, but through the server's
+/// `/benchmark/stream` WebSocket, printing progress updates as they arrive
+async fn stream_in_process_benchmark(base_url: &str, query: &str) -> anyhow::Result<BenchmarkResult> {
+    let url = format!("{}/benchmark/stream?{}", websocket_url(base_url), query);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+
+    while let Some(message) = socket.next().await {
+        let Message::Text(text) = message? else {
+            continue;
+        };
+        match serde_json::from_str::<BenchmarkStreamMessage>(text.as_str())? {
+            BenchmarkStreamMessage::Progress(update) => print_progress(&update),
+            BenchmarkStreamMessage::Completed(result) => return Ok(*result),
+            BenchmarkStreamMessage::Failed { error } => anyhow::bail!("In-process benchmark failed: {}", error),
+        }
+    }
+    anyhow::bail!("The benchmark stream closed before sending a result")
+}
+
+fn websocket_url(base_url: &str) -> String {
+    if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        base_url.to_string()
+    }
+}
+
+fn print_progress(update: &ProgressUpdate) {
+    info!(
+        "⏱️ {:.0}s: {} requests, {:.1} req/s, {} errors",
+        update.elapsed_seconds, update.requests, update.current_rps, update.errors
+    );
+}
+
+/// Prints `progress` every `PROGRESS_INTERVAL` until the returned guard is dropped
+fn report_progress(progress: Arc<BenchmarkProgress>) -> DropGuard {
+    let done = CancellationToken::new();
+    let stopped = done.clone();
 
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + PROGRESS_INTERVAL, PROGRESS_INTERVAL);
+        let mut previous = None;
+        loop {
+            tokio::select! {
+                _ = stopped.cancelled() => break,
+                _ = ticker.tick() => {
+                    let update = progress.update(previous.as_ref());
+                    print_progress(&update);
+                    previous = Some(update);
+                }
+            }
+        }
+    });
+
+    done.drop_guard()
+}
+
+/// Prints each scenario's results, then fails if any breaks the SLA
+fn report_single_results(framework: &str, results: &[BenchmarkResult], sla: Sla) -> anyhow::Result<()> {
     println!("\n# {} Benchmark Results\n", framework);
-    for result in &results {
-        println!("## {}", result.test_name);
+    for result in results {
+        if result.interrupted {
+            println!("## {} (interrupted, partial results)", result.test_name);
+        } else {
+            println!("## {}", result.test_name);
+        }
         println!("- Requests/sec: {:.2}", result.requests_per_second);
         println!("- Avg response time: {:.2}ms", result.average_response_time_ms);
         println!("- P95 response time: {:.2}ms", result.p95_response_time_ms);
@@ -175,6 +401,23 @@ async fn run_single_benchmark(
         println!();
     }
 
+    if !sla.is_set() {
+        return Ok(());
+    }
+    // Nothing measured can't be shown to meet the limits
+    if results.is_empty() {
+        return Err(anyhow::anyhow!("SLA check failed: no scenario produced results"));
+    }
+
+    let violations: Vec<String> = results.iter().flat_map(|result| sla.violations(result)).collect();
+    if !violations.is_empty() {
+        for violation in &violations {
+            error!("❌ SLA violated: {}", violation);
+        }
+        return Err(anyhow::anyhow!("SLA check failed: {} violation(s)", violations.len()));
+    }
+    info!("✅ All scenarios met the SLA");
+
     Ok(())
 }
 
@@ -184,25 +427,25 @@ async fn run_framework_benchmark(
     users: u32,
     duration: u64,
     ramp_up: u64,
+    show_progress: bool,
+    cancel: &CancellationToken,
 ) -> anyhow::Result<Vec<BenchmarkResult>> {
     let mut results = Vec::new();
 
-    // Test scenarios
-    let scenarios = vec![
-        ("Health Check", create_health_config(base_url, users, duration, ramp_up)),
-        ("REST API", create_rest_config(base_url, users, duration, ramp_up)),
-        ("GraphQL", create_graphql_config(base_url, users, duration, ramp_up)),
-        ("Mixed Load", create_mixed_config(base_url, users, duration, ramp_up)),
-    ];
-
-    for (test_name, config) in scenarios {
+    for (test_name, config) in benchmark_scenarios(base_url, users, duration, ramp_up) {
+        if cancel.is_cancelled() {
+            break;
+        }
         info!("🧪 Running {} test for {}", test_name, framework);
         
         let load_tester = LoadTester::new(config);
+        let reporter = show_progress.then(|| report_progress(load_tester.progress()));
         
-        match load_tester.run_benchmark(framework.to_string()).await {
+        let outcome = load_tester.run_benchmark_until(framework.to_string(), cancel.clone()).await;
+        drop(reporter);
+        match outcome {
             Ok(metrics) => {
-                let result = metrics.to_benchmark_result(test_name.to_string());
+                let result = metrics.to_benchmark_result(test_name.to_string(), None);
                 results.push(result);
             }
             Err(e) => {
@@ -211,18 +454,88 @@ async fn run_framework_benchmark(
         }
 
         // Wait between tests
-        tokio::time::sleep(Duration::from_secs(5)).await;
+        pause(Duration::from_secs(5), cancel).await;
     }
 
     Ok(results)
 }
 
+// Test scenarios
+fn benchmark_scenarios(base_url: &str, users: u32, duration: u64, ramp_up: u64) -> Vec<(&'static str, BenchmarkConfig)> {
+    vec![
+        ("Health Check", create_health_config(base_url, users, duration, ramp_up)),
+        ("REST API", create_rest_config(base_url, users, duration, ramp_up)),
+        ("GraphQL", create_graphql_config(base_url, users, duration, ramp_up)),
+        ("Mixed Load", create_mixed_config(base_url, users, duration, ramp_up)),
+        ("Authenticated", create_authenticated_config(base_url, users, duration, ramp_up)),
+    ]
+}
+
+/// Checks every target without load testing it; fails if any request did
+async fn dry_run(targets: &[(&str, &str)], users: u32, duration: u64, ramp_up: u64) -> anyhow::Result<()> {
+    info!("🔍 Dry run: {} users, {}s duration, {}s ramp-up", users, duration, ramp_up);
+
+    let mut failures = 0;
+    for (framework, url) in targets {
+        failures += dry_run_framework(url, framework, users, duration, ramp_up).await;
+    }
+
+    if failures > 0 {
+        return Err(anyhow::anyhow!("Dry run failed: {} validation request(s) failed", failures));
+    }
+    info!("✅ Dry run passed");
+    Ok(())
+}
+
+/// Prints each scenario's endpoints and sends each one request. Returns how
+/// many failed; a scenario whose login fails counts once.
+async fn dry_run_framework(base_url: &str, framework: &str, users: u32, duration: u64, ramp_up: u64) -> usize {
+    let mut failures = 0;
+
+    for (test_name, config) in benchmark_scenarios(base_url, users, duration, ramp_up) {
+        println!("\n## {} - {} ({} users, {}s duration, {}s ramp-up)",
+                 framework, test_name, config.concurrent_users, config.duration_seconds, config.ramp_up_seconds);
+
+        match LoadTester::new(config.clone()).validate().await {
+            Ok(checks) => {
+                // One check per endpoint, in config order
+                for (endpoint, check) in config.endpoints.iter().zip(checks) {
+                    let outcome = match &check.outcome {
+                        Ok(status) => status.to_string(),
+                        Err(e) => e.clone(),
+                    };
+                    let mark = if check.is_ok() { "✅" } else { "❌" };
+                    println!("{} {} {} (weight {:.2}): {}", mark, check.method, check.path, endpoint.weight, outcome);
+                    if let Some(body) = &endpoint.body {
+                        println!("   body: {}", body);
+                    }
+
+                    if !check.is_ok() {
+                        failures += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                println!("❌ {}", e);
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
 fn create_health_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -> BenchmarkConfig {
     BenchmarkConfig {
         target_url: base_url.to_string(),
         concurrent_users: users,
         duration_seconds: duration,
         ramp_up_seconds: ramp_up,
+        seed: None,
+        think_time: ThinkTime::None,
+        warmup_seconds: 0,
+        credentials: BenchmarkCredentials::default(),
+        global_headers: std::collections::HashMap::new(),
         endpoints: vec![
             EndpointConfig {
                 path: "/health".to_string(),
@@ -230,6 +543,8 @@ fn create_health_config(base_url: &str, users: u32, duration: u64, ramp_up: u64)
                 headers: std::collections::HashMap::new(),
                 body: None,
                 weight: 1.0,
+                requires_auth: false,
+                graphql: None,
             },
         ],
     }
@@ -241,16 +556,23 @@ fn create_rest_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -
         concurrent_users: users,
         duration_seconds: duration,
         ramp_up_seconds: ramp_up,
+        seed: None,
+        think_time: ThinkTime::None,
+        warmup_seconds: 0,
+        credentials: BenchmarkCredentials::default(),
+        global_headers: std::collections::HashMap::new(),
         endpoints: vec![
             EndpointConfig {
-                path: "/api/products".to_string(),
+                path: "/api/v1/products".to_string(),
                 method: "GET".to_string(),
                 headers: std::collections::HashMap::new(),
                 body: None,
                 weight: 0.6,
+                requires_auth: false,
+                graphql: None,
             },
             EndpointConfig {
-                path: "/api/products".to_string(),
+                path: "/api/v1/products".to_string(),
                 method: "POST".to_string(),
                 headers: {
                     let mut headers = std::collections::HashMap::new();
@@ -259,9 +581,11 @@ fn create_rest_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -
                 },
                 body: Some(r#"{"name":"Benchmark Product","description":"Created during benchmark","price":99.99}"#.to_string()),
                 weight: 0.2,
+                requires_auth: false,
+                graphql: None,
             },
             EndpointConfig {
-                path: "/api/auth/login".to_string(),
+                path: "/api/v1/auth/login".to_string(),
                 method: "POST".to_string(),
                 headers: {
                     let mut headers = std::collections::HashMap::new();
@@ -270,51 +594,44 @@ fn create_rest_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -
                 },
                 body: Some(r#"{"email":"benchmark@example.com","password":"BenchmarkPass123!"}"#.to_string()),
                 weight: 0.2,
+                requires_auth: false,
+                graphql: None,
             },
         ],
     }
 }
 
 fn create_graphql_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -> BenchmarkConfig {
+    let graphql = |operation: GraphQLOperation, weight: f32| EndpointConfig {
+        path: "/graphql".to_string(),
+        method: "POST".to_string(),
+        headers: std::collections::HashMap::new(),
+        body: None,
+        weight,
+        requires_auth: false,
+        graphql: Some(operation),
+    };
+
     BenchmarkConfig {
         target_url: base_url.to_string(),
         concurrent_users: users,
         duration_seconds: duration,
         ramp_up_seconds: ramp_up,
+        seed: None,
+        think_time: ThinkTime::None,
+        warmup_seconds: 0,
+        credentials: BenchmarkCredentials::default(),
+        global_headers: std::collections::HashMap::new(),
         endpoints: vec![
-            EndpointConfig {
-                path: "/graphql".to_string(),
-                method: "POST".to_string(),
-                headers: {
-                    let mut headers = std::collections::HashMap::new();
-                    headers.insert("Content-Type".to_string(), "application/json".to_string());
-                    headers
-                },
-                body: Some(r#"{"query":"query { health }"}"#.to_string()),
-                weight: 0.3,
-            },
-            EndpointConfig {
-                path: "/graphql".to_string(),
-                method: "POST".to_string(),
-                headers: {
-                    let mut headers = std::collections::HashMap::new();
-                    headers.insert("Content-Type".to_string(), "application/json".to_string());
-                    headers
-                },
-                body: Some(r#"{"query":"query { products { id name price } }"}"#.to_string()),
-                weight: 0.4,
-            },
-            EndpointConfig {
-                path: "/graphql".to_string(),
-                method: "POST".to_string(),
-                headers: {
-                    let mut headers = std::collections::HashMap::new();
-                    headers.insert("Content-Type".to_string(), "application/json".to_string());
-                    headers
-                },
-                body: Some(r#"{"query":"query { users { id email name } }"}"#.to_string()),
-                weight: 0.3,
-            },
+            graphql(GraphQLOperation::new("query { health }"), 0.3),
+            graphql(GraphQLOperation::new("query { products { edges { node { id name price } } } }"), 0.3),
+            graphql(
+                GraphQLOperation::new("query Product($id: UUID!) { product(id: $id) { id name price } }")
+                    .with_variables(serde_json::json!({ "id": product_uuid_from_shopify_id(1) }))
+                    .with_operation_name("Product"),
+                0.2,
+            ),
+            graphql(GraphQLOperation::new("query { users { id email name } }"), 0.2),
         ],
     }
 }
@@ -325,6 +642,11 @@ fn create_mixed_config(base_url: &str, users: u32, duration: u64, ramp_up: u64)
         concurrent_users: users,
         duration_seconds: duration,
         ramp_up_seconds: ramp_up,
+        seed: None,
+        think_time: ThinkTime::None,
+        warmup_seconds: 0,
+        credentials: BenchmarkCredentials::default(),
+        global_headers: std::collections::HashMap::new(),
         endpoints: vec![
             EndpointConfig {
                 path: "/health".to_string(),
@@ -332,13 +654,17 @@ fn create_mixed_config(base_url: &str, users: u32, duration: u64, ramp_up: u64)
                 headers: std::collections::HashMap::new(),
                 body: None,
                 weight: 0.2,
+                requires_auth: false,
+                graphql: None,
             },
             EndpointConfig {
-                path: "/api/products".to_string(),
+                path: "/api/v1/products".to_string(),
                 method: "GET".to_string(),
                 headers: std::collections::HashMap::new(),
                 body: None,
                 weight: 0.3,
+                requires_auth: false,
+                graphql: None,
             },
             EndpointConfig {
                 path: "/graphql".to_string(),
@@ -348,8 +674,10 @@ fn create_mixed_config(base_url: &str, users: u32, duration: u64, ramp_up: u64)
                     headers.insert("Content-Type".to_string(), "application/json".to_string());
                     headers
                 },
-                body: Some(r#"{"query":"query { products { id name } }"}"#.to_string()),
+                body: Some(r#"{"query":"query { products { edges { node { id name } } } }"}"#.to_string()),
                 weight: 0.3,
+                requires_auth: false,
+                graphql: None,
             },
             EndpointConfig {
                 path: "/metrics".to_string(),
@@ -357,67 +685,59 @@ fn create_mixed_config(base_url: &str, users: u32, duration: u64, ramp_up: u64)
                 headers: std::collections::HashMap::new(),
                 body: None,
                 weight: 0.2,
+                requires_auth: false,
+                graphql: None,
             },
         ],
     }
 }
 
-async fn generate_report(format: String, output: Option<String>) -> anyhow::Result<()> {
-    info!("📊 Generating comparison report in {} format", format);
-
-    // This would typically load previous benchmark results from a database or file
-    // For demo purposes, we'll create a sample report
-    let mut comparison = FrameworkComparison::new();
-    
-    // Add sample AXUM results
-    comparison.add_axum_result(BenchmarkResult {
-        framework: "AXUM".to_string(),
-        test_name: "Health Check".to_string(),
-        requests_per_second: 15420.5,
-        average_response_time_ms: 6.2,
-        p95_response_time_ms: 12.8,
-        p99_response_time_ms: 25.4,
-        memory_usage_mb: 45.2,
-        cpu_usage_percent: 12.3,
-        timestamp: chrono::Utc::now(),
-    });
-
-    comparison.add_axum_result(BenchmarkResult {
-        framework: "AXUM".to_string(),
-        test_name: "REST API".to_string(),
-        requests_per_second: 8750.3,
-        average_response_time_ms: 11.4,
-        p95_response_time_ms: 28.6,
-        p99_response_time_ms: 45.2,
-        memory_usage_mb: 52.1,
-        cpu_usage_percent: 18.7,
-        timestamp: chrono::Utc::now(),
-    });
+// Endpoints that need a bearer token; `LoadTester` logs in once before the run
+fn create_authenticated_config(base_url: &str, users: u32, duration: u64, ramp_up: u64) -> BenchmarkConfig {
+    BenchmarkConfig {
+        target_url: base_url.to_string(),
+        concurrent_users: users,
+        duration_seconds: duration,
+        ramp_up_seconds: ramp_up,
+        seed: None,
+        think_time: ThinkTime::None,
+        warmup_seconds: 0,
+        credentials: BenchmarkCredentials::default(),
+        global_headers: std::collections::HashMap::new(),
+        endpoints: vec![
+            EndpointConfig {
+                path: "/api/v1/users/me".to_string(),
+                method: "GET".to_string(),
+                headers: std::collections::HashMap::new(),
+                body: None,
+                weight: 0.5,
+                requires_auth: true,
+                graphql: None,
+            },
+            EndpointConfig {
+                path: "/api/v1/orders".to_string(),
+                method: "GET".to_string(),
+                headers: std::collections::HashMap::new(),
+                body: None,
+                weight: 0.5,
+                requires_auth: true,
+                graphql: None,
+            },
+        ],
+    }
+}
 
-    // Add sample LOCO results
-    comparison.add_loco_result(BenchmarkResult {
-        framework: "LOCO".to_string(),
-        test_name: "Health Check".to_string(),
-        requests_per_second: 14850.2,
-        average_response_time_ms: 6.7,
-        p95_response_time_ms: 13.5,
-        p99_response_time_ms: 27.1,
-        memory_usage_mb: 42.8,
-        cpu_usage_percent: 10.5,
-        timestamp: chrono::Utc::now(),
-    });
+async fn generate_report(format: String, output: Option<String>, input: Option<String>) -> anyhow::Result<()> {
+    info!("📊 Generating comparison report in {} format", format);
 
-    comparison.add_loco_result(BenchmarkResult {
-        framework: "LOCO".to_string(),
-        test_name: "REST API".to_string(),
-        requests_per_second: 8420.7,
-        average_response_time_ms: 11.9,
-        p95_response_time_ms: 30.2,
-        p99_response_time_ms: 48.6,
-        memory_usage_mb: 48.5,
-        cpu_usage_percent: 16.2,
-        timestamp: chrono::Utc::now(),
-    });
+    let comparison_path = match input {
+        Some(path) => PathBuf::from(path),
+        None => latest_saved_comparison(Path::new(RESULTS_DIR))?.ok_or_else(|| {
+            anyhow::anyhow!("No saved results in {}; run `benchmarks compare` first", RESULTS_DIR)
+        })?,
+    };
+    info!("📂 Loading results from {}", comparison_path.display());
+    let comparison = FrameworkComparison::load_from_file(&comparison_path)?;
 
     let report = match format.as_str() {
         "markdown" | "md" => comparison.generate_comparison_report(),
@@ -425,10 +745,12 @@ async fn generate_report(format: String, output: Option<String>) -> anyhow::Resu
             serde_json::to_string_pretty(&serde_json::json!({
                 "axum_results": comparison.axum_results,
                 "loco_results": comparison.loco_results,
+                "build_info": comparison.build_info,
                 "generated_at": chrono::Utc::now()
             }))?
         }
         "html" => generate_html_report(&comparison),
+        "csv" => generate_csv_report(&comparison),
         _ => {
             error!("Unsupported format: {}", format);
             return Err(anyhow::anyhow!("Unsupported format"));
@@ -448,7 +770,30 @@ async fn generate_report(format: String, output: Option<String>) -> anyhow::Resu
     Ok(())
 }
 
-fn generate_html_report(_comparison: &FrameworkComparison) -> String {
+/// Finds the newest `comparison_*.json` in `dir`. The timestamped names sort
+/// chronologically, so the lexicographically greatest one is the latest.
+fn latest_saved_comparison(dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    let mut latest: Option<PathBuf> = None;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_comparison = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("comparison_") && name.ends_with(".json"));
+
+        if is_comparison && latest.as_ref().is_none_or(|current| path > *current) {
+            latest = Some(path);
+        }
+    }
+
+    Ok(latest)
+}
+
+fn generate_html_report(comparison: &FrameworkComparison) -> String {
     format!(
         r#"<!DOCTYPE html>
 <html>
@@ -466,44 +811,118 @@ fn generate_html_report(_comparison: &FrameworkComparison) -> String {
 <body>
     <h1>AXUM vs LOCO Performance Comparison</h1>
     <p>Generated at: {}</p>
+    <p>Build: <code>{}</code> (built {}, rustc {})</p>
     
     <h2>Summary</h2>
-    <table>
-        <tr>
-            <th>Framework</th>
-            <th>Avg RPS</th>
-            <th>Avg Response Time (ms)</th>
-            <th>P95 (ms)</th>
-            <th>P99 (ms)</th>
-        </tr>
-        <tr>
-            <td>AXUM</td>
-            <td>12,085.4</td>
-            <td>8.8</td>
-            <td>20.7</td>
-            <td>35.3</td>
-        </tr>
-        <tr>
-            <td>LOCO</td>
-            <td>11,635.5</td>
-            <td>9.3</td>
-            <td>21.9</td>
-            <td>37.9</td>
-        </tr>
-    </table>
-    
+{}
     <h2>Analysis</h2>
-    <p>🏆 <strong>AXUM wins in throughput</strong> by 3.9% (12,085.4 vs 11,635.5 req/s)</p>
-    <p>⚡ <strong>AXUM wins in response time</strong> by 5.4% (8.8ms vs 9.3ms)</p>
-    
+{}
     <h2>Detailed Results</h2>
     <p>See the full markdown report for detailed test results.</p>
+
+    <h2>Response Time Distribution</h2>
+{}
 </body>
 </html>"#,
-        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        comparison.build_info.git_sha,
+        comparison.build_info.build_timestamp,
+        comparison.build_info.rustc_version,
+        html_summary_table(comparison),
+        html_analysis(comparison),
+        html_histogram_tables(comparison)
     )
 }
 
+// The markdown report's summary table: each framework's average across its runs
+fn html_summary_table(comparison: &FrameworkComparison) -> String {
+    let mut html = String::from("    <table>\n        <tr>");
+    for (name, _) in SUMMARY_COLUMNS {
+        html.push_str(&format!("<th>{}</th>", name));
+    }
+    html.push_str("</tr>\n");
+
+    for (label, average) in comparison.summary() {
+        html.push_str(&format!("        <tr><td>{}</td>", label));
+        for value in summary_values(&average) {
+            html.push_str(&format!("<td>{:.2}</td>", value));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("    </table>\n");
+
+    html
+}
+
+fn html_analysis(comparison: &FrameworkComparison) -> String {
+    let notes: Vec<ReportNote> = comparison.success_rate_warnings().into_iter().chain(comparison.analysis()).collect();
+    if notes.is_empty() {
+        return "    <p>Both frameworks need results to compare them.</p>\n".to_string();
+    }
+
+    notes
+        .iter()
+        .map(|note| format!("    <p>{} <strong>{}</strong>{}</p>\n", note.icon, note.headline, note.detail))
+        .collect()
+}
+
+const CSV_HEADER: &str = "framework,test_name,requests_per_second,average_response_time_ms,p95_response_time_ms,p99_response_time_ms,memory_usage_mb,cpu_usage_percent,timestamp";
+
+fn generate_csv_report(comparison: &FrameworkComparison) -> String {
+    let mut csv = String::new();
+    csv.push_str(CSV_HEADER);
+    csv.push('\n');
+
+    for result in comparison.axum_results.iter().chain(&comparison.loco_results) {
+        let row = [
+            csv_field(&result.framework),
+            csv_field(&result.test_name),
+            format!("{:.2}", result.requests_per_second),
+            format!("{:.2}", result.average_response_time_ms),
+            format!("{:.2}", result.p95_response_time_ms),
+            format!("{:.2}", result.p99_response_time_ms),
+            format!("{:.2}", result.memory_usage_mb),
+            format!("{:.2}", result.cpu_usage_percent),
+            result.timestamp.to_rfc3339(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+// Quotes a field when it contains a delimiter, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_histogram_tables(comparison: &FrameworkComparison) -> String {
+    let mut html = String::new();
+
+    for result in comparison.axum_results.iter().chain(&comparison.loco_results) {
+        if result.response_time_histogram.is_empty() {
+            continue;
+        }
+
+        html.push_str(&format!("    <h3>{} - {}</h3>\n", result.framework, result.test_name));
+        html.push_str("    <table>\n        <tr><th>Response Time (ms)</th><th>Requests</th></tr>\n");
+        for bucket in &result.response_time_histogram {
+            html.push_str(&format!(
+                "        <tr><td>{:.2} - {:.2}</td><td>{}</td></tr>\n",
+                bucket.low_ms, bucket.high_ms, bucket.count
+            ));
+        }
+        html.push_str("    </table>\n");
+    }
+
+    html
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -519,20 +938,211 @@ mod tests {
         assert_eq!(config.endpoints[0].path, "/health");
     }
 
+    #[test]
+    fn test_sla_violations() {
+        let mut result = BenchmarkMetrics::new("AXUM".to_string()).to_benchmark_result("REST API".to_string(), None);
+        result.requests_per_second = 950.0;
+        result.p99_response_time_ms = 42.0;
+
+        assert!(!Sla::default().is_set());
+        assert!(Sla::default().violations(&result).is_empty());
+        let passing = Sla { max_p99_ms: Some(50.0), min_rps: Some(900.0) };
+        assert!(passing.violations(&result).is_empty());
+
+        let failing = Sla { max_p99_ms: Some(40.0), min_rps: Some(1000.0) };
+        let violations = failing.violations(&result);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0], "REST API: P99 response time 42.00ms is above the 40.00ms limit");
+        assert_eq!(violations[1], "REST API: 950.00 req/s is below the 1000.00 req/s minimum");
+
+        let p99_only = Sla { max_p99_ms: Some(40.0), min_rps: None };
+        assert_eq!(p99_only.violations(&result).len(), 1);
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("http://localhost:3000"), "ws://localhost:3000");
+        assert_eq!(websocket_url("https://bench.example.com"), "wss://bench.example.com");
+    }
+
+    #[test]
+    fn test_in_process_query_forwards_load() {
+        assert_eq!(in_process_query(25, 10, 2), "in_process=true&users=25&duration=10&ramp_up=2");
+    }
+
     #[test]
     fn test_rest_config() {
         let config = create_rest_config("http://localhost:3000", 50, 30, 5);
         assert_eq!(config.endpoints.len(), 3);
-        assert!(config.endpoints.iter().any(|e| e.path == "/api/products" && e.method == "GET"));
-        assert!(config.endpoints.iter().any(|e| e.path == "/api/products" && e.method == "POST"));
-        assert!(config.endpoints.iter().any(|e| e.path == "/api/auth/login"));
+        assert!(config.endpoints.iter().any(|e| e.path == "/api/v1/products" && e.method == "GET"));
+        assert!(config.endpoints.iter().any(|e| e.path == "/api/v1/products" && e.method == "POST"));
+        assert!(config.endpoints.iter().any(|e| e.path == "/api/v1/auth/login"));
     }
 
     #[test]
     fn test_graphql_config() {
         let config = create_graphql_config("http://localhost:3000", 75, 45, 8);
-        assert_eq!(config.endpoints.len(), 3);
+        assert_eq!(config.endpoints.len(), 4);
         assert!(config.endpoints.iter().all(|e| e.path == "/graphql" && e.method == "POST"));
-        assert!(config.endpoints.iter().all(|e| e.body.is_some()));
+        assert!(config.endpoints.iter().all(|e| e.graphql.is_some()));
+
+        let by_id = config
+            .endpoints
+            .iter()
+            .filter_map(|e| e.graphql.as_ref())
+            .find(|operation| operation.operation_name.as_deref() == Some("Product"))
+            .unwrap();
+        assert_eq!(by_id.request_body()["variables"]["id"], "00000000-0000-0000-0000-000000000001");
+    }
+
+    // Minimal RFC 4180 line parser, enough to read back what `generate_csv_report` writes
+    fn parse_csv_line(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        fields.push(field);
+
+        fields
+    }
+
+    fn sample_result(framework: &str, test_name: &str) -> BenchmarkResult {
+        BenchmarkResult {
+            framework: framework.to_string(),
+            test_name: test_name.to_string(),
+            requests_per_second: 8750.3,
+            average_response_time_ms: 11.4,
+            min_response_time_ms: 2.3,
+            max_response_time_ms: 71.5,
+            stddev_response_time_ms: 8.2,
+            p95_response_time_ms: 28.6,
+            p99_response_time_ms: 45.2,
+            memory_usage_mb: 52.1,
+            cpu_usage_percent: 18.7,
+            response_time_histogram: vec![],
+            endpoint_stats: Default::default(),
+            top_errors: vec![],
+            success_rate: 100.0,
+            error_counts: Default::default(),
+            interrupted: false,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_csv_report() {
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(sample_result("AXUM", "REST API, \"cached\""));
+
+        let csv = generate_csv_report(&comparison);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], CSV_HEADER);
+
+        let row = parse_csv_line(lines[1]);
+        assert_eq!(row.len(), 9);
+        assert_eq!(row[0], "AXUM");
+        assert_eq!(row[1], "REST API, \"cached\"");
+        assert_eq!(row[2], "8750.30");
+        assert_eq!(row[5], "45.20");
+        assert!(chrono::DateTime::parse_from_rfc3339(&row[8]).is_ok());
+    }
+
+    #[test]
+    fn test_html_report_uses_loaded_results() {
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(sample_result("AXUM", "REST API"));
+        comparison.add_loco_result(BenchmarkResult {
+            requests_per_second: 9120.5,
+            average_response_time_ms: 10.9,
+            ..sample_result("LOCO", "REST API")
+        });
+
+        let html = generate_html_report(&comparison);
+        assert!(html.contains("<tr><td>AXUM</td><td>8750.30</td><td>11.40</td>"), "{}", html);
+        assert!(html.contains("<tr><td>LOCO</td><td>9120.50</td><td>10.90</td>"), "{}", html);
+        assert!(html.contains("<strong>LOCO wins in throughput</strong> by 4.2% (9120.50 vs 8750.30 req/s)"), "{}", html);
+        assert!(!html.contains("12,085.4"));
+    }
+
+    // Answers every request with 200 except those to `failing_path`, and issues a token on login
+    async fn spawn_mock_server(failing_path: Option<&str>) -> wiremock::MockServer {
+        use wiremock::{matchers::{any, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        if let Some(failing_path) = failing_path {
+            Mock::given(path(failing_path)).respond_with(ResponseTemplate::new(500)).mount(&server).await;
+        }
+
+        let login = ApiResponse::success(AuthResponse {
+            token: "benchmark-token".to_string(),
+            refresh_token: "refresh-token".to_string(),
+            user: User {
+                id: uuid::Uuid::new_v4(),
+                email: "benchmark@example.com".to_string(),
+                name: "Benchmark User".to_string(),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            },
+        });
+        Mock::given(path(LOGIN_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&login))
+            .mount(&server)
+            .await;
+        Mock::given(any()).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_sends_one_request_per_endpoint() {
+        let server = spawn_mock_server(None).await;
+
+        assert_eq!(dry_run_framework(&server.uri(), "AXUM", 10, 60, 5).await, 0);
+
+        // Plus the login for the authenticated scenario, and registering the
+        // benchmark account before the REST and authenticated scenarios
+        let endpoints: usize = benchmark_scenarios(&server.uri(), 10, 60, 5)
+            .iter()
+            .map(|(_, config)| config.endpoints.len())
+            .sum();
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), endpoints + 3);
+        assert_eq!(requests.iter().filter(|request| request.url.path() == REGISTER_PATH).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_fails_when_an_endpoint_fails() {
+        let server = spawn_mock_server(Some("/metrics")).await;
+
+        assert_eq!(dry_run_framework(&server.uri(), "AXUM", 10, 60, 5).await, 1);
+        assert!(dry_run(&[("AXUM", server.uri().as_str())], 10, 60, 5).await.is_err());
+    }
+
+    #[test]
+    fn test_latest_saved_comparison() {
+        let dir = std::env::temp_dir().join(format!("benchmark_results_{}", uuid::Uuid::new_v4()));
+        assert!(latest_saved_comparison(&dir).unwrap().is_none());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["comparison_20240101_120000.json", "comparison_20240102_090000.json", "notes.txt"] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        let latest = latest_saved_comparison(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(latest, Some(dir.join("comparison_20240102_090000.json")));
     }
 }
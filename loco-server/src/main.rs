@@ -1,20 +1,25 @@
 use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Html, Json},
+    body::Body,
+    error_handling::HandleErrorLayer,
+    extract::{ws::WebSocketUpgrade, FromRef, OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{Html, IntoResponse, Json},
     routing::{get, post},
     Router,
 };
-use std::{collections::HashMap, sync::Arc, time::Instant};
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
+use std::{collections::HashMap, sync::Arc, time::{Duration, Instant}};
+use tokio::{net::TcpListener, sync::broadcast};
+use tower::{limit::GlobalConcurrencyLimitLayer, load_shed::LoadShedLayer, ServiceBuilder};
 use tower_http::{
     cors::CorsLayer,
-    trace::TraceLayer,
-    compression::CompressionLayer,
+    limit::RequestBodyLimitLayer,
+    timeout::TimeoutLayer,
 };
 use tracing::{info, warn};
 use uuid::Uuid;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use shared::{
     models::*,
@@ -22,52 +27,218 @@ use shared::{
     shopify::*,
     graphql::*,
     benchmarks::*,
+    system_metrics::*,
+    errors::*,
+    request_id::*,
+    shutdown::*,
+    logging::*,
+    idempotency::*,
+    check_cache::*,
+    bind_address::*,
+    response_format::*,
+    rate_limit::*,
+    version::*,
+    circuit_breaker::*,
+    json_body::*,
+    concurrency_limit::*,
+    pagination_headers::*,
+    compression::*,
+    server_config::*,
+    tls::*,
+    users::*,
+    slow_request::*,
+    api_version::*,
+    http_metrics::*,
+    api_doc::*,
 };
 
+// Used when the PORT environment variable isn't set
+const DEFAULT_PORT: u16 = 5150;
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+// How long in-flight requests get to finish after a shutdown signal
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+// Larger request bodies are rejected with 413 before reaching a handler
+const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+// Requests still running after this are answered with 408
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// How long a create request's result is replayed for a repeated Idempotency-Key
+const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+// Health and readiness results are reused for this long, so probes under load
+// don't hammer dependencies
+const DEFAULT_HEALTH_CACHE_TTL: Duration = Duration::from_secs(5);
+// Products fetched from Shopify per page while streaming the catalog
+const PRODUCT_STREAM_PAGE_SIZE: u32 = 50;
+
 // LOCO-style Application State
 #[derive(Clone)]
 pub struct AppState {
     pub auth_service: Arc<AuthService>,
-    pub shopify_client: Arc<MockShopifyClient>,
+    // The mock or the real Shopify API, chosen by SHOPIFY_BACKEND, behind shopify_breaker
+    pub shopify_client: Arc<dyn ShopifyBackend>,
+    pub shopify_breaker: Arc<CircuitBreaker>,
+    // Also create new orders in Shopify, set by SHOPIFY_SYNC_ORDERS
+    pub sync_orders_to_shopify: bool,
+    // Real Shopify client, built once so its connection pool is shared
+    pub shopify_api: Arc<ShopifyClient>,
+    pub login_rate_limiter: Arc<RateLimiter>,
+    // Registered users; in memory, so they're gone after a restart
+    pub users: Arc<dyn UserRepository>,
+    pub webhook_queue: WebhookQueue,
     pub graphql_schema: AppSchema,
+    pub prometheus: PrometheusHandle,
+    pub request_stats: Arc<RequestStats>,
+    pub product_idempotency: Arc<IdempotencyCache<Product>>,
+    pub health_cache: Arc<CheckCache<HealthCheck>>,
+    pub readiness_cache: Arc<CheckCache<ReadinessCheck>>,
+    pub order_events: broadcast::Sender<Order>,
+    pub token_expiry_hours: i64,
+    pub max_body_bytes: usize,
+    pub request_timeout: Duration,
+    // Requests slower than this are logged, set by `slow_request_threshold_ms`
+    pub slow_request_threshold: Duration,
+    // Per-client request throttling, off unless RATE_LIMIT_RPS is set
+    pub rate_limiter: Option<Arc<IpRateLimiter>>,
+    // Requests processed at once; more than this are shed with a 503
+    pub max_concurrent_requests: usize,
+    // Allowed origins from the config; any origin when none are listed
+    pub cors: CorsLayer,
+    // Response compression, set by COMPRESSION_ALGORITHMS, COMPRESSION_LEVEL and COMPRESSION_MIN_SIZE
+    pub compression: CompressionConfig,
+    // Where the network-mode self-benchmark sends its requests
+    pub self_url: String,
     pub start_time: Instant,
 }
 
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromRef<AppState> for Arc<AuthService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth_service.clone()
+    }
+}
+
 impl AppState {
     pub fn new() -> Self {
-        let auth_config = AuthConfig::default();
-        let auth_service = Arc::new(AuthService::new(auth_config.jwt_secret));
-        let shopify_client = Arc::new(MockShopifyClient::new());
-        let graphql_schema = create_schema();
+        Self::from_config(&ServerConfig::new(DEFAULT_PORT)).expect("the default config is valid")
+    }
+
+    pub fn from_config(config: &ServerConfig) -> Result<Self, AuthError> {
+        Self::build(config, config.auth_config())
+    }
+
+    pub fn with_auth_config(auth_config: AuthConfig) -> Result<Self, AuthError> {
+        Self::build(&ServerConfig::new(DEFAULT_PORT), auth_config)
+    }
+
+    fn build(config: &ServerConfig, auth_config: AuthConfig) -> Result<Self, AuthError> {
+        let token_expiry_hours = auth_config.token_expiry_hours;
+        let auth_service = Arc::new(AuthService::with_config(auth_config)?);
+        let shopify_api = Arc::new(ShopifyClient::new(config.shopify_config()));
+        let shopify_breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig::default()));
+        let shopify_client: Arc<dyn ShopifyBackend> = Arc::new(CircuitBreakerBackend::new(
+            config.shopify_backend(shopify_api.clone()),
+            shopify_breaker.clone(),
+        ));
+        let login_rate_limiter = Arc::new(RateLimiter::new(5, 15));
+        let users: Arc<dyn UserRepository> = Arc::new(InMemoryUserRepository::new(auth_service.clone()));
+        let graphql_schema = create_schema_with_config(SchemaConfig::from_env());
 
-        Self {
+        // Nothing handles this queue, so webhooks get a 503 until `main` swaps
+        // in one with a `WebhookWorker` behind it
+        let (webhook_queue, _) = WebhookQueue::new(WEBHOOK_QUEUE_CAPACITY);
+
+        Ok(Self {
             auth_service,
             shopify_client,
+            shopify_breaker,
+            sync_orders_to_shopify: config.shopify.sync_orders,
+            shopify_api,
+            login_rate_limiter,
+            users,
+            webhook_queue,
             graphql_schema,
+            order_events: order_events_channel(),
+            prometheus: prometheus_handle(),
+            request_stats: Arc::new(RequestStats::default()),
+            product_idempotency: Arc::new(IdempotencyCache::new(DEFAULT_IDEMPOTENCY_TTL)),
+            health_cache: Arc::new(CheckCache::new(DEFAULT_HEALTH_CACHE_TTL)),
+            readiness_cache: Arc::new(CheckCache::new(DEFAULT_HEALTH_CACHE_TTL)),
+            token_expiry_hours,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            slow_request_threshold: config.slow_request_threshold(),
+            rate_limiter: config.ip_rate_limiter(),
+            cors: config.cors_layer(),
+            max_concurrent_requests: max_concurrent_requests_from_env(),
+            compression: CompressionConfig::from_env(),
+            self_url: config.self_url(),
             start_time: Instant::now(),
-        }
+        })
     }
 }
 
 // LOCO-style Controllers
 pub mod controllers {
     use super::*;
-    use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+    use async_graphql::http::ALL_WEBSOCKET_PROTOCOLS;
+    use async_graphql_axum::{GraphQLBatchRequest, GraphQLProtocol, GraphQLResponse, GraphQLWebSocket};
 
     // Health Controller
     pub mod health {
         use super::*;
 
         pub async fn health_check(State(state): State<AppState>) -> Json<HealthCheck> {
-            Json(HealthCheck {
-                status: "healthy".to_string(),
-                framework: "LOCO-style".to_string(),
-                version: "0.1.0".to_string(),
-                uptime_seconds: state.start_time.elapsed().as_secs(),
-                database_connected: true, // Mock
-                shopify_connected: true,  // Mock
-                timestamp: chrono::Utc::now(),
-            })
+            let health = state
+                .health_cache
+                .get_or_refresh(|| async {
+                    HealthCheck {
+                        status: "healthy".to_string(),
+                        framework: "LOCO-style".to_string(),
+                        version: "0.1.0".to_string(),
+                        uptime_seconds: state.start_time.elapsed().as_secs(),
+                        database_connected: true, // Mock
+                        shopify_connected: true,  // Mock
+                        timestamp: chrono::Utc::now(),
+                    }
+                })
+                .await;
+
+            Json(health)
+        }
+
+        // Build details; static, so cheap enough to leave unauthenticated
+        pub async fn version() -> Json<VersionInfo> {
+            Json(VersionInfo::new("LOCO-style", env!("CARGO_PKG_VERSION")))
+        }
+
+        // Readiness probe: unlike `/health`, this actually exercises dependencies
+        pub async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<ReadinessCheck>) {
+            let readiness = state.readiness_cache.get_or_refresh(|| check_readiness(&state)).await;
+            let status = if readiness.is_ready() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+
+            (status, Json(readiness))
+        }
+
+        async fn check_readiness(state: &AppState) -> ReadinessCheck {
+            let shopify_status = match tokio::time::timeout(READINESS_TIMEOUT, state.shopify_client.get_products()).await {
+                Ok(Ok(_)) => "ok".to_string(),
+                Ok(Err(e)) => {
+                    warn!("Readiness check failed for Shopify: {}", e);
+                    format!("error: {}", e)
+                }
+                Err(_) => "timeout".to_string(),
+            };
+
+            let checks = HashMap::from([("shopify".to_string(), shopify_status)]);
+            ReadinessCheck::new("LOCO-style", checks)
         }
     }
 
@@ -75,55 +246,173 @@ pub mod controllers {
     pub mod products {
         use super::*;
 
-        pub async fn get_products(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<Product>>>, StatusCode> {
-            match state.shopify_client.get_products().await {
-                Ok(shopify_products) => {
-                    let products: Vec<Product> = shopify_products
-                        .into_iter()
-                        .map(|sp| Product {
-                            id: Uuid::new_v4(),
-                            name: sp.title,
-                            description: sp.body_html,
-                            price: 99.99, // Mock price
-                            shopify_id: sp.id.map(|id| id.to_string()),
-                            created_at: sp.created_at.unwrap_or_else(chrono::Utc::now),
-                            updated_at: sp.updated_at.unwrap_or_else(chrono::Utc::now),
-                        })
-                        .collect();
+        #[utoipa::path(
+            get,
+            path = "/api/v1/products",
+            tag = "products",
+            params(ProductQuery, PaginationParams),
+            responses(
+                (status = 200, description = "Filtered, sorted page of products", body = ApiResponse<PaginatedResponse<Product>>, headers(
+                    ("X-Total-Count" = u64, description = "Number of products matching the filters"),
+                    ("X-Page" = u32, description = "The page returned"),
+                    ("Link" = String, description = "URLs of the next, previous, first and last pages"),
+                )),
+                (status = 400, description = "Invalid query parameters"),
+            )
+        )]
+        pub async fn get_products(
+            format: ResponseFormat,
+            OriginalUri(uri): OriginalUri,
+            Query(filter): Query<ProductQuery>,
+            Query(pagination): Query<PaginationParams>,
+            State(state): State<AppState>,
+        ) -> Result<(HeaderMap, Negotiated<ApiResponse<PaginatedResponse<Product>>>), AppError> {
+            let shopify_products = state
+                .shopify_client
+                .get_products()
+                .await
+                .inspect_err(|e| warn!("Failed to fetch products: {}", e))?;
 
-                    Ok(Json(ApiResponse::success(products)))
-                }
-                Err(e) => {
-                    warn!("Failed to fetch products: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
+            let products: Vec<Product> = shopify_products
+                .into_iter()
+                .map(product_from_shopify)
+                .collect();
+            let products = filter.apply(products);
+            let page = PaginatedResponse::from_all(products, &pagination);
+
+            // The original URI, since nesting strips the /api/v1 prefix from `Uri`
+            Ok((pagination_headers(&uri, &page), format.respond(ApiResponse::success(page))))
+        }
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/products/stream",
+            tag = "products",
+            responses(
+                (status = 200, description = "Every product, one JSON object per line", body = Product, content_type = "application/x-ndjson"),
+            )
+        )]
+        pub async fn stream_products(State(state): State<AppState>) -> impl IntoResponse {
+            // Pages are fetched as the client reads, so the catalog is never held in memory
+            let body = Body::from_stream(products_ndjson(state.shopify_client.clone(), PRODUCT_STREAM_PAGE_SIZE));
+
+            ([(header::CONTENT_TYPE, "application/x-ndjson")], body)
         }
 
+        #[utoipa::path(
+            get,
+            path = "/api/v1/products/{id}",
+            tag = "products",
+            params(("id" = Uuid, Path, description = "Product ID")),
+            responses(
+                (status = 200, description = "The product", body = ApiResponse<Product>),
+                (status = 404, description = "Product not found"),
+            )
+        )]
         pub async fn get_product(
+            format: ResponseFormat,
             Path(id): Path<Uuid>,
-            State(_state): State<AppState>,
-        ) -> Result<Json<ApiResponse<Product>>, StatusCode> {
-            // Mock product lookup
-            let product = Product {
-                id,
-                name: "LOCO-style Product".to_string(),
-                description: Some("Product fetched via LOCO-style implementation".to_string()),
-                price: 149.99,
-                shopify_id: Some("loco_style_1".to_string()),
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
-            };
+            State(state): State<AppState>,
+        ) -> Result<Negotiated<ApiResponse<Product>>, AppError> {
+            let shopify_id = shopify_id_from_product_uuid(id).ok_or(ShopifyError::ProductNotFound)?;
+            let shopify_product = state.shopify_client.get_product(shopify_id).await?;
 
-            Ok(Json(ApiResponse::success(product)))
+            Ok(format.respond(ApiResponse::success(product_from_shopify(shopify_product))))
         }
 
+        #[utoipa::path(
+            post,
+            path = "/api/v1/products",
+            tag = "products",
+            request_body = CreateProductInput,
+            params(("Idempotency-Key" = Option<String>, Header, description = "Replays the original product for a repeated key")),
+            responses(
+                (status = 200, description = "The created product", body = ApiResponse<Product>),
+                (status = 400, description = "Validation failed"),
+            )
+        )]
         pub async fn create_product(
+            format: ResponseFormat,
+            headers: HeaderMap,
+            State(state): State<AppState>,
+            JsonBody(input): JsonBody<CreateProductInput>,
+        ) -> Result<Negotiated<ApiResponse<Product>>, AppError> {
+            if let Err(errors) = input.validate() {
+                return Err(AppError::Validation(errors.join(", ")));
+            }
+
+            // A retry of a request we've already handled gets the original product back,
+            // waiting for it if the original is still being created
+            let idempotency_key = headers
+                .get(IDEMPOTENCY_KEY_HEADER)
+                .and_then(|value| value.to_str().ok());
+            let reservation = match idempotency_key {
+                Some(key) => match state.product_idempotency.reserve(key).await {
+                    Idempotent::Cached(product) => return Ok(format.respond(ApiResponse::success(product))),
+                    Idempotent::Reserved(reservation) => Some(reservation),
+                },
+                None => None,
+            };
+
+            let created_product = state
+                .shopify_client
+                .create_product(&shopify_product_from_input(&input))
+                .await
+                .inspect_err(|e| warn!("Failed to create product: {}", e))?;
+
+            let product = product_from_shopify(created_product);
+
+            if let Some(reservation) = reservation {
+                reservation.complete(product.clone());
+            }
+
+            Ok(format.respond(ApiResponse::success(product)))
+        }
+
+        /// Creates every valid input in one Shopify call. Invalid inputs are reported
+        /// per item instead of failing the whole batch.
+        #[utoipa::path(
+            post,
+            path = "/api/v1/products/bulk",
+            tag = "products",
+            request_body = Vec<CreateProductInput>,
+            responses(
+                (status = 200, description = "Per-item results, in input order", body = ApiResponse<BulkResponse<Product>>),
+            )
+        )]
+        pub async fn create_products_bulk(
+            format: ResponseFormat,
             State(state): State<AppState>,
-            Json(input): Json<CreateProductInput>,
-        ) -> Result<Json<ApiResponse<Product>>, StatusCode> {
-            // Create Shopify product
-            let shopify_product = ShopifyProduct {
+            JsonBody(inputs): JsonBody<Vec<CreateProductInput>>,
+        ) -> Result<Negotiated<ApiResponse<BulkResponse<Product>>>, AppError> {
+            let mut results = Vec::with_capacity(inputs.len());
+            let mut valid = Vec::new();
+
+            for (index, input) in inputs.into_iter().enumerate() {
+                match input.validate() {
+                    Ok(()) => valid.push((index, input)),
+                    Err(errors) => results.push(BulkItemResult::failure(index, errors.join(", "))),
+                }
+            }
+
+            if !valid.is_empty() {
+                let shopify_products = valid.iter().map(|(_, input)| shopify_product_from_input(input)).collect();
+                let created_products = state
+                    .shopify_client
+                    .create_products(shopify_products)
+                    .await
+                    .inspect_err(|e| warn!("Failed to create products: {}", e))?;
+
+                for ((index, _), created_product) in valid.into_iter().zip(created_products) {
+                    results.push(BulkItemResult::success(index, product_from_shopify(created_product)));
+                }
+            }
+
+            Ok(format.respond(ApiResponse::success(BulkResponse::new(results))))
+        }
+
+        fn shopify_product_from_input(input: &CreateProductInput) -> ShopifyProduct {
+            ShopifyProduct {
                 id: None,
                 title: input.name.clone(),
                 body_html: input.description.clone(),
@@ -137,30 +426,67 @@ pub mod controllers {
                 published_scope: "web".to_string(),
                 tags: "loco,demo".to_string(),
                 admin_graphql_api_id: None,
-                variants: vec![],
+                variants: vec![ShopifyVariant::priced(input.price)],
                 options: vec![],
                 images: vec![],
-            };
+            }
+        }
 
-            match state.shopify_client.create_product(&shopify_product).await {
-                Ok(created_product) => {
-                    let product = Product {
-                        id: Uuid::new_v4(),
-                        name: input.name,
-                        description: input.description,
-                        price: input.price,
-                        shopify_id: created_product.id.map(|id| id.to_string()),
-                        created_at: chrono::Utc::now(),
-                        updated_at: chrono::Utc::now(),
-                    };
-
-                    Ok(Json(ApiResponse::success(product)))
-                }
-                Err(e) => {
-                    warn!("Failed to create product: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
+        #[utoipa::path(
+            put,
+            path = "/api/v1/products/{id}",
+            tag = "products",
+            request_body = UpdateProductInput,
+            params(("id" = Uuid, Path, description = "Product ID")),
+            responses(
+                (status = 200, description = "The updated product", body = ApiResponse<Product>),
+                (status = 400, description = "A price was given for a product without variants"),
+                (status = 404, description = "Product not found"),
+            )
+        )]
+        pub async fn update_product(
+            format: ResponseFormat,
+            Path(id): Path<Uuid>,
+            State(state): State<AppState>,
+            JsonBody(input): JsonBody<UpdateProductInput>,
+        ) -> Result<Negotiated<ApiResponse<Product>>, AppError> {
+            let shopify_id = shopify_id_from_product_uuid(id).ok_or(ShopifyError::ProductNotFound)?;
+
+            let mut shopify_product = state.shopify_client.get_product(shopify_id).await?;
+
+            // Nothing to change, so skip the update call entirely
+            if !input.is_empty() {
+                shopify_product.apply_update(&input).map_err(AppError::Validation)?;
+                shopify_product = state
+                    .shopify_client
+                    .update_product(shopify_id, &shopify_product)
+                    .await
+                    .inspect_err(|e| warn!("Failed to update product: {}", e))?;
             }
+
+            Ok(format.respond(ApiResponse::success(product_from_shopify(shopify_product))))
+        }
+
+        #[utoipa::path(
+            delete,
+            path = "/api/v1/products/{id}",
+            tag = "products",
+            params(("id" = Uuid, Path, description = "Product ID")),
+            responses(
+                (status = 200, description = "The product was deleted", body = ApiResponse<bool>),
+                (status = 404, description = "Product not found"),
+            )
+        )]
+        pub async fn delete_product(
+            format: ResponseFormat,
+            Path(id): Path<Uuid>,
+            State(state): State<AppState>,
+        ) -> Result<Negotiated<ApiResponse<bool>>, AppError> {
+            let shopify_id = shopify_id_from_product_uuid(id).ok_or(ShopifyError::ProductNotFound)?;
+
+            state.shopify_client.delete_product(shopify_id).await?;
+
+            Ok(format.respond(ApiResponse::success(true)))
         }
     }
 
@@ -168,108 +494,268 @@ pub mod controllers {
     pub mod auth {
         use super::*;
 
+        #[utoipa::path(
+            post,
+            path = "/api/v1/auth/register",
+            tag = "auth",
+            request_body = CreateUserInput,
+            responses(
+                (status = 200, description = "The new user and their tokens", body = ApiResponse<AuthResponse>),
+                (status = 400, description = "Validation failed"),
+            )
+        )]
         pub async fn register(
             State(state): State<AppState>,
-            Json(input): Json<CreateUserInput>,
-        ) -> Result<Json<ApiResponse<AuthResponse>>, StatusCode> {
-            // Validate password
-            if let Err(errors) = PasswordValidator::validate(&input.password) {
-                return Ok(Json(ApiResponse::error(format!(
-                    "Password validation failed: {}",
+            JsonBody(input): JsonBody<CreateUserInput>,
+        ) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
+            // Validate email and password
+            if let Err(errors) = input.validate() {
+                return Err(AppError::Validation(format!(
+                    "Validation failed: {}",
                     errors.join(", ")
-                ))));
+                )));
             }
 
-            // Hash password
-            let _password_hash = match state.auth_service.hash_password(&input.password) {
-                Ok(hash) => hash,
-                Err(e) => {
-                    warn!("Password hashing failed: {}", e);
-                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                }
-            };
-
-            // Create user (mock implementation)
-            let user_id = Uuid::new_v4();
-            let user = User {
-                id: user_id,
-                email: input.email.clone(),
-                name: input.name.clone(),
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
-            };
+            let user = state.users.create(&input).await?;
 
             // Generate JWT token
-            let claims = Claims::new(user_id, input.email, input.name, 24);
-            match state.auth_service.generate_token(&claims) {
-                Ok(token) => {
-                    let auth_response = AuthResponse { token, user };
-                    Ok(Json(ApiResponse::success(auth_response)))
-                }
-                Err(e) => {
-                    warn!("Token generation failed: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
+            let claims = Claims::new(user.id, user.email.clone(), user.name.clone(), state.token_expiry_hours);
+            let token = state.auth_service.generate_token(&claims)?;
+            let refresh_token = state.auth_service.generate_refresh_token(&claims)?;
+
+            let auth_response = AuthResponse { token, refresh_token, user };
+            Ok(Json(ApiResponse::success(auth_response)))
         }
 
+        #[utoipa::path(
+            post,
+            path = "/api/v1/auth/login",
+            tag = "auth",
+            request_body = LoginInput,
+            responses(
+                (status = 200, description = "The user and their tokens", body = ApiResponse<AuthResponse>),
+                (status = 401, description = "Invalid credentials"),
+                (status = 429, description = "Too many failed attempts"),
+            )
+        )]
         pub async fn login(
             State(state): State<AppState>,
-            Json(input): Json<LoginInput>,
-        ) -> Result<Json<ApiResponse<AuthResponse>>, StatusCode> {
-            // Mock user lookup and password verification
-            let user_id = Uuid::new_v4();
-            let user = User {
-                id: user_id,
-                email: input.email.clone(),
-                name: "LOCO-style User".to_string(),
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
+            JsonBody(input): JsonBody<LoginInput>,
+        ) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
+            // A locked account is turned away before its password is looked at
+            state.auth_service.check_login_allowed(&input.email)?;
+            // Keyed like the lockout, so other spellings of the email share its attempts
+            let rate_limit_key = email_key(&input.email);
+            if !state.login_rate_limiter.check_rate_limit(&rate_limit_key) {
+                warn!("Too many failed login attempts for {}", input.email);
+                return Err(AppError::TooManyRequests);
+            }
+
+            let user = match state.users.verify_credentials(&input.email, &input.password).await {
+                Err(AuthError::InvalidCredentials) => {
+                    state.login_rate_limiter.record_attempt(&rate_limit_key);
+                    state.auth_service.record_failed_login(&input.email);
+                    return Err(AuthError::InvalidCredentials.into());
+                }
+                result => result?,
             };
 
             // Generate JWT token
-            let claims = Claims::new(user_id, input.email, "LOCO-style User".to_string(), 24);
-            match state.auth_service.generate_token(&claims) {
-                Ok(token) => {
-                    let auth_response = AuthResponse { token, user };
-                    Ok(Json(ApiResponse::success(auth_response)))
-                }
-                Err(e) => {
-                    warn!("Token generation failed: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
+            let claims = Claims::new(user.id, user.email.clone(), user.name.clone(), state.token_expiry_hours);
+            let token = state.auth_service.generate_token(&claims)?;
+            let refresh_token = state.auth_service.generate_refresh_token(&claims)?;
+
+            state.login_rate_limiter.reset(&rate_limit_key);
+            state.auth_service.record_successful_login(&input.email);
+            let auth_response = AuthResponse { token, refresh_token, user };
+            Ok(Json(ApiResponse::success(auth_response)))
+        }
+
+        #[utoipa::path(
+            post,
+            path = "/api/v1/auth/refresh",
+            tag = "auth",
+            request_body = RefreshTokenInput,
+            responses(
+                (status = 200, description = "A new token pair", body = ApiResponse<RefreshTokenResponse>),
+                (status = 401, description = "Invalid refresh token"),
+            )
+        )]
+        pub async fn refresh_token(
+            State(state): State<AppState>,
+            JsonBody(input): JsonBody<RefreshTokenInput>,
+        ) -> Result<Json<ApiResponse<RefreshTokenResponse>>, AppError> {
+            let (token, refresh_token) = state
+                .auth_service
+                .refresh_access_token(&input.refresh_token)
+                .inspect_err(|e| warn!("Token refresh failed: {}", e))?;
+
+            let refresh_response = RefreshTokenResponse { token, refresh_token };
+            Ok(Json(ApiResponse::success(refresh_response)))
+        }
+
+        #[utoipa::path(
+            post,
+            path = "/api/v1/auth/logout",
+            tag = "auth",
+            responses(
+                (status = 200, description = "The token was revoked", body = ApiResponse<bool>),
+                (status = 401, description = "Missing or invalid bearer token"),
+            )
+        )]
+        pub async fn logout(
+            headers: HeaderMap,
+            State(state): State<AppState>,
+        ) -> Result<Json<ApiResponse<bool>>, AppError> {
+            let token = bearer_token(&headers).ok_or(AppError::Unauthorized)?;
+
+            let claims = state
+                .auth_service
+                .verify_token(token)
+                .inspect_err(|e| warn!("Logout with invalid token: {}", e))?;
+            state.auth_service.revoke(&claims);
+
+            Ok(Json(ApiResponse::success(true)))
         }
 
+        #[utoipa::path(
+            get,
+            path = "/api/v1/users/me",
+            tag = "auth",
+            responses(
+                (status = 200, description = "The authenticated user", body = ApiResponse<User>),
+                (status = 401, description = "Missing or invalid bearer token"),
+            )
+        )]
         pub async fn get_current_user(
             headers: HeaderMap,
             State(state): State<AppState>,
-        ) -> Result<Json<ApiResponse<User>>, StatusCode> {
+        ) -> Result<Json<ApiResponse<User>>, AppError> {
             // Extract user from headers
-            if let Some(auth_header) = headers.get("Authorization") {
-                if let Ok(auth_str) = auth_header.to_str() {
-                    if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                        match state.auth_service.verify_token(token) {
-                            Ok(claims) => {
-                                let user = User {
-                                    id: Uuid::parse_str(&claims.sub).unwrap_or_else(|_| Uuid::new_v4()),
-                                    email: claims.email,
-                                    name: claims.name,
-                                    created_at: chrono::Utc::now(),
-                                    updated_at: chrono::Utc::now(),
-                                };
-                                return Ok(Json(ApiResponse::success(user)));
-                            }
-                            Err(e) => {
-                                warn!("Token verification failed: {}", e);
-                                return Err(StatusCode::UNAUTHORIZED);
-                            }
-                        }
+            if let Some(token) = bearer_token(&headers) {
+                match state.auth_service.verify_token(token) {
+                    Ok(claims) => {
+                        // A subject that isn't a user ID means the token is bad
+                        let authenticated = AuthenticatedUser::from_claims(claims)?;
+                        let user = User {
+                            id: authenticated.id,
+                            email: authenticated.email,
+                            name: authenticated.name,
+                            created_at: chrono::Utc::now(),
+                            updated_at: chrono::Utc::now(),
+                        };
+                        return Ok(Json(ApiResponse::success(user)));
+                    }
+                    Err(e) => {
+                        warn!("Token verification failed: {}", e);
+                        return Err(e.into());
                     }
                 }
             }
 
-            Err(StatusCode::UNAUTHORIZED)
+            Err(AppError::Unauthorized)
+        }
+    }
+
+    // Orders Controller
+    pub mod orders {
+        use super::*;
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/orders",
+            tag = "orders",
+            responses(
+                (status = 200, description = "The caller's orders", body = ApiResponse<Vec<Order>>),
+                (status = 401, description = "Missing or invalid bearer token"),
+            )
+        )]
+        pub async fn list_orders(user: AuthenticatedUser) -> Json<ApiResponse<Vec<Order>>> {
+            Json(ApiResponse::success(mock_orders_for_user(user.id)))
+        }
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/orders/{id}",
+            tag = "orders",
+            params(("id" = Uuid, Path, description = "Order ID")),
+            responses(
+                (status = 200, description = "The order", body = ApiResponse<Order>),
+                (status = 401, description = "Missing or invalid bearer token"),
+            )
+        )]
+        pub async fn get_order(
+            user: AuthenticatedUser,
+            Path(id): Path<Uuid>,
+        ) -> Json<ApiResponse<Order>> {
+            // Mock order lookup
+            Json(ApiResponse::success(mock_order(id, user.id)))
+        }
+
+        #[utoipa::path(
+            post,
+            path = "/api/v1/orders",
+            tag = "orders",
+            request_body = CreateOrderInput,
+            responses(
+                (status = 200, description = "The created order", body = ApiResponse<Order>),
+                (status = 400, description = "Invalid line items"),
+                (status = 409, description = "Not enough inventory for a line item"),
+                (status = 401, description = "Missing or invalid bearer token"),
+            )
+        )]
+        pub async fn create_order(
+            user: AuthenticatedUser,
+            State(state): State<AppState>,
+            JsonBody(input): JsonBody<CreateOrderInput>,
+        ) -> Result<Json<ApiResponse<Order>>, AppError> {
+            OrderLineInput::validate_all(&input.lines).map_err(AppError::Validation)?;
+
+            let mut line_items = Vec::with_capacity(input.lines.len());
+            for line in input.lines {
+                let shopify_id = shopify_id_from_product_uuid(line.product_id)
+                    .ok_or_else(|| AppError::Validation(format!("Product {} not found", line.product_id)))?;
+                let product = product_from_shopify(state.shopify_client.get_product(shopify_id).await?);
+
+                line_items.push(LineItem {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                    unit_price: product.price,
+                });
+            }
+
+            let mut order = Order::new(user.id, line_items);
+            place_order(state.shopify_client.as_ref(), &mut order, state.sync_orders_to_shopify).await?;
+
+            // Sending only fails when nobody is subscribed
+            let _ = state.order_events.send(order.clone());
+
+            Ok(Json(ApiResponse::success(order)))
+        }
+    }
+
+    // Admin Controller
+    pub mod admin {
+        use super::*;
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/admin/sessions",
+            tag = "admin",
+            responses(
+                (status = 200, description = "Access tokens that are still valid, without the tokens themselves", body = ApiResponse<Vec<SessionInfo>>),
+                (status = 401, description = "Missing or invalid bearer token"),
+                (status = 403, description = "The caller isn't an admin"),
+            )
+        )]
+        pub async fn list_sessions(
+            user: AuthenticatedUser,
+            State(state): State<AppState>,
+        ) -> Result<Json<ApiResponse<Vec<SessionInfo>>>, AppError> {
+            if user.role < Role::Admin {
+                return Err(AppError::Forbidden);
+            }
+            Ok(Json(ApiResponse::success(state.auth_service.active_sessions())))
         }
     }
 
@@ -279,25 +765,52 @@ pub mod controllers {
 
         pub async fn graphql_handler(
             State(state): State<AppState>,
+            request_id: RequestId,
             headers: HeaderMap,
-            req: GraphQLRequest,
+            req: GraphQLBatchRequest,
         ) -> GraphQLResponse {
-            let mut context = GraphQLContext::new(state.auth_service.clone(), state.shopify_client.clone());
+            let mut context = GraphQLContext::new(state.auth_service.clone(), state.shopify_client.clone())
+                .with_order_events(state.order_events.clone())
+                .with_users(state.users.clone(), state.login_rate_limiter.clone())
+                .with_order_sync(state.sync_orders_to_shopify);
 
             // Extract user from headers if present
-            if let Some(auth_header) = headers.get("Authorization") {
-                if let Ok(auth_str) = auth_header.to_str() {
-                    if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                        if let Ok(claims) = state.auth_service.verify_token(token) {
-                            if let Ok(user) = AuthenticatedUser::from_claims(claims) {
-                                context = context.with_user(user);
-                            }
-                        }
+            if let Some(token) = bearer_token(&headers) {
+                if let Ok(claims) = state.auth_service.verify_token(token) {
+                    if let Ok(user) = AuthenticatedUser::from_claims(claims) {
+                        context = context.with_user(user);
                     }
                 }
             }
 
-            state.graphql_schema.execute(req.into_inner().data(context)).await.into()
+            // A batch runs each operation on its own, so one failing doesn't fail the rest
+            let response = state.graphql_schema.execute_batch(req.into_inner().data(context)).await;
+            with_request_id(response, &request_id).into()
+        }
+
+        // GraphQL subscriptions over WebSocket; the client authenticates in its
+        // `connection_init` payload since browsers can't set headers on the upgrade
+        pub async fn graphql_ws_handler(
+            State(state): State<AppState>,
+            protocol: GraphQLProtocol,
+            websocket: WebSocketUpgrade,
+        ) -> axum::response::Response {
+            websocket
+                .protocols(ALL_WEBSOCKET_PROTOCOLS)
+                .on_upgrade(move |stream| {
+                    let context = GraphQLContext::new(state.auth_service.clone(), state.shopify_client.clone())
+                        .with_order_events(state.order_events.clone())
+                        .with_users(state.users.clone(), state.login_rate_limiter.clone())
+                        .with_order_sync(state.sync_orders_to_shopify);
+
+                    GraphQLWebSocket::new(stream, state.graphql_schema.clone(), protocol)
+                        .on_connection_init(move |payload| async move {
+                            let mut data = async_graphql::Data::default();
+                            data.insert(context.with_init_payload(&payload)?);
+                            Ok::<_, async_graphql::Error>(data)
+                        })
+                        .serve()
+                })
         }
 
         pub async fn graphql_playground() -> Html<&'static str> {
@@ -310,37 +823,36 @@ pub mod controllers {
         use super::*;
 
         pub async fn shopify_webhook(
-            State(_state): State<AppState>,
+            State(state): State<AppState>,
             headers: HeaderMap,
             body: String,
-        ) -> Result<Json<ApiResponse<String>>, StatusCode> {
+        ) -> Result<Json<ApiResponse<String>>, AppError> {
+            let signature = headers
+                .get("X-Shopify-Hmac-Sha256")
+                .ok_or_else(|| AppError::BadRequest("Missing webhook signature header".to_string()))?
+                .to_str()
+                .map_err(|_| AppError::BadRequest("Malformed webhook signature header".to_string()))?;
+
+            let tolerance = chrono::Duration::seconds(state.shopify_api.config().webhook_tolerance_seconds);
+
             // Verify webhook signature
-            if let Some(signature) = headers.get("X-Shopify-Hmac-Sha256") {
-                if let Ok(sig_str) = signature.to_str() {
-                    let shopify_config = ShopifyConfig::default();
-                    let client = ShopifyClient::new(shopify_config);
-                    
-                    match client.verify_webhook(&body, sig_str) {
-                        Ok(true) => {
-                            info!("Received valid Shopify webhook");
-                            // Process webhook payload here
-                            Ok(Json(ApiResponse::success("Webhook processed".to_string())))
-                        }
-                        Ok(false) => {
-                            warn!("Invalid webhook signature");
-                            Err(StatusCode::UNAUTHORIZED)
-                        }
-                        Err(e) => {
-                            warn!("Webhook verification failed: {}", e);
-                            Err(StatusCode::BAD_REQUEST)
-                        }
-                    }
-                } else {
-                    Err(StatusCode::BAD_REQUEST)
-                }
-            } else {
-                Err(StatusCode::BAD_REQUEST)
+            if !state.shopify_api.verify_webhook(&body, signature)? {
+                warn!("Invalid webhook signature");
+                return Err(ShopifyError::InvalidWebhookSignature.into());
+            }
+
+            let webhook = ShopifyWebhook::parse(&headers, &body)?;
+            webhook
+                .ensure_fresh(tolerance)
+                .inspect_err(|e| warn!("Rejected {} webhook: {}", webhook.topic, e))?;
+
+            // Acknowledge straight away. A full queue answers 503 so Shopify retries
+            // the webhook later rather than it being lost.
+            if !state.webhook_queue.enqueue(webhook) {
+                return Err(AppError::Overloaded);
             }
+
+            Ok(Json(ApiResponse::success("Webhook accepted".to_string())))
         }
     }
 
@@ -348,25 +860,42 @@ pub mod controllers {
     pub mod metrics {
         use super::*;
 
-        pub async fn get_metrics(State(_state): State<AppState>) -> Json<PerformanceMetrics> {
+        pub async fn get_metrics(State(state): State<AppState>) -> Json<PerformanceMetrics> {
+            let process_stats = sample_process();
+
             Json(PerformanceMetrics {
                 framework: "LOCO-style".to_string(),
                 endpoint: "/metrics".to_string(),
                 method: "GET".to_string(),
-                response_time_ms: 1.2, // Mock
-                memory_usage_mb: 42.8,  // Mock
-                cpu_usage_percent: 10.5, // Mock
-                active_connections: 120, // Mock
+                response_time_ms: state.request_stats.average_response_time_ms(),
+                memory_usage_mb: process_stats.memory_usage_mb,
+                cpu_usage_percent: process_stats.cpu_usage_percent,
+                active_connections: state.request_stats.active_connections() as u32,
+                total_requests: state.request_stats.total_requests(),
                 timestamp: chrono::Utc::now(),
             })
         }
 
-        pub async fn run_benchmark(State(_state): State<AppState>) -> Result<Json<ApiResponse<BenchmarkResult>>, StatusCode> {
+        // Prometheus text exposition of the request counters and latency histogram
+        pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+            (
+                [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                state.prometheus.render(),
+            )
+        }
+
+        // The built-in scenario both benchmark endpoints run against this server
+        fn self_benchmark(state: AppState, query: BenchmarkQuery) -> (LoadTester, &'static str) {
             let config = BenchmarkConfig {
-                target_url: "http://localhost:5150".to_string(), // LOCO-style default port
-                concurrent_users: 50,
-                duration_seconds: 30,
-                ramp_up_seconds: 5,
+                target_url: state.self_url.clone(),
+                concurrent_users: query.users.unwrap_or(50),
+                duration_seconds: query.duration.unwrap_or(30),
+                ramp_up_seconds: query.ramp_up.unwrap_or(5),
+                seed: None,
+                think_time: ThinkTime::None,
+                warmup_seconds: 0,
+                credentials: BenchmarkCredentials::default(),
+                global_headers: HashMap::new(),
                 endpoints: vec![
                     EndpointConfig {
                         path: "/health".to_string(),
@@ -374,43 +903,116 @@ pub mod controllers {
                         headers: HashMap::new(),
                         body: None,
                         weight: 1.0,
+                        requires_auth: false,
+                        graphql: None,
                     },
                 ],
             };
 
-            let load_tester = LoadTester::new(config);
-            
+            // In-process runs call a copy of this server's router, leaving out the
+            // loopback connection that would otherwise be part of every measurement
+            if query.in_process {
+                let app = create_router(&state).with_state(state);
+                (LoadTester::in_process(config, app), "Self Benchmark (in-process)")
+            } else {
+                (LoadTester::new(config), "Self Benchmark")
+            }
+        }
+
+        pub async fn run_benchmark(
+            State(state): State<AppState>,
+            Query(query): Query<BenchmarkQuery>,
+        ) -> Result<Json<ApiResponse<BenchmarkResult>>, AppError> {
+            let (load_tester, test_name) = self_benchmark(state, query);
             match load_tester.run_benchmark("LOCO-style".to_string()).await {
                 Ok(metrics) => {
-                    let result = metrics.to_benchmark_result("Self Benchmark".to_string());
+                    let result = metrics.to_benchmark_result(test_name.to_string(), Some(sample_process()));
                     Ok(Json(ApiResponse::success(result)))
                 }
-                Err(e) => {
-                    warn!("Benchmark failed: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
+                Err(e) => Err(AppError::Internal(format!("Benchmark failed: {}", e))),
             }
         }
+
+        // Runs the same benchmark, streaming its progress every second and then the
+        // result; closing the socket stops the run
+        pub async fn benchmark_stream_handler(
+            State(state): State<AppState>,
+            Query(query): Query<BenchmarkQuery>,
+            websocket: WebSocketUpgrade,
+        ) -> axum::response::Response {
+            let (load_tester, test_name) = self_benchmark(state, query);
+            websocket.on_upgrade(move |socket| stream_benchmark(socket, load_tester, "LOCO-style", test_name))
+        }
     }
 }
 
 // LOCO-style Router Configuration
-fn create_router() -> Router<AppState> {
+// Paths of the REST API's OpenAPI document; `rest_api_doc` adds the info and
+// tags shared with the other server
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        controllers::products::get_products,
+        controllers::products::create_product,
+        controllers::products::create_products_bulk,
+        controllers::products::stream_products,
+        controllers::products::get_product,
+        controllers::products::update_product,
+        controllers::products::delete_product,
+        controllers::auth::register,
+        controllers::auth::login,
+        controllers::auth::refresh_token,
+        controllers::auth::logout,
+        controllers::auth::get_current_user,
+        controllers::orders::list_orders,
+        controllers::orders::get_order,
+        controllers::orders::create_order,
+        controllers::admin::list_sessions,
+    ),
+)]
+struct ApiDoc;
+
+// REST routes, relative to the API prefix they're nested under
+fn api_routes() -> Router<AppState> {
+    Router::new()
+        // Products
+        .route("/products", get(controllers::products::get_products).post(controllers::products::create_product))
+        .route("/products/bulk", post(controllers::products::create_products_bulk))
+        .route("/products/stream", get(controllers::products::stream_products))
+        .route("/products/{id}", get(controllers::products::get_product).put(controllers::products::update_product).delete(controllers::products::delete_product))
+        
+        // Authentication routes
+        .route("/auth/register", post(controllers::auth::register))
+        .route("/auth/login", post(controllers::auth::login))
+        .route("/auth/refresh", post(controllers::auth::refresh_token))
+        .route("/auth/logout", post(controllers::auth::logout))
+        .route("/users/me", get(controllers::auth::get_current_user))
+        
+        // Orders
+        .route("/orders", get(controllers::orders::list_orders).post(controllers::orders::create_order))
+        .route("/orders/{id}", get(controllers::orders::get_order))
+
+        // Admin
+        .route("/admin/sessions", get(controllers::admin::list_sessions))
+}
+
+fn create_router(state: &AppState) -> Router<AppState> {
     Router::new()
         // Health check
         .route("/health", get(controllers::health::health_check))
+        .route("/ready", get(controllers::health::readiness_check))
+        .route("/version", get(controllers::health::version))
         
-        // REST API routes (LOCO-style organization)
-        .route("/api/products", get(controllers::products::get_products).post(controllers::products::create_product))
-        .route("/api/products/{id}", get(controllers::products::get_product))
+        // REST API, versioned, plus the deprecated unversioned paths
+        .nest(API_V1_PREFIX, api_routes())
+        .nest("/api", api_routes().layer(middleware::from_fn(deprecated_api_middleware)))
         
-        // Authentication routes
-        .route("/api/auth/register", post(controllers::auth::register))
-        .route("/api/auth/login", post(controllers::auth::login))
-        .route("/api/users/me", get(controllers::auth::get_current_user))
+        // OpenAPI spec and Swagger UI
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", rest_api_doc(ApiDoc::openapi())))
         
         // GraphQL routes
         .route("/graphql", post(controllers::graphql::graphql_handler))
+        .route("/graphql/ws", get(controllers::graphql::graphql_ws_handler))
         .route("/graphql/playground", get(controllers::graphql::graphql_playground))
         
         // Shopify integration
@@ -418,41 +1020,76 @@ fn create_router() -> Router<AppState> {
         
         // Performance and benchmarking
         .route("/metrics", get(controllers::metrics::get_metrics))
+        .route("/metrics/prometheus", get(controllers::metrics::prometheus_metrics))
         .route("/benchmark", post(controllers::metrics::run_benchmark))
+        .route("/benchmark/stream", get(controllers::metrics::benchmark_stream_handler))
         
         // LOCO-style middleware stack
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(CompressionLayer::new())
-                .layer(CorsLayer::permissive())
+                .layer(middleware::from_fn(request_id_middleware))
+                .layer(middleware::from_fn(track_http_metrics))
+                .layer(middleware::from_fn_with_state(state.slow_request_threshold, log_slow_requests))
+                .layer(middleware::from_fn_with_state(state.request_stats.clone(), track_request_stats))
+                .layer(middleware::from_fn_with_state(state.rate_limiter.clone(), rate_limit_middleware))
+                // One set of permits for the whole server (a plain ConcurrencyLimitLayer
+                // would get one per route), shedding rather than queueing when all are taken
+                .layer(HandleErrorLayer::new(handle_overload))
+                .layer(LoadShedLayer::new())
+                .layer(GlobalConcurrencyLimitLayer::new(state.max_concurrent_requests))
+                .layer(RequestBodyLimitLayer::new(state.max_body_bytes))
+                .layer(TimeoutLayer::new(state.request_timeout))
+                .layer(http_trace_layer())
+                .layer(state.compression.layer())
+                .layer(state.cors.clone())
         )
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .compact()
-        .init();
+    init_tracing(LogFormat::from_env());
+
+    // Load and validate the config first, so a bad value fails before anything starts
+    let config = ServerConfig::load(DEFAULT_PORT)?;
+    let addr = config.bind_address()?;
+    // A certificate or key that can't be read is also caught here
+    let tls = match config.tls_paths()? {
+        Some(paths) => Some(paths.load().await?),
+        None => None,
+    };
+
+    // Webhooks are handled off the request path, by a worker that outlives the server
+    let (webhook_queue, webhooks) = WebhookQueue::new(WEBHOOK_QUEUE_CAPACITY);
+    let webhook_worker = WebhookWorker::spawn(webhooks, Arc::new(LoggingWebhookHandler));
 
     // Create application state
-    let state = AppState::new();
+    let state = AppState {
+        webhook_queue,
+        ..AppState::from_config(&config)?
+    };
 
     // Create router with LOCO-style organization
-    let app = create_router().with_state(state);
+    let app = create_router(&state).with_state(state);
 
     // Start server
-    let listener = TcpListener::bind("0.0.0.0:5150").await?;
-    
-    info!("🚀 LOCO-style server starting on http://0.0.0.0:5150");
-    info!("📊 GraphQL Playground available at http://0.0.0.0:5150/graphql/playground");
-    info!("🏥 Health check available at http://0.0.0.0:5150/health");
-    info!("📈 Metrics available at http://0.0.0.0:5150/metrics");
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("🚀 LOCO-style server starting on {}://{}", scheme, addr);
+    info!("📊 GraphQL Playground available at {}://{}/graphql/playground", scheme, addr);
+    info!("🏥 Health check available at {}://{}/health", scheme, addr);
+    info!("📈 Metrics available at {}://{}/metrics", scheme, addr);
     info!("🎯 Demonstrating LOCO-style patterns and organization");
     
-    axum::serve(listener, app).await?;
+    match tls {
+        Some(tls) => serve_tls_with_graceful_shutdown(addr, tls, app, shutdown_signal(), DRAIN_TIMEOUT).await?,
+        None => {
+            let listener = TcpListener::bind(addr).await?;
+            serve_with_graceful_shutdown(listener, app, shutdown_signal(), DRAIN_TIMEOUT).await?
+        }
+    }
+
+    // Handle whatever was accepted before the server stopped
+    webhook_worker.shutdown().await;
 
     Ok(())
 }
@@ -462,11 +1099,12 @@ mod tests {
     use super::*;
     use axum::http::StatusCode;
     use axum_test::TestServer;
+    use shared::test_support::CapturedLogs;
 
     #[tokio::test]
     async fn test_health_check() {
         let state = AppState::new();
-        let app = create_router().with_state(state);
+        let app = create_router(&state).with_state(state);
         let server = TestServer::new(app).unwrap();
 
         let response = server.get("/health").await;
@@ -478,53 +1116,1298 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_get_products() {
+    async fn test_request_id_is_echoed_or_generated() {
         let state = AppState::new();
-        let app = create_router().with_state(state);
+        let app = create_router(&state).with_state(state);
         let server = TestServer::new(app).unwrap();
 
-        let response = server.get("/api/products").await;
+        let response = server
+            .get("/health")
+            .add_header("X-Request-Id", "test-request-123")
+            .await;
+        assert_eq!(response.header("X-Request-Id"), "test-request-123");
+
+        let response = server.get("/health").await;
+        let request_id = response.header("X-Request-Id");
+        assert!(Uuid::parse_str(request_id.to_str().unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_readiness_check() {
+        // Without caching, so the second probe sees Shopify go down
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let state = AppState {
+            shopify_client: shopify_client.clone(),
+            readiness_cache: Arc::new(CheckCache::new(Duration::ZERO)),
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/ready").await;
         assert_eq!(response.status_code(), StatusCode::OK);
-        
-        let api_response: ApiResponse<Vec<Product>> = response.json();
-        assert!(api_response.success);
-        assert!(api_response.data.is_some());
+        assert!(response.json::<ReadinessCheck>().is_ready());
+
+        shopify_client.set_available(false);
+        let response = server.get("/ready").await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let readiness: ReadinessCheck = response.json();
+        assert_eq!(readiness.status, "not_ready");
+        assert!(readiness.checks["shopify"].starts_with("error"));
     }
 
     #[tokio::test]
-    async fn test_register_user() {
-        let state = AppState::new();
-        let app = create_router().with_state(state);
+    async fn test_shopify_circuit_opens_after_repeated_failures() {
+        let mock = Arc::new(MockShopifyClient::new());
+        let shopify_breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+        }));
+        let state = AppState {
+            shopify_client: Arc::new(CircuitBreakerBackend::new(mock.clone(), shopify_breaker.clone())),
+            shopify_breaker,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state.clone());
         let server = TestServer::new(app).unwrap();
 
-        let user_input = CreateUserInput {
-            email: "test@example.com".to_string(),
-            name: "Test User".to_string(),
-            password: "TestPassword123!".to_string(),
+        mock.set_available(false);
+        for _ in 0..3 {
+            let response = server.get("/api/v1/products").await;
+            assert_eq!(response.status_code(), StatusCode::BAD_GATEWAY);
+        }
+
+        // The third request failed fast without reaching Shopify
+        assert_eq!(mock.api_call_count(), 2);
+        assert_eq!(state.shopify_breaker.state(), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_app_state_accepts_either_shopify_backend() {
+        let mock: Arc<dyn ShopifyBackend> = Arc::new(MockShopifyClient::new());
+        let state = AppState {
+            shopify_client: mock,
+            ..AppState::new()
         };
+        let server = TestServer::new(create_router(&state).with_state(state)).unwrap();
+        let response = server.get("/api/v1/products").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
 
-        let response = server
-            .post("/api/auth/register")
-            .json(&user_input)
-            .await;
-        
+        // Nothing listens on port 1, so the real client's call fails and
+        // surfaces as a Shopify error rather than mock data
+        let live: Arc<dyn ShopifyBackend> = Arc::new(ShopifyClient::with_base_url(
+            ShopifyConfig::default(),
+            "http://127.0.0.1:1".to_string(),
+        ));
+        let state = AppState {
+            shopify_client: live,
+            ..AppState::new()
+        };
+        let server = TestServer::new(create_router(&state).with_state(state)).unwrap();
+        let response = server.get("/api/v1/products").await;
+        assert_eq!(response.status_code(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_readiness_check_is_cached() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let state = AppState {
+            shopify_client: shopify_client.clone(),
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        server.get("/ready").await;
+        let response = server.get("/ready").await;
         assert_eq!(response.status_code(), StatusCode::OK);
-        
-        let api_response: ApiResponse<AuthResponse> = response.json();
-        assert!(api_response.success);
-        assert!(api_response.data.is_some());
+        assert_eq!(shopify_client.api_call_count(), 1);
+    }
+
+    fn sign_webhook(body: &str) -> String {
+        sign_webhook_with(&ShopifyConfig::default().webhook_secret, body)
+    }
+
+    fn sign_webhook_with(secret: &str, body: &str) -> String {
+        use base64::Engine;
+        use hmac::{Hmac, Mac};
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body.as_bytes());
+        base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    async fn post_webhook(server: &TestServer, body: &str) -> axum_test::TestResponse {
+        post_signed_webhook(server, body, sign_webhook(body)).await
+    }
+
+    async fn post_signed_webhook(server: &TestServer, body: &str, signature: String) -> axum_test::TestResponse {
+        server
+            .post("/webhooks/shopify")
+            .add_header("X-Shopify-Hmac-Sha256", signature)
+            .add_header("X-Shopify-Topic", "products/create")
+            .add_header("X-Shopify-Shop-Domain", "demo.myshopify.com")
+            .add_header("X-Shopify-Triggered-At", chrono::Utc::now().to_rfc3339())
+            .text(body)
+            .await
+    }
+
+    async fn product_webhook_body() -> String {
+        let product = MockShopifyClient::new().get_product(1).await.unwrap();
+        serde_json::to_string(&product).unwrap()
     }
 
     #[tokio::test]
-    async fn test_get_metrics() {
-        let state = AppState::new();
-        let app = create_router().with_state(state);
+    async fn test_shopify_webhook_is_queued() {
+        let (webhook_queue, mut webhooks) = WebhookQueue::new(8);
+        let state = AppState {
+            webhook_queue,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
         let server = TestServer::new(app).unwrap();
 
-        let response = server.get("/metrics").await;
+        let response = post_webhook(&server, &product_webhook_body().await).await;
         assert_eq!(response.status_code(), StatusCode::OK);
-        
-        let metrics: PerformanceMetrics = response.json();
-        assert_eq!(metrics.framework, "LOCO-style");
+
+        let webhook = webhooks.try_recv().unwrap();
+        assert_eq!(webhook.topic, "products/create");
+        assert!(matches!(&webhook.payload, WebhookPayload::Product(product) if product.title == "Demo Product 1"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_payload_must_match_topic() {
+        let (webhook_queue, mut webhooks) = WebhookQueue::new(8);
+        let state = AppState {
+            webhook_queue,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = post_webhook(&server, r#"{"id": 1, "title": "Demo Product"}"#).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("products/create payload is not a valid product"));
+        assert!(webhooks.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_webhook_queue_returns_503() {
+        let (webhook_queue, _webhooks) = WebhookQueue::new(1);
+        let state = AppState {
+            webhook_queue,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+        let body = product_webhook_body().await;
+
+        assert_eq!(post_webhook(&server, &body).await.status_code(), StatusCode::OK);
+        let response = post_webhook(&server, &body).await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.text().contains("OVERLOADED"));
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_share_one_shopify_client() {
+        // Only the shared client has this secret, so a handler that built its
+        // own client from the default config would reject these webhooks
+        let shopify_api = Arc::new(ShopifyClient::new(ShopifyConfig {
+            webhook_secret: "shared-client-secret".to_string(),
+            ..ShopifyConfig::default()
+        }));
+        let (webhook_queue, mut webhooks) = WebhookQueue::new(8);
+        let state = AppState {
+            shopify_api,
+            webhook_queue,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+        let body = product_webhook_body().await;
+
+        for _ in 0..2 {
+            let response = post_signed_webhook(&server, &body, sign_webhook_with("shared-client-secret", &body)).await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert!(webhooks.try_recv().is_ok());
+        }
+
+        let response = post_webhook(&server, &body).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_rejected() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/api/products")
+            .content_type("application/json")
+            .bytes(vec![b' '; DEFAULT_MAX_BODY_BYTES + 1].into())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_is_configurable() {
+        let state = AppState {
+            max_body_bytes: 64,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/graphql")
+            .content_type("application/json")
+            .bytes(vec![b' '; 65].into())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_only_large_responses_are_compressed() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/health").add_header("Accept-Encoding", "gzip").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.maybe_header("Content-Encoding").is_none());
+
+        let response = server.get("/api-docs/openapi.json").add_header("Accept-Encoding", "gzip").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.header("Content-Encoding"), "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_only_configured_origins() {
+        let mut config = ServerConfig::new(DEFAULT_PORT);
+        config.cors.allowed_origins = vec!["https://shop.example.com".to_string()];
+        let state = AppState::from_config(&config).unwrap();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/health").add_header("Origin", "https://shop.example.com").await;
+        assert_eq!(response.header("Access-Control-Allow-Origin"), "https://shop.example.com");
+
+        let response = server.get("/health").add_header("Origin", "https://evil.example.com").await;
+        assert!(response.maybe_header("Access-Control-Allow-Origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_times_out() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        shopify_client.set_latency(Duration::from_secs(2));
+        let state = AppState {
+            shopify_client,
+            request_timeout: Duration::from_millis(50),
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/products").await;
+        assert_eq!(response.status_code(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_requests_over_concurrency_limit_are_shed() {
+        use std::future::IntoFuture;
+
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        shopify_client.set_latency(Duration::from_millis(200));
+        let state = AppState {
+            shopify_client,
+            max_concurrent_requests: 1,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let (first, second, third) = tokio::join!(
+            server.get("/api/products").into_future(),
+            server.get("/api/products").into_future(),
+            server.get("/api/products").into_future(),
+        );
+        let statuses = [first.status_code(), second.status_code(), third.status_code()];
+
+        assert!(statuses.contains(&StatusCode::OK));
+        assert!(statuses.contains(&StatusCode::SERVICE_UNAVAILABLE));
+        let shed = [first, second, third]
+            .into_iter()
+            .find(|response| response.status_code() == StatusCode::SERVICE_UNAVAILABLE)
+            .unwrap();
+        assert_eq!(shed.json::<ApiResponse<()>>().error_code.as_deref(), Some("OVERLOADED"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_rapid_requests_from_one_ip() {
+        let config = RateLimitConfig { requests_per_second: 1.0, burst: 3 };
+        // The test client connects from loopback, standing in for a proxy
+        let limiter = IpRateLimiter::new(config).with_trusted_proxies(vec!["127.0.0.1".parse().unwrap()]);
+        let state = AppState {
+            rate_limiter: Some(Arc::new(limiter)),
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        // The peer address is only known over a real connection
+        let server = TestServer::builder()
+            .http_transport()
+            .build(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .unwrap();
+
+        for _ in 0..3 {
+            let response = server.get("/health").add_header("X-Forwarded-For", "203.0.113.7").await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+
+        let response = server.get("/health").add_header("X-Forwarded-For", "203.0.113.7").await;
+        assert_eq!(response.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.header("Retry-After"), "1");
+
+        let response = server.get("/health").add_header("X-Forwarded-For", "203.0.113.8").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics_count_requests() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        server.get("/health").await;
+
+        let response = server.get("/metrics/prometheus").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let body = response.text();
+        assert!(body.lines().any(|line| line.starts_with("http_requests_total{") && line.contains(r#"path="/health""#)));
+        assert!(body.contains("http_request_duration_seconds_bucket"));
+    }
+
+    #[tokio::test]
+    async fn test_versioned_api_routes() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/v1/products").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.maybe_header("deprecation").is_none());
+
+        let response = server.get("/api/products").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.header("deprecation"), "true");
+        assert_eq!(response.header("link"), r#"</api/v1/products>; rel="successor-version""#);
+    }
+
+    #[tokio::test]
+    async fn test_create_product_is_idempotent() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let input = CreateProductInput {
+            name: "Retried Product".to_string(),
+            description: None,
+            price: 12.5,
+        };
+
+        let first: ApiResponse<Product> = server
+            .post("/api/v1/products")
+            .add_header(IDEMPOTENCY_KEY_HEADER, "create-retried-product")
+            .json(&input)
+            .await
+            .json();
+        let second: ApiResponse<Product> = server
+            .post("/api/v1/products")
+            .add_header(IDEMPOTENCY_KEY_HEADER, "create-retried-product")
+            .json(&input)
+            .await
+            .json();
+
+        let (first, second) = (first.data.unwrap(), second.data.unwrap());
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.shopify_id, second.shopify_id);
+
+        let response = server.get("/api/v1/products").await;
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert_eq!(page.total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_openapi_spec_describes_rest_api() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api-docs/openapi.json").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let spec: serde_json::Value = response.json();
+        assert_eq!(spec["info"]["title"], "AXUM vs LOCO demo API");
+        // The same operations as the other server documents
+        let mut operations: Vec<(String, String)> = spec["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object().unwrap().keys().map(move |method| (method.clone(), path.clone()))
+            })
+            .collect();
+        operations.sort();
+        let mut expected: Vec<(String, String)> = REST_API_OPERATIONS
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()))
+            .collect();
+        expected.sort();
+        assert_eq!(operations, expected);
+        assert!(spec["paths"]["/api/v1/products"]["post"].is_object());
+        assert!(spec["paths"]["/api/v1/products"]["get"].is_object());
+        assert!(spec["components"]["schemas"]["CreateProductInput"].is_object());
+
+        let response = server.get("/swagger-ui/").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_stream_products_as_ndjson() {
+        let state = AppState::new();
+        let product_count = state.shopify_client.get_products().await.unwrap().len();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/v1/products/stream").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.header("Content-Type"), "application/x-ndjson");
+
+        let text = response.text();
+        let products: Vec<Product> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(products.len(), product_count);
+    }
+
+    #[tokio::test]
+    async fn test_request_span_records_method_path_and_status() {
+        let logs = CapturedLogs::default();
+        let _guard = logs.capture();
+
+        let state = AppState::new();
+        let server = TestServer::new(create_router(&state).with_state(state)).unwrap();
+        server.get("/health").await;
+
+        let output = logs.contents();
+        let line = output
+            .lines()
+            .find(|line| line.contains("request completed"))
+            .expect("no completed request was logged");
+        assert!(line.contains("http_request{method=GET path=/health"), "{}", line);
+        assert!(line.contains("status=200"), "{}", line);
+        assert!(line.contains("latency_ms="), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_websocket_handshake() {
+        let state = AppState::new();
+        let claims = Claims::new(Uuid::new_v4(), "ws@example.com".to_string(), "WS User".to_string(), 1);
+        let token = state.auth_service.generate_token(&claims).unwrap();
+        // WebSockets need a real connection rather than the mock transport
+        let server = TestServer::builder()
+            .http_transport()
+            .build(create_router(&state).with_state(state))
+            .unwrap();
+
+        let mut websocket = server
+            .get_websocket("/graphql/ws")
+            .add_header("Sec-WebSocket-Protocol", "graphql-transport-ws")
+            .await
+            .into_websocket()
+            .await;
+
+        websocket
+            .send_json(&serde_json::json!({
+                "type": "connection_init",
+                "payload": { "Authorization": format!("Bearer {}", token) },
+            }))
+            .await;
+        let ack: serde_json::Value = websocket.receive_json().await;
+        assert_eq!(ack["type"], "connection_ack");
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_stream_sends_progress() {
+        let state = AppState::new();
+        let server = TestServer::builder()
+            .http_transport()
+            .build(create_router(&state).with_state(state))
+            .unwrap();
+
+        let mut websocket = server
+            .get_websocket("/benchmark/stream?in_process=true")
+            .await
+            .into_websocket()
+            .await;
+
+        let message: serde_json::Value = websocket.receive_json().await;
+        assert_eq!(message["type"], "progress", "{}", message);
+        let update: ProgressUpdate = serde_json::from_value(message).unwrap();
+        assert!(update.requests > 0);
+        assert!(update.elapsed_seconds >= 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_products_respond_with_msgpack_when_accepted() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+        let path = format!("/api/v1/products/{}", product_uuid_from_shopify_id(1));
+
+        let response = server.get(&path).add_header("Accept", "application/msgpack").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.header("Content-Type"), "application/msgpack");
+
+        let body: ApiResponse<Product> = rmp_serde::from_slice(response.as_bytes()).unwrap();
+        let product = body.data.unwrap();
+        assert_eq!(product.id, product_uuid_from_shopify_id(1));
+        assert_eq!(product.name, "Demo Product 1");
+
+        let response = server.get(&path).await;
+        assert_eq!(response.header("Content-Type"), "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_current_user_rejects_non_uuid_subject() {
+        let state = AppState::new();
+        let mut claims = Claims::new(Uuid::new_v4(), "test@example.com".to_string(), "Test User".to_string(), 1);
+        claims.sub = "not-a-uuid".to_string();
+        let authorization = format!("Bearer {}", state.auth_service.generate_token(&claims).unwrap());
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/v1/users/me").add_header("Authorization", &authorization).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.json::<ApiResponse<()>>().error_code.as_deref(), Some("INVALID_TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn test_graphql_errors_carry_code_and_request_id() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/graphql")
+            .add_header("X-Request-Id", "graphql-request-123")
+            .json(&serde_json::json!({ "query": "{ myOrders { id } }" }))
+            .await;
+
+        let body: serde_json::Value = response.json();
+        let extensions = &body["errors"][0]["extensions"];
+        assert_eq!(extensions["code"], "UNAUTHENTICATED");
+        assert_eq!(extensions["request_id"], "graphql-request-123");
+    }
+
+    #[tokio::test]
+    async fn test_version_reports_crate_version() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/version").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let version: VersionInfo = response.json();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.framework, "LOCO-style");
+        assert_eq!(version.axum_version, AXUM_VERSION);
+        assert_eq!(version.async_graphql_version, ASYNC_GRAPHQL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_batch_returns_a_response_per_operation() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/graphql")
+            .json(&serde_json::json!([
+                { "query": "{ health }" },
+                { "query": "{ myOrders { id } }" },
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let body: serde_json::Value = response.json();
+        let responses = body.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0]["data"]["health"].is_string());
+        assert!(responses[0].get("errors").is_none());
+        assert_eq!(responses[1]["errors"][0]["extensions"]["code"], "UNAUTHENTICATED");
+    }
+
+    #[tokio::test]
+    async fn test_graphql_persisted_query_register_then_reuse() {
+        use sha2::Digest;
+
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let query = "{ health }";
+        let extensions = serde_json::json!({
+            "persistedQuery": {
+                "version": 1,
+                "sha256Hash": format!("{:x}", sha2::Sha256::digest(query.as_bytes())),
+            }
+        });
+
+        // Unknown hash: the client should retry with the full query
+        let response = server.post("/graphql").json(&serde_json::json!({ "extensions": extensions })).await;
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["errors"][0]["message"], "PersistedQueryNotFound");
+
+        // Register the query with its hash
+        let response = server
+            .post("/graphql")
+            .json(&serde_json::json!({ "query": query, "extensions": extensions }))
+            .await;
+        let body: serde_json::Value = response.json();
+        assert!(body["data"]["health"].is_string());
+
+        // From now on the hash alone is enough
+        let response = server.post("/graphql").json(&serde_json::json!({ "extensions": extensions })).await;
+        let body: serde_json::Value = response.json();
+        assert!(body["data"]["health"].is_string());
+        assert!(body.get("errors").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_products() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/products").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        
+        let api_response: ApiResponse<PaginatedResponse<Product>> = response.json();
+        assert!(api_response.success);
+
+        let page = api_response.data.unwrap();
+        assert_eq!((page.page, page.per_page), (1, DEFAULT_PER_PAGE));
+        assert_eq!((page.total, page.total_pages), (2, 1));
+        assert_eq!(page.items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_archived_products_are_listed_only_on_request() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        shopify_client.archive_product(1).await.unwrap();
+        let state = AppState {
+            shopify_client,
+            ..AppState::new()
+        };
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let page: ApiResponse<PaginatedResponse<Product>> = server.get("/api/v1/products").await.json();
+        let page = page.data.unwrap();
+        assert_eq!(page.total, 1);
+        assert!(page.items.iter().all(|product| !product.archived));
+
+        let page: ApiResponse<PaginatedResponse<Product>> = server
+            .get("/api/v1/products")
+            .add_query_param("include_archived", true)
+            .await
+            .json();
+        let page = page.data.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.iter().filter(|product| product.archived).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_create_reports_partial_failures() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let inputs = vec![
+            CreateProductInput { name: "Bulk 1".to_string(), description: None, price: 10.0 },
+            CreateProductInput { name: "  ".to_string(), description: None, price: 5.0 },
+            CreateProductInput { name: "Bulk 3".to_string(), description: None, price: 30.0 },
+        ];
+
+        let response = server.post("/api/products/bulk").json(&inputs).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let bulk = response.json::<ApiResponse<BulkResponse<Product>>>().data.unwrap();
+        assert_eq!((bulk.succeeded, bulk.failed), (2, 1));
+        assert_eq!(bulk.results.iter().map(|r| r.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(bulk.results[0].data.as_ref().unwrap().name, "Bulk 1");
+        assert_eq!(bulk.results[1].error.as_deref(), Some("Product name must not be empty"));
+        assert_eq!(bulk.results[2].data.as_ref().unwrap().price, 30.0);
+
+        let response = server.get("/api/products").await;
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert_eq!(page.total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_get_products_filter_and_sort() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/products?name_contains=product%202&sort_by=name&order=desc").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].name, "Demo Product 2");
+
+        let response = server.get("/api/products?min_price=1000").await;
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert!(page.items.is_empty());
+
+        let response = server.get("/api/products?sort_by=popularity").await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_products_explicit_page() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/products?page=2&per_page=1").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert_eq!((page.page, page.per_page), (2, 1));
+        assert_eq!((page.total, page.total_pages), (2, 2));
+        assert_eq!(page.items[0].name, "Demo Product 2");
+
+        // Oversized pages are clamped rather than rejected
+        let response = server.get("/api/products?per_page=10000").await;
+        let page = response.json::<ApiResponse<PaginatedResponse<Product>>>().data.unwrap();
+        assert_eq!(page.per_page, MAX_PER_PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_get_products_pagination_headers() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/v1/products?per_page=1").await;
+        assert_eq!(response.header("X-Total-Count"), "2");
+        assert_eq!(response.header("X-Page"), "1");
+        let link = response.header("Link");
+        let link = link.to_str().unwrap();
+        assert!(link.contains("</api/v1/products?per_page=1&page=2>; rel=\"next\""), "{}", link);
+        assert!(!link.contains("rel=\"prev\""));
+
+        let response = server.get("/api/v1/products?per_page=1&page=2").await;
+        assert!(!response.header("Link").to_str().unwrap().contains("rel=\"next\""));
+    }
+
+    #[tokio::test]
+    async fn test_register_user() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let user_input = CreateUserInput {
+            email: "test@example.com".to_string(),
+            name: "Test User".to_string(),
+            password: "TestPassword123!".to_string(),
+        };
+
+        let response = server
+            .post("/api/auth/register")
+            .json(&user_input)
+            .await;
+        
+        assert_eq!(response.status_code(), StatusCode::OK);
+        
+        let api_response: ApiResponse<AuthResponse> = response.json();
+        assert!(api_response.success);
+        assert!(api_response.data.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_logout_revokes_token() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let user_input = CreateUserInput {
+            email: "test@example.com".to_string(),
+            name: "Test User".to_string(),
+            password: "TestPassword123!".to_string(),
+        };
+        let api_response: ApiResponse<AuthResponse> = server
+            .post("/api/auth/register")
+            .json(&user_input)
+            .await
+            .json();
+        let authorization = format!("Bearer {}", api_response.data.unwrap().token);
+
+        let response = server.get("/api/users/me").add_header("Authorization", &authorization).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let response = server.post("/api/auth/logout").add_header("Authorization", &authorization).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let response = server.get("/api/users/me").add_header("Authorization", &authorization).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_sessions_lists_active_tokens() {
+        let state = AppState::new();
+        let admin = Claims::new(Uuid::new_v4(), "admin@example.com".to_string(), "Admin".to_string(), 1).with_role(Role::Admin);
+        let admin_token = state.auth_service.generate_token(&admin).unwrap();
+        let admin_authorization = format!("Bearer {}", admin_token);
+        let user = Claims::new(Uuid::new_v4(), "test@example.com".to_string(), "Test User".to_string(), 1);
+        let user_token = state.auth_service.generate_token(&user).unwrap();
+        let user_authorization = format!("Bearer {}", user_token);
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/v1/admin/sessions").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        let response = server.get("/api/v1/admin/sessions").add_header("Authorization", &user_authorization).await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = server.get("/api/v1/admin/sessions").add_header("Authorization", &admin_authorization).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(!response.text().contains(&user_token));
+        let sessions = response.json::<ApiResponse<Vec<SessionInfo>>>().data.unwrap();
+        let session = sessions.iter().find(|session| session.jti == user.jti).unwrap();
+        assert_eq!(session.email, "test@example.com");
+        assert_eq!(session.expires_at.timestamp(), user.exp);
+
+        let response = server.post("/api/v1/auth/logout").add_header("Authorization", &user_authorization).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let response = server.get("/api/v1/admin/sessions").add_header("Authorization", &admin_authorization).await;
+        let sessions = response.json::<ApiResponse<Vec<SessionInfo>>>().data.unwrap();
+        assert!(sessions.iter().all(|session| session.jti != user.jti));
+        assert!(sessions.iter().any(|session| session.jti == admin.jti));
+    }
+
+    #[tokio::test]
+    async fn test_list_orders_requires_authentication() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/orders").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server.get("/api/orders").add_header("Authorization", "Bearer not-a-token").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_orders() {
+        let state = AppState::new();
+        let user_id = Uuid::new_v4();
+        let claims = Claims::new(user_id, "test@example.com".to_string(), "Test User".to_string(), 1);
+        let authorization = format!("Bearer {}", state.auth_service.generate_token(&claims).unwrap());
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/api/orders").add_header("Authorization", &authorization).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let orders = response.json::<ApiResponse<Vec<Order>>>().data.unwrap();
+        assert!(!orders.is_empty());
+        assert!(orders.iter().all(|order| order.user_id == user_id));
+
+        let input = CreateOrderInput {
+            lines: vec![OrderLineInput { product_id: product_uuid_from_shopify_id(1), quantity: 3 }],
+        };
+        let response = server
+            .post("/api/orders")
+            .add_header("Authorization", &authorization)
+            .json(&input)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let order = response.json::<ApiResponse<Order>>().data.unwrap();
+        assert_eq!(order.user_id, user_id);
+        assert_eq!(order.total_amount, Order::calculate_total(&order.line_items));
+    }
+
+    #[tokio::test]
+    async fn test_create_order_syncs_to_shopify_when_enabled() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let state = AppState {
+            shopify_client: shopify_client.clone(),
+            sync_orders_to_shopify: true,
+            ..AppState::new()
+        };
+        let claims = Claims::new(Uuid::new_v4(), "test@example.com".to_string(), "Test User".to_string(), 1);
+        let authorization = format!("Bearer {}", state.auth_service.generate_token(&claims).unwrap());
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let input = CreateOrderInput {
+            lines: vec![OrderLineInput { product_id: product_uuid_from_shopify_id(1), quantity: 2 }],
+        };
+        let response = server
+            .post("/api/orders")
+            .add_header("Authorization", &authorization)
+            .json(&input)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let order = response.json::<ApiResponse<Order>>().data.unwrap();
+
+        let shopify_orders = shopify_client.get_orders().await.unwrap();
+        assert_eq!(shopify_orders.len(), 1);
+        assert_eq!(order.shopify_order_id, shopify_orders[0].id.map(|id| id.to_string()));
+        assert_eq!(shopify_orders[0].total_price, format!("{:.2}", order.total_amount));
+    }
+
+    #[tokio::test]
+    async fn test_failed_shopify_sync_releases_inventory() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        shopify_client.set_reject_orders(true);
+        let state = AppState {
+            shopify_client: shopify_client.clone(),
+            sync_orders_to_shopify: true,
+            ..AppState::new()
+        };
+        let claims = Claims::new(Uuid::new_v4(), "test@example.com".to_string(), "Test User".to_string(), 1);
+        let authorization = format!("Bearer {}", state.auth_service.generate_token(&claims).unwrap());
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let input = CreateOrderInput {
+            lines: vec![OrderLineInput { product_id: product_uuid_from_shopify_id(1), quantity: 2 }],
+        };
+        let response = server
+            .post("/api/orders")
+            .add_header("Authorization", &authorization)
+            .json(&input)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(shopify_client.inventory_quantity(1), DEFAULT_MOCK_INVENTORY);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_email() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let user_input = CreateUserInput {
+            email: "not-an-email".to_string(),
+            name: "Test User".to_string(),
+            password: "TestPassword123!".to_string(),
+        };
+
+        let response = server
+            .post("/api/auth/register")
+            .json(&user_input)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let api_response: ApiResponse<()> = response.json();
+        assert_eq!(api_response.error_code.as_deref(), Some("VALIDATION_ERROR"));
+        assert!(api_response.error.unwrap().contains("not a valid email address"));
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/metrics").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        
+        let metrics: PerformanceMetrics = response.json();
+        assert_eq!(metrics.framework, "LOCO-style");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_real_requests() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        for _ in 0..3 {
+            server.get("/health").await;
+        }
+
+        let metrics: PerformanceMetrics = server.get("/metrics").await.json();
+        assert_eq!(metrics.total_requests, 3);
+        // Only the metrics request itself is still in flight
+        assert_eq!(metrics.active_connections, 1);
+        assert!(metrics.response_time_ms > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_body_gets_structured_error() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/api/auth/register")
+            .content_type("application/json")
+            .bytes(r#"{"email": "user@example.com","#.into())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        let api_response: ApiResponse<()> = response.json();
+        assert!(!api_response.success);
+        assert_eq!(api_response.error_code.as_deref(), Some("MALFORMED_JSON"));
+        assert!(api_response.error.is_some());
+
+        let response = server
+            .post("/api/products")
+            .json(&serde_json::json!({ "description": "No name or price" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let api_response: ApiResponse<()> = response.json();
+        assert_eq!(api_response.error_code.as_deref(), Some("INVALID_JSON_BODY"));
+        assert!(api_response.error.unwrap().contains("missing field `name`"));
+    }
+
+    // Registers test@example.com, returning the matching login
+    async fn register_test_user(server: &TestServer) -> LoginInput {
+        let user_input = CreateUserInput {
+            email: "test@example.com".to_string(),
+            name: "Test User".to_string(),
+            password: "TestPassword123!".to_string(),
+        };
+        let response = server.post("/api/auth/register").json(&user_input).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        LoginInput {
+            email: user_input.email,
+            password: user_input.password,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_checks_registered_password() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let login_input = register_test_user(&server).await;
+        let response = server.post("/api/auth/login").json(&login_input).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let auth_response = response.json::<ApiResponse<AuthResponse>>().data.unwrap();
+        assert_eq!(auth_response.user.email, "test@example.com");
+        assert_eq!(auth_response.user.name, "Test User");
+
+        let wrong_password = LoginInput {
+            password: "WrongPassword123!".to_string(),
+            ..login_input.clone()
+        };
+        let response = server.post("/api/auth/login").json(&wrong_password).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.json::<ApiResponse<()>>().error_code.as_deref(), Some("INVALID_CREDENTIALS"));
+
+        // Registering the same email again is a conflict
+        let user_input = CreateUserInput {
+            email: login_input.email,
+            name: "Someone Else".to_string(),
+            password: login_input.password,
+        };
+        let response = server.post("/api/auth/register").json(&user_input).await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_login_uses_configured_token_expiry() {
+        let state = AppState::with_auth_config(AuthConfig {
+            token_expiry_hours: 1,
+            ..AuthConfig::default()
+        }).unwrap();
+        let auth_service = state.auth_service.clone();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let login_input = register_test_user(&server).await;
+        let response = server.post("/api/auth/login").json(&login_input).await;
+        let token = response.json::<ApiResponse<AuthResponse>>().data.unwrap().token;
+
+        let claims = auth_service.verify_token(&token).unwrap();
+        let expected_exp = chrono::Utc::now().timestamp() + 3600;
+        assert!((claims.exp - expected_exp).abs() <= 5);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_rotation() {
+        let state = AppState::new();
+        let auth_service = state.auth_service.clone();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let login_input = register_test_user(&server).await;
+        let response = server.post("/api/auth/login").json(&login_input).await;
+        let api_response: ApiResponse<AuthResponse> = response.json();
+        let refresh_token = api_response.data.unwrap().refresh_token;
+
+        let refresh_input = RefreshTokenInput { refresh_token: refresh_token.clone() };
+        let response = server.post("/api/auth/refresh").json(&refresh_input).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let refreshed = response.json::<ApiResponse<RefreshTokenResponse>>().data.unwrap();
+        assert_ne!(refreshed.refresh_token, refresh_token);
+        // The new access token is for the same user as the one from the login
+        let claims = auth_service.verify_token(&refreshed.token).unwrap();
+        assert_eq!(claims.email, "test@example.com");
+        assert_eq!(claims.name, "Test User");
+
+        // The rotated token must not be accepted a second time
+        let response = server.post("/api/auth/refresh").json(&refresh_input).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_account_locked_after_repeated_failed_logins() {
+        let state = AppState::with_auth_config(AuthConfig {
+            max_failed_logins: 3,
+            ..AuthConfig::default()
+        }).unwrap();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let wrong_password = LoginInput {
+            email: "locked@example.com".to_string(),
+            password: "wrong".to_string(),
+        };
+        for _ in 0..3 {
+            let response = server.post("/api/v1/auth/login").json(&wrong_password).await;
+            assert_eq!(response.json::<ApiResponse<()>>().error_code.as_deref(), Some("INVALID_CREDENTIALS"));
+        }
+
+        // Even the right password is refused while the account is locked
+        let right_password = LoginInput {
+            password: "TestPassword123!".to_string(),
+            ..wrong_password
+        };
+        let response = server.post("/api/v1/auth/login").json(&right_password).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.json::<ApiResponse<()>>().error_code.as_deref(), Some("ACCOUNT_LOCKED"));
+        assert_eq!(response.header("Retry-After"), "3600");
+    }
+
+    #[tokio::test]
+    async fn test_login_rate_limited_after_failed_attempts() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let login_input = |email: &str| LoginInput {
+            email: email.to_string(),
+            password: "wrong".to_string(),
+        };
+
+        // Differently cased spellings of the email share one limit
+        for email in ["brute@example.com", "Brute@example.com", "BRUTE@example.com", "brute@EXAMPLE.com", " brute@example.com"] {
+            let response = server.post("/api/auth/login").json(&login_input(email)).await;
+            assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        }
+
+        let response = server.post("/api/auth/login").json(&login_input("bRuTe@example.com")).await;
+        assert_eq!(response.status_code(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_create_product_then_fetch() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let input = CreateProductInput {
+            name: "New Product".to_string(),
+            description: Some("Freshly created".to_string()),
+            price: 42.0,
+        };
+        let response = server.post("/api/products").json(&input).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let created = response.json::<ApiResponse<Product>>().data.unwrap();
+
+        let response = server.get(&format!("/api/products/{}", created.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let fetched = response.json::<ApiResponse<Product>>().data.unwrap();
+        assert_eq!(fetched.name, "New Product");
+        assert_eq!(fetched.shopify_id, created.shopify_id);
+    }
+
+    #[tokio::test]
+    async fn test_delete_product() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let path = format!("/api/products/{}", product_uuid_from_shopify_id(1));
+        let response = server.delete(&path).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let api_response: ApiResponse<bool> = response.json();
+        assert_eq!(api_response.data, Some(true));
+
+        let response = server.delete(&path).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_product() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.delete(&format!("/api/products/{}", Uuid::new_v4())).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_unknown_product_returns_error_body() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get(&format!("/api/products/{}", Uuid::new_v4())).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let api_response: ApiResponse<()> = response.json();
+        assert!(!api_response.success);
+        assert_eq!(api_response.error_code.as_deref(), Some("PRODUCT_NOT_FOUND"));
+        assert_eq!(api_response.error.as_deref(), Some("Product not found"));
+    }
+
+    #[tokio::test]
+    async fn test_update_product_partial() {
+        let state = AppState::new();
+        let app = create_router(&state).with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let path = format!("/api/products/{}", product_uuid_from_shopify_id(1));
+        let response = server
+            .put(&path)
+            .json(&serde_json::json!({ "name": "Renamed Product", "price": 12.5 }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let product = response.json::<ApiResponse<Product>>().data.unwrap();
+        assert_eq!(product.name, "Renamed Product");
+        assert_eq!(product.price, 12.5);
+        assert_eq!(product.description, Some("<p>This is a demo product</p>".to_string()));
+
+        // An empty update returns the product unchanged
+        let response = server.put(&path).json(&serde_json::json!({})).await;
+        let product = response.json::<ApiResponse<Product>>().data.unwrap();
+        assert_eq!(product.name, "Renamed Product");
     }
 }
@@ -0,0 +1,60 @@
+// Records the git commit, build time and rustc version that benchmark reports
+// are stamped with (see `BuildInfo`), and the locked versions of the web stack
+// reported by `/version`. Missing git info is a warning, not an error.
+use std::path::Path;
+use vergen::EmitBuilder;
+
+// Dependencies whose resolved versions are exposed as `LOCKED_<NAME>_VERSION`
+const LOCKED_DEPENDENCIES: &[&str] = &["axum", "async-graphql"];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    EmitBuilder::builder()
+        .build_timestamp()
+        .git_sha(false)
+        .rustc_semver()
+        .emit()?;
+
+    let lockfile = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+    let lockfile: toml::Table = std::fs::read_to_string(lockfile)
+        .ok()
+        .and_then(|lockfile| lockfile.parse().ok())
+        .unwrap_or_default();
+
+    for name in LOCKED_DEPENDENCIES {
+        let env_name = format!("LOCKED_{}_VERSION", name.replace('-', "_").to_uppercase());
+        if let Some(version) = locked_version(&lockfile, name) {
+            println!("cargo:rustc-env={}={}", env_name, version);
+        }
+    }
+
+    Ok(())
+}
+
+// The locked version of `name` as this crate depends on it. Cargo.lock lists a
+// dependency as `name`, or as `name version` when several versions are locked.
+fn locked_version(lockfile: &toml::Table, name: &str) -> Option<String> {
+    let packages = lockfile.get("package")?.as_array()?;
+    let this_crate = packages
+        .iter()
+        .find(|package| field(package, "name") == Some(env!("CARGO_PKG_NAME")))?;
+    let dependency = this_crate
+        .get("dependencies")?
+        .as_array()?
+        .iter()
+        .filter_map(toml::Value::as_str)
+        .find(|dependency| dependency.split(' ').next() == Some(name))?;
+
+    match dependency.split(' ').nth(1) {
+        Some(version) => Some(version.to_string()),
+        None => packages
+            .iter()
+            .find(|package| field(package, "name") == Some(name))
+            .and_then(|package| field(package, "version"))
+            .map(str::to_string),
+    }
+}
+
+fn field<'a>(package: &'a toml::Value, key: &str) -> Option<&'a str> {
+    package.get(key).and_then(toml::Value::as_str)
+}
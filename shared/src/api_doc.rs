@@ -0,0 +1,64 @@
+use utoipa::OpenApi;
+
+/// The parts of the REST API's OpenAPI document that don't depend on the
+/// handlers. Each server derives a document for its own handlers' paths and
+/// combines the two with `rest_api_doc`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AXUM vs LOCO demo API",
+        version = "1.0.0",
+        description = "REST endpoints shared by the AXUM and LOCO servers",
+    ),
+    tags(
+        (name = "products", description = "Product catalogue backed by Shopify"),
+        (name = "auth", description = "Registration, login and tokens"),
+        (name = "orders", description = "Orders for the authenticated user"),
+        (name = "admin", description = "Operational endpoints for admins"),
+    )
+)]
+pub struct RestApiDoc;
+
+/// Every documented operation as (method, path). Both servers check their
+/// document against this list, so neither can drift from the other.
+pub const REST_API_OPERATIONS: [(&str, &str); 16] = [
+    ("get", "/api/v1/products"),
+    ("post", "/api/v1/products"),
+    ("post", "/api/v1/products/bulk"),
+    ("get", "/api/v1/products/stream"),
+    ("get", "/api/v1/products/{id}"),
+    ("put", "/api/v1/products/{id}"),
+    ("delete", "/api/v1/products/{id}"),
+    ("post", "/api/v1/auth/register"),
+    ("post", "/api/v1/auth/login"),
+    ("post", "/api/v1/auth/refresh"),
+    ("post", "/api/v1/auth/logout"),
+    ("get", "/api/v1/users/me"),
+    ("get", "/api/v1/orders"),
+    ("post", "/api/v1/orders"),
+    ("get", "/api/v1/orders/{id}"),
+    ("get", "/api/v1/admin/sessions"),
+];
+
+/// `RestApiDoc` with a server's `paths` and the schemas they use merged in
+pub fn rest_api_doc(paths: utoipa::openapi::OpenApi) -> utoipa::openapi::OpenApi {
+    let mut doc = RestApiDoc::openapi();
+    doc.merge(paths);
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_doc_keeps_info_and_tags() {
+        #[derive(OpenApi)]
+        #[openapi(info(title = "Paths only"))]
+        struct PathsOnly;
+
+        let doc = rest_api_doc(PathsOnly::openapi());
+        assert_eq!(doc.info.title, "AXUM vs LOCO demo API");
+        assert_eq!(doc.tags.map(|tags| tags.len()), Some(4));
+    }
+}
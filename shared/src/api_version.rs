@@ -0,0 +1,25 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// Prefix the current REST routes are nested under
+pub const API_V1_PREFIX: &str = "/api/v1";
+
+/// Marks responses from the unversioned `/api/*` routes as deprecated and points
+/// at the `/api/v1` equivalent. Must be layered on the router nested at `/api`,
+/// where the request path no longer includes the prefix.
+pub async fn deprecated_api_middleware(req: Request, next: Next) -> Response {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", API_V1_PREFIX, req.uri().path());
+    let mut response = next.run(req).await;
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(header::LINK, link);
+    }
+
+    response
+}
@@ -2,10 +2,20 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::time::Instant;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use anyhow::Result;
+use axum::extract::ws::{Message, WebSocket};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
 
-use crate::models::BenchmarkResult;
+use crate::models::{
+    ApiResponse, AuthResponse, BenchmarkResult, CreateUserInput, EndpointStats, ErrorSummary, HistogramBucket, LoginInput,
+    RefreshTokenInput, RefreshTokenResponse,
+};
+use crate::system_metrics::{sample_process, ProcessStats};
 
 #[derive(Debug, Error)]
 pub enum BenchmarkError {
@@ -17,17 +27,87 @@ pub enum BenchmarkError {
     InvalidConfig,
     #[error("Benchmark execution failed: {0}")]
     ExecutionFailed(String),
+    #[error("Benchmark login failed: {0}")]
+    AuthFailed(String),
 }
 
+pub const LOGIN_PATH: &str = "/api/v1/auth/login";
+pub const BENCHMARK_USER_AGENT: &str = concat!("axum-loco-benchmarks/", env!("CARGO_PKG_VERSION"));
+pub const REFRESH_PATH: &str = "/api/v1/auth/refresh";
+pub const REGISTER_PATH: &str = "/api/v1/auth/register";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkConfig {
     pub target_url: String,
     pub concurrent_users: u32,
     pub duration_seconds: u64,
     pub ramp_up_seconds: u64,
+    // Fixes the endpoint selection sequence so runs can be reproduced
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // Pause each virtual user takes between requests. Defaults to none: results
+    // recorded before this was configurable slept a fixed 10ms, which capped
+    // every user at ~100 req/s and understated throughput.
+    #[serde(default)]
+    pub think_time: ThinkTime,
+    // Requests sent during the first `warmup_seconds` are discarded so cold-start
+    // effects don't skew the results; the run lasts warmup + duration
+    #[serde(default)]
+    pub warmup_seconds: u64,
+    // Account logged in before the run when any endpoint `requires_auth`
+    #[serde(default)]
+    pub credentials: BenchmarkCredentials,
+    // Sent with every request; an endpoint's own `headers` win on conflict.
+    // Requests identify themselves with `BENCHMARK_USER_AGENT` unless a
+    // `User-Agent` is given here or on the endpoint.
+    #[serde(default)]
+    pub global_headers: HashMap<String, String>,
     pub endpoints: Vec<EndpointConfig>,
 }
 
+/// How long a virtual user pauses between requests, in milliseconds. Real users
+/// don't send requests at a fixed cadence, so the pause can be drawn at random.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ThinkTime {
+    #[default]
+    None,
+    Fixed(u64),
+    // Anywhere from the first to the second value, inclusive
+    Uniform(u64, u64),
+    // Exponentially distributed around this mean, like the gaps between
+    // independent arrivals
+    Exponential(u64),
+}
+
+impl ThinkTime {
+    pub fn sample(&self, rng: &mut impl Rng) -> std::time::Duration {
+        let ms = match *self {
+            ThinkTime::None => 0.0,
+            ThinkTime::Fixed(ms) => ms as f64,
+            ThinkTime::Uniform(min, max) if max <= min => min as f64,
+            ThinkTime::Uniform(min, max) => rng.gen_range(min..=max) as f64,
+            // Inverse transform sampling; 1 - u is in (0, 1], so the log is finite
+            ThinkTime::Exponential(mean) => -(mean as f64) * (1.0 - rng.gen::<f64>()).ln(),
+        };
+        std::time::Duration::from_secs_f64(ms / 1000.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkCredentials {
+    pub email: String,
+    pub password: String,
+}
+
+impl Default for BenchmarkCredentials {
+    fn default() -> Self {
+        Self {
+            email: "benchmark@example.com".to_string(),
+            password: "BenchmarkPass123!".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndpointConfig {
     pub path: String,
@@ -35,6 +115,56 @@ pub struct EndpointConfig {
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
     pub weight: f32, // Probability weight for this endpoint
+    // Sent with the bearer token acquired by logging in before the run
+    #[serde(default)]
+    pub requires_auth: bool,
+    // Sent as the JSON body instead of `body` when set
+    #[serde(default)]
+    pub graphql: Option<GraphQLOperation>,
+}
+
+/// A GraphQL request for a benchmark endpoint, so scenarios can exercise
+/// parameterized queries rather than only literal query strings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLOperation {
+    pub query: String,
+    #[serde(default)]
+    pub variables: Option<serde_json::Value>,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQLOperation {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            variables: None,
+            operation_name: None,
+        }
+    }
+
+    pub fn with_variables(mut self, variables: serde_json::Value) -> Self {
+        self.variables = Some(variables);
+        self
+    }
+
+    pub fn with_operation_name(mut self, operation_name: impl Into<String>) -> Self {
+        self.operation_name = Some(operation_name.into());
+        self
+    }
+
+    /// The `{query, variables, operationName}` body a GraphQL server expects,
+    /// leaving out whatever isn't set
+    pub fn request_body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({ "query": self.query });
+        if let Some(variables) = &self.variables {
+            body["variables"] = variables.clone();
+        }
+        if let Some(operation_name) = &self.operation_name {
+            body["operationName"] = serde_json::Value::String(operation_name.clone());
+        }
+        body
+    }
 }
 
 impl Default for BenchmarkConfig {
@@ -44,6 +174,11 @@ impl Default for BenchmarkConfig {
             concurrent_users: 100,
             duration_seconds: 60,
             ramp_up_seconds: 10,
+            seed: None,
+            think_time: ThinkTime::None,
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
             endpoints: vec![
                 EndpointConfig {
                     path: "/health".to_string(),
@@ -51,24 +186,26 @@ impl Default for BenchmarkConfig {
                     headers: HashMap::new(),
                     body: None,
                     weight: 0.3,
+                    requires_auth: false,
+                    graphql: None,
                 },
                 EndpointConfig {
-                    path: "/api/products".to_string(),
+                    path: "/api/v1/products".to_string(),
                     method: "GET".to_string(),
                     headers: HashMap::new(),
                     body: None,
                     weight: 0.4,
+                    requires_auth: false,
+                    graphql: None,
                 },
                 EndpointConfig {
-                    path: "/api/users/me".to_string(),
+                    path: "/api/v1/users/me".to_string(),
                     method: "GET".to_string(),
-                    headers: {
-                        let mut headers = HashMap::new();
-                        headers.insert("Authorization".to_string(), "Bearer demo-token".to_string());
-                        headers
-                    },
+                    headers: HashMap::new(),
                     body: None,
                     weight: 0.2,
+                    requires_auth: true,
+                    graphql: None,
                 },
                 EndpointConfig {
                     path: "/graphql".to_string(),
@@ -80,6 +217,8 @@ impl Default for BenchmarkConfig {
                     },
                     body: Some(r#"{"query":"query { health }"}"#.to_string()),
                     weight: 0.1,
+                    requires_auth: false,
+                    graphql: None,
                 },
             ],
         }
@@ -94,14 +233,34 @@ pub struct RequestMetrics {
     pub response_size: usize,
     pub endpoint: String,
     pub success: bool,
+    // Start of the response body for non-2xx responses, at most
+    // `MAX_ERROR_BODY_BYTES`; successful responses are never read
+    pub error_body: Option<String>,
 }
 
 impl RequestMetrics {
+    // Fractional, since in-process requests usually finish in under a millisecond
     pub fn duration_ms(&self) -> f64 {
-        self.end_time.duration_since(self.start_time).as_millis() as f64
+        self.end_time.duration_since(self.start_time).as_secs_f64() * 1000.0
     }
 }
 
+pub const MAX_ERROR_BODY_BYTES: usize = 512;
+// How many distinct error bodies a result keeps for the report
+const TOP_ERRORS: usize = 5;
+// Below this a framework's throughput and latency are flagged in the report,
+// since failing fast can look like winning
+pub const SUCCESS_RATE_WARNING_THRESHOLD: f64 = 99.0;
+
+/// Cuts `body` down to `MAX_ERROR_BODY_BYTES`, backing off to a char boundary
+pub fn truncate_error_body(body: &str) -> String {
+    let mut end = body.len().min(MAX_ERROR_BODY_BYTES);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    body[..end].to_string()
+}
+
 #[derive(Debug, Clone)]
 pub struct BenchmarkMetrics {
     pub framework: String,
@@ -113,6 +272,8 @@ pub struct BenchmarkMetrics {
     pub total_bytes_received: u64,
     pub request_metrics: Vec<RequestMetrics>,
     pub error_counts: HashMap<String, u32>,
+    // Set when the run was cancelled before its configured duration
+    pub interrupted: bool,
 }
 
 impl BenchmarkMetrics {
@@ -127,6 +288,7 @@ impl BenchmarkMetrics {
             total_bytes_received: 0,
             request_metrics: Vec::new(),
             error_counts: HashMap::new(),
+            interrupted: false,
         }
     }
 
@@ -154,6 +316,10 @@ impl BenchmarkMetrics {
     }
 
     pub fn requests_per_second(&self) -> f64 {
+        // A run interrupted during its warmup measured nothing
+        if self.duration_seconds() <= 0.0 {
+            return 0.0;
+        }
         self.total_requests as f64 / self.duration_seconds()
     }
 
@@ -170,22 +336,134 @@ impl BenchmarkMetrics {
         total_time / self.request_metrics.len() as f64
     }
 
-    pub fn percentile_response_time_ms(&self, percentile: f64) -> f64 {
+    pub fn min_response_time_ms(&self) -> f64 {
+        self.sorted_durations_ms().first().copied().unwrap_or(0.0)
+    }
+
+    pub fn max_response_time_ms(&self) -> f64 {
+        self.sorted_durations_ms().last().copied().unwrap_or(0.0)
+    }
+
+    /// Population standard deviation of the response times
+    pub fn stddev_response_time_ms(&self) -> f64 {
         if self.request_metrics.is_empty() {
             return 0.0;
         }
 
+        let mean = self.average_response_time_ms();
+        let variance = self.request_metrics
+            .iter()
+            .map(|m| (m.duration_ms() - mean).powi(2))
+            .sum::<f64>()
+            / self.request_metrics.len() as f64;
+
+        variance.sqrt()
+    }
+
+    /// Splits the min..max response time range into `bucket_count` equal buckets
+    /// and returns `(bucket_low, bucket_high, count)` for each one.
+    pub fn response_time_histogram(&self, bucket_count: usize) -> Vec<(f64, f64, u64)> {
+        let durations = self.sorted_durations_ms();
+        let (Some(&min), Some(&max)) = (durations.first(), durations.last()) else {
+            return Vec::new();
+        };
+        if bucket_count == 0 {
+            return Vec::new();
+        }
+        if min == max {
+            return vec![(min, max, durations.len() as u64)];
+        }
+
+        let width = (max - min) / bucket_count as f64;
+        let mut counts = vec![0u64; bucket_count];
+        for duration in durations {
+            // The maximum lands exactly on the upper edge, so fold it into the last bucket
+            let index = (((duration - min) / width) as usize).min(bucket_count - 1);
+            counts[index] += 1;
+        }
+
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| (min + width * i as f64, min + width * (i + 1) as f64, count))
+            .collect()
+    }
+
+    /// Nearest-rank percentile: the smallest response time that at least
+    /// `percentile`% of requests completed within.
+    pub fn percentile_response_time_ms(&self, percentile: f64) -> f64 {
+        nearest_rank(&self.sorted_durations_ms(), percentile)
+    }
+
+    /// Computes several percentiles in one pass, keyed like `"p50"` or `"p99.9"`.
+    pub fn percentiles(&self, ps: &[f64]) -> HashMap<String, f64> {
+        let durations = self.sorted_durations_ms();
+
+        ps.iter()
+            .map(|&p| (format!("p{}", p), nearest_rank(&durations, p)))
+            .collect()
+    }
+
+    fn sorted_durations_ms(&self) -> Vec<f64> {
         let mut durations: Vec<f64> = self.request_metrics
             .iter()
             .map(|m| m.duration_ms())
             .collect();
-        
-        durations.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        
-        let index = ((percentile / 100.0) * durations.len() as f64) as usize;
-        let clamped_index = index.min(durations.len() - 1);
-        
-        durations[clamped_index]
+
+        durations.sort_by(|a, b| a.total_cmp(b));
+        durations
+    }
+
+    /// The same numbers as the aggregate ones, computed separately for each
+    /// endpoint path.
+    pub fn per_endpoint_stats(&self) -> HashMap<String, EndpointStats> {
+        let mut by_endpoint: HashMap<&str, (Vec<f64>, u64)> = HashMap::new();
+        for request in &self.request_metrics {
+            let (durations, failures) = by_endpoint.entry(request.endpoint.as_str()).or_default();
+            durations.push(request.duration_ms());
+            if !request.success {
+                *failures += 1;
+            }
+        }
+
+        by_endpoint
+            .into_iter()
+            .map(|(endpoint, (mut durations, failures))| {
+                durations.sort_by(|a, b| a.total_cmp(b));
+                let count = durations.len() as u64;
+
+                let stats = EndpointStats {
+                    count,
+                    average_response_time_ms: durations.iter().sum::<f64>() / count as f64,
+                    p95_response_time_ms: nearest_rank(&durations, 95.0),
+                    p99_response_time_ms: nearest_rank(&durations, 99.0),
+                    error_rate_percent: failures as f64 / count as f64 * 100.0,
+                };
+                (endpoint.to_string(), stats)
+            })
+            .collect()
+    }
+
+    /// Most frequent `(status, body)` pairs among failed requests, most common first
+    pub fn top_errors(&self, limit: usize) -> Vec<ErrorSummary> {
+        let mut counts: HashMap<(u16, &str), u64> = HashMap::new();
+        for request in &self.request_metrics {
+            if let Some(body) = &request.error_body {
+                *counts.entry((request.status_code, body.as_str())).or_insert(0) += 1;
+            }
+        }
+
+        let mut errors: Vec<ErrorSummary> = counts
+            .into_iter()
+            .map(|((status_code, body), count)| ErrorSummary {
+                status_code,
+                body: body.to_string(),
+                count,
+            })
+            .collect();
+        errors.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.body.cmp(&b.body)));
+        errors.truncate(limit);
+        errors
     }
 
     pub fn success_rate(&self) -> f64 {
@@ -196,41 +474,635 @@ impl BenchmarkMetrics {
     }
 
     pub fn throughput_mb_per_second(&self) -> f64 {
+        if self.duration_seconds() <= 0.0 {
+            return 0.0;
+        }
         let mb = self.total_bytes_received as f64 / (1024.0 * 1024.0);
         mb / self.duration_seconds()
     }
 
-    pub fn to_benchmark_result(&self, test_name: String) -> BenchmarkResult {
+    /// `process_stats` should only be supplied when the benchmarked server runs in
+    /// this process; remote targets can't be sampled and report 0.0.
+    pub fn to_benchmark_result(&self, test_name: String, process_stats: Option<ProcessStats>) -> BenchmarkResult {
+        let process_stats = process_stats.unwrap_or_default();
+
         BenchmarkResult {
             framework: self.framework.clone(),
             test_name,
             requests_per_second: self.requests_per_second(),
             average_response_time_ms: self.average_response_time_ms(),
+            min_response_time_ms: self.min_response_time_ms(),
+            max_response_time_ms: self.max_response_time_ms(),
+            stddev_response_time_ms: self.stddev_response_time_ms(),
             p95_response_time_ms: self.percentile_response_time_ms(95.0),
             p99_response_time_ms: self.percentile_response_time_ms(99.0),
-            memory_usage_mb: 0.0, // Would need system monitoring
-            cpu_usage_percent: 0.0, // Would need system monitoring
+            memory_usage_mb: process_stats.memory_usage_mb,
+            cpu_usage_percent: process_stats.cpu_usage_percent,
+            response_time_histogram: self
+                .response_time_histogram(HISTOGRAM_BUCKETS)
+                .into_iter()
+                .map(|(low_ms, high_ms, count)| HistogramBucket { low_ms, high_ms, count })
+                .collect(),
+            endpoint_stats: self.per_endpoint_stats(),
+            top_errors: self.top_errors(TOP_ERRORS),
+            success_rate: self.success_rate(),
+            error_counts: self.error_counts.clone(),
+            interrupted: self.interrupted,
             timestamp: Utc::now(),
         }
     }
 }
 
+const HISTOGRAM_BUCKETS: usize = 10;
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+/// Renders histogram buckets as fixed-width ASCII bars scaled to the largest bucket.
+pub fn render_ascii_histogram(buckets: &[HistogramBucket]) -> String {
+    let max_count = buckets.iter().map(|b| b.count).max().unwrap_or(0);
+    let mut output = String::new();
+
+    for bucket in buckets {
+        let bar_len = if max_count == 0 {
+            0
+        } else {
+            (bucket.count as f64 / max_count as f64 * HISTOGRAM_BAR_WIDTH as f64).round() as usize
+        };
+        output.push_str(&format!(
+            "{:>9.2} - {:>9.2} ms | {:<bar_width$} {}\n",
+            bucket.low_ms,
+            bucket.high_ms,
+            "#".repeat(bar_len),
+            bucket.count,
+            bar_width = HISTOGRAM_BAR_WIDTH
+        ));
+    }
+
+    output
+}
+
+fn push_histogram(report: &mut String, result: &BenchmarkResult) {
+    if result.response_time_histogram.is_empty() {
+        return;
+    }
+
+    report.push_str("\n```\n");
+    report.push_str(&render_ascii_histogram(&result.response_time_histogram));
+    report.push_str("```\n");
+}
+
+fn push_endpoint_breakdown(report: &mut String, result: &BenchmarkResult) {
+    if result.endpoint_stats.is_empty() {
+        return;
+    }
+
+    let mut endpoints: Vec<_> = result.endpoint_stats.iter().collect();
+    endpoints.sort_by(|a, b| a.0.cmp(b.0));
+
+    report.push_str("\n| Endpoint | Requests | Avg (ms) | P95 (ms) | P99 (ms) | Errors |\n");
+    report.push_str("|---|---|---|---|---|---|\n");
+    for (endpoint, stats) in endpoints {
+        report.push_str(&format!(
+            "| {} | {} | {:.2} | {:.2} | {:.2} | {:.1}% |\n",
+            endpoint,
+            stats.count,
+            stats.average_response_time_ms,
+            stats.p95_response_time_ms,
+            stats.p99_response_time_ms,
+            stats.error_rate_percent
+        ));
+    }
+}
+
+// Most frequent status codes first, e.g. "HTTP_500 × 12, HTTP_0 × 3"
+fn format_error_counts(error_counts: &HashMap<String, u32>) -> String {
+    let mut counts: Vec<_> = error_counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    counts
+        .into_iter()
+        .take(TOP_ERRORS)
+        .map(|(code, count)| format!("{} × {}", code, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A highlighted sentence of a comparison report, such as the throughput
+/// winner, kept format-neutral so the markdown and HTML reports agree
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportNote {
+    pub icon: &'static str,
+    // Emphasized in the report
+    pub headline: String,
+    // Follows the headline directly, including any leading punctuation
+    pub detail: String,
+}
+
+impl ReportNote {
+    fn to_markdown(&self) -> String {
+        format!("{} **{}**{}", self.icon, self.headline, self.detail)
+    }
+}
+
+fn success_rate_warning(label: &str, result: &BenchmarkResult) -> Option<ReportNote> {
+    if result.success_rate >= SUCCESS_RATE_WARNING_THRESHOLD {
+        return None;
+    }
+
+    Some(ReportNote {
+        icon: "⚠️",
+        headline: format!("{} success rate is {:.1}%", label, result.success_rate),
+        detail: format!(
+            ", below {:.0}%; its throughput and response times include failed requests ({})",
+            SUCCESS_RATE_WARNING_THRESHOLD,
+            format_error_counts(&result.error_counts)
+        ),
+    })
+}
+
+// Which of two averages wins a metric, and by how much relative to the loser.
+// `values` formats the winning and losing averages for the note.
+fn winner_note(
+    icon: &'static str,
+    metric: &str,
+    (axum, loco): (f64, f64),
+    higher_wins: bool,
+    values: fn(f64, f64) -> String,
+) -> ReportNote {
+    let axum_wins = if higher_wins { axum > loco } else { axum < loco };
+    let (winner, winning, losing) = if axum_wins { ("AXUM", axum, loco) } else { ("LOCO", loco, axum) };
+
+    ReportNote {
+        icon,
+        headline: format!("{} wins in {}", winner, metric),
+        detail: format!(" by {:.1}% ({})", (winning - losing).abs() / losing * 100.0, values(winning, losing)),
+    }
+}
+
+// Lists the most common failure bodies for each run that had any
+fn push_error_analysis(report: &mut String, axum_results: &[BenchmarkResult], loco_results: &[BenchmarkResult]) {
+    let failing: Vec<&BenchmarkResult> = axum_results
+        .iter()
+        .chain(loco_results)
+        .filter(|result| !result.top_errors.is_empty())
+        .collect();
+    if failing.is_empty() {
+        return;
+    }
+
+    report.push_str("## Error Analysis\n\n");
+    for result in failing {
+        report.push_str(&format!("**{} - {}**\n", result.framework, result.test_name));
+        for error in &result.top_errors {
+            // Keep each body on one line and inside its code span
+            let body = error.body.replace(['\n', '\r'], " ").replace('`', "'");
+            report.push_str(&format!("- {} × HTTP {}: `{}`\n", error.count, error.status_code, body));
+        }
+        report.push('\n');
+    }
+}
+
+// Returns 0.0 for an empty sample so reports don't have to special-case it
+fn nearest_rank(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// (header, column width) for the summary table; every row is padded to these
+// widths so the raw markdown stays aligned
+pub const SUMMARY_COLUMNS: [(&str, usize); 9] = [
+    ("Framework", 9),
+    ("Avg RPS", 10),
+    ("Avg (ms)", 9),
+    ("Min (ms)", 9),
+    ("Max (ms)", 9),
+    ("Std Dev (ms)", 12),
+    ("P95 (ms)", 9),
+    ("P99 (ms)", 9),
+    ("Success %", 9),
+];
+
+fn summary_header() -> String {
+    let header: Vec<String> = SUMMARY_COLUMNS
+        .iter()
+        .map(|(name, width)| format!(" {:<width$} ", name, width = width))
+        .collect();
+    let separator: Vec<String> = SUMMARY_COLUMNS
+        .iter()
+        .map(|(_, width)| "-".repeat(width + 2))
+        .collect();
+
+    format!("|{}|\n|{}|\n", header.join("|"), separator.join("|"))
+}
+
+fn interrupted_marker(result: &BenchmarkResult) -> &'static str {
+    if result.interrupted {
+        " (interrupted, partial results)"
+    } else {
+        ""
+    }
+}
+
+/// The summary table's cells after the framework label, in `SUMMARY_COLUMNS` order
+pub fn summary_values(result: &BenchmarkResult) -> [f64; 8] {
+    [
+        result.requests_per_second,
+        result.average_response_time_ms,
+        result.min_response_time_ms,
+        result.max_response_time_ms,
+        result.stddev_response_time_ms,
+        result.p95_response_time_ms,
+        result.p99_response_time_ms,
+        result.success_rate,
+    ]
+}
+
+fn summary_row(label: &str, result: &BenchmarkResult) -> String {
+    let mut cells = vec![format!(" {:<width$} ", label, width = SUMMARY_COLUMNS[0].1)];
+    cells.extend(
+        summary_values(result)
+            .iter()
+            .zip(&SUMMARY_COLUMNS[1..])
+            .map(|(value, (_, width))| format!(" {:>width$.2} ", value, width = width)),
+    );
+
+    format!("|{}|\n", cells.join("|"))
+}
+
 pub struct LoadTester {
     client: reqwest::Client,
     config: BenchmarkConfig,
+    // Set for in-process runs, which call the router instead of sending
+    // requests over the network
+    router: Option<axum::Router>,
+    progress: Arc<BenchmarkProgress>,
+}
+
+// How often progress is reported while a benchmark runs
+pub const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Running totals for the current benchmark run, updated by the virtual users
+/// as they go so the run can be watched before it finishes. Unlike the
+/// finished metrics, these include warmup requests.
+#[derive(Debug)]
+pub struct BenchmarkProgress {
+    started: Mutex<Instant>,
+    requests: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Default for BenchmarkProgress {
+    fn default() -> Self {
+        Self {
+            started: Mutex::new(Instant::now()),
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+}
+
+/// A snapshot of `BenchmarkProgress`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    pub elapsed_seconds: f64,
+    pub requests: u64,
+    pub errors: u64,
+    // Requests per second since the previous update
+    pub current_rps: f64,
+}
+
+impl BenchmarkProgress {
+    fn reset(&self) {
+        *self.started.lock().unwrap() = Instant::now();
+        self.requests.store(0, Ordering::Relaxed);
+        self.errors.store(0, Ordering::Relaxed);
+    }
+
+    fn record(&self, success: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Totals so far, with the request rate since `previous`, or since the
+    /// run started for the first update
+    pub fn update(&self, previous: Option<&ProgressUpdate>) -> ProgressUpdate {
+        let elapsed_seconds = self.started.lock().unwrap().elapsed().as_secs_f64();
+        let requests = self.requests.load(Ordering::Relaxed);
+        let errors = self.errors.load(Ordering::Relaxed);
+
+        let (previous_requests, previous_seconds) = previous.map_or((0, 0.0), |p| (p.requests, p.elapsed_seconds));
+        let window = elapsed_seconds - previous_seconds;
+        let current_rps = if window > 0.0 {
+            requests.saturating_sub(previous_requests) as f64 / window
+        } else {
+            0.0
+        };
+
+        ProgressUpdate { elapsed_seconds, requests, errors, current_rps }
+    }
+}
+
+/// What `stream_benchmark` sends over the WebSocket, as JSON tagged by `type`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BenchmarkStreamMessage {
+    Progress(ProgressUpdate),
+    // Boxed so progress messages stay small
+    Completed(Box<BenchmarkResult>),
+    Failed { error: String },
+}
+
+/// Runs `load_tester`, sending a progress update over `socket` every
+/// `PROGRESS_INTERVAL` and then the result. The run is cancelled once the
+/// client goes away.
+pub async fn stream_benchmark(mut socket: WebSocket, load_tester: LoadTester, framework: &str, test_name: &str) {
+    let cancel = CancellationToken::new();
+    let run = load_tester.run_benchmark_until(framework.to_string(), cancel.clone());
+    tokio::pin!(run);
+
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + PROGRESS_INTERVAL, PROGRESS_INTERVAL);
+    let mut previous = None;
+    let outcome = loop {
+        tokio::select! {
+            outcome = &mut run => break outcome,
+            _ = ticker.tick() => {
+                let update = load_tester.progress.update(previous.as_ref());
+                if send_stream_message(&mut socket, &BenchmarkStreamMessage::Progress(update)).await.is_err() {
+                    cancel.cancel();
+                    return;
+                }
+                previous = Some(update);
+            }
+        }
+    };
+
+    let message = match outcome {
+        Ok(metrics) => {
+            BenchmarkStreamMessage::Completed(Box::new(metrics.to_benchmark_result(test_name.to_string(), Some(sample_process()))))
+        }
+        Err(e) => BenchmarkStreamMessage::Failed { error: e.to_string() },
+    };
+    if send_stream_message(&mut socket, &message).await.is_ok() {
+        let _ = socket.send(Message::Close(None)).await;
+    }
+}
+
+async fn send_stream_message(socket: &mut WebSocket, message: &BenchmarkStreamMessage) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(message).map_err(axum::Error::new)?;
+    socket.send(Message::Text(json.into())).await
+}
+
+/// Query string of the servers' `/benchmark` endpoint
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct BenchmarkQuery {
+    // Drive the server's own router directly rather than over loopback TCP
+    #[serde(default)]
+    pub in_process: bool,
+    // Override the self-benchmark's concurrent users, duration and ramp-up
+    pub users: Option<u32>,
+    pub duration: Option<u64>,
+    pub ramp_up: Option<u64>,
+}
+
+// A response as the load tester sees it, whether it came over the network or
+// straight from an in-process router
+struct Reply {
+    status: u16,
+    received_at: Instant,
+    size: usize,
+    // Only read when asked for, or for non-2xx responses
+    body: Option<Vec<u8>>,
+}
+
+impl Reply {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends `request` over the network, or to `router` when there is one. The
+/// body is read when `read_body` is set or the response isn't a 2xx.
+async fn send(
+    router: Option<&axum::Router>,
+    request: reqwest::RequestBuilder,
+    read_body: bool,
+) -> Result<Reply, BenchmarkError> {
+    let Some(router) = router else {
+        let response = request.send().await?;
+        let mut reply = Reply {
+            status: response.status().as_u16(),
+            received_at: Instant::now(),
+            size: response.content_length().unwrap_or(0) as usize,
+            body: None,
+        };
+        if read_body || !reply.is_success() {
+            reply.body = response.bytes().await.ok().map(|body| body.to_vec());
+        }
+        return Ok(reply);
+    };
+
+    let response = match router.clone().oneshot(to_router_request(request.build()?)?).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let status = response.status().as_u16();
+    let received_at = Instant::now();
+    // Always drained, since a handler may still be producing the body
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| BenchmarkError::ExecutionFailed(e.to_string()))?;
+
+    let mut reply = Reply { status, received_at, size: body.len(), body: None };
+    if read_body || !reply.is_success() {
+        reply.body = Some(body.to_vec());
+    }
+    Ok(reply)
+}
+
+// reqwest and axum use different versions of the `http` crate, so the request
+// is rebuilt from its parts rather than converted
+fn to_router_request(request: reqwest::Request) -> Result<axum::extract::Request, BenchmarkError> {
+    let url = request.url();
+    let uri = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let mut builder = axum::http::Request::builder().method(request.method().as_str()).uri(uri);
+    for (name, value) in request.headers() {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    let body = request.body().and_then(|body| body.as_bytes()).map(<[u8]>::to_vec).unwrap_or_default();
+
+    builder
+        .body(axum::body::Body::from(body))
+        .map_err(|e| BenchmarkError::ExecutionFailed(e.to_string()))
+}
+
+/// Result of the single request `LoadTester::validate` sends to an endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointCheck {
+    pub method: String,
+    pub path: String,
+    // Response status, or why no response came back
+    pub outcome: std::result::Result<u16, String>,
+}
+
+impl EndpointCheck {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.outcome, Ok(status) if (200..300).contains(&status))
+    }
+}
+
+/// Bearer token shared by every virtual user, acquired by logging in before the
+/// run so authenticated endpoints measure real throughput rather than 401s.
+struct AuthSession {
+    client: reqwest::Client,
+    router: Option<axum::Router>,
+    target_url: String,
+    credentials: BenchmarkCredentials,
+    // (access token, refresh token)
+    tokens: tokio::sync::RwLock<(String, String)>,
+}
+
+impl AuthSession {
+    async fn login(
+        client: reqwest::Client,
+        router: Option<axum::Router>,
+        target_url: String,
+        credentials: BenchmarkCredentials,
+    ) -> Result<Self, BenchmarkError> {
+        let tokens = Self::request_login(&client, router.as_ref(), &target_url, &credentials).await?;
+
+        Ok(Self {
+            client,
+            router,
+            target_url,
+            credentials,
+            tokens: tokio::sync::RwLock::new(tokens),
+        })
+    }
+
+    async fn token(&self) -> String {
+        self.tokens.read().await.0.clone()
+    }
+
+    /// Replaces `rejected` after the server answered 401, e.g. because it expired
+    /// mid-run. Uses the refresh token, falling back to logging in again.
+    async fn refresh(&self, rejected: &str) -> Result<(), BenchmarkError> {
+        let mut tokens = self.tokens.write().await;
+        // Another virtual user may have refreshed it while we waited for the lock
+        if tokens.0 != rejected {
+            return Ok(());
+        }
+
+        let request = self
+            .client
+            .post(format!("{}{}", self.target_url, REFRESH_PATH))
+            .json(&RefreshTokenInput { refresh_token: tokens.1.clone() });
+        let refreshed = send(self.router.as_ref(), request, true).await?;
+        let body = refreshed.body.unwrap_or_default();
+        *tokens = match serde_json::from_slice::<ApiResponse<RefreshTokenResponse>>(&body).ok().and_then(|r| r.data) {
+            Some(response) => (response.token, response.refresh_token),
+            None => Self::request_login(&self.client, self.router.as_ref(), &self.target_url, &self.credentials).await?,
+        };
+
+        Ok(())
+    }
+
+    async fn request_login(
+        client: &reqwest::Client,
+        router: Option<&axum::Router>,
+        target_url: &str,
+        credentials: &BenchmarkCredentials,
+    ) -> Result<(String, String), BenchmarkError> {
+        let input = LoginInput {
+            email: credentials.email.clone(),
+            password: credentials.password.clone(),
+        };
+        let request = client.post(format!("{}{}", target_url, LOGIN_PATH)).json(&input);
+        let response = send(router, request, true).await?;
+
+        let status = response.status;
+        let body: ApiResponse<AuthResponse> = serde_json::from_slice(&response.body.unwrap_or_default())
+            .map_err(|e| BenchmarkError::AuthFailed(format!("{} ({})", status, e)))?;
+
+        match body.data {
+            Some(auth) => Ok((auth.token, auth.refresh_token)),
+            None => Err(BenchmarkError::AuthFailed(body.error.unwrap_or_else(|| status.to_string()))),
+        }
+    }
 }
 
 impl LoadTester {
     pub fn new(config: BenchmarkConfig) -> Self {
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
+            .user_agent(BENCHMARK_USER_AGENT)
+            .pool_max_idle_per_host(Self::pool_size(config.concurrent_users))
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, config }
+        Self {
+            client,
+            config,
+            router: None,
+            progress: Arc::new(BenchmarkProgress::default()),
+        }
+    }
+
+    /// Drives `router` directly instead of sending requests over the network,
+    /// so results measure the handlers and middleware without TCP overhead.
+    /// `target_url` is still used to build request URLs, but nothing connects
+    /// to it.
+    pub fn in_process(config: BenchmarkConfig, router: axum::Router) -> Self {
+        Self {
+            router: Some(router),
+            ..Self::new(config)
+        }
+    }
+
+    /// Counters for the run in progress, e.g. to report on it from another task
+    pub fn progress(&self) -> Arc<BenchmarkProgress> {
+        self.progress.clone()
+    }
+
+    // One idle connection per virtual user, so users don't queue for a
+    // connection or keep reconnecting between requests
+    fn pool_size(concurrent_users: u32) -> usize {
+        concurrent_users.max(1) as usize
+    }
+
+    fn check_config(&self) -> Result<(), BenchmarkError> {
+        // Zero users would also divide by zero when spreading out the ramp-up
+        if self.config.endpoints.is_empty() || self.config.concurrent_users == 0 {
+            return Err(BenchmarkError::InvalidConfig);
+        }
+        Ok(())
     }
 
     pub async fn run_benchmark(&self, framework_name: String) -> Result<BenchmarkMetrics, BenchmarkError> {
+        self.run_benchmark_until(framework_name, CancellationToken::new()).await
+    }
+
+    /// Like `run_benchmark`, but stops the virtual users as soon as `cancel` is
+    /// cancelled. Requests still in flight are dropped, and whatever was
+    /// measured up to then is returned flagged as `interrupted`.
+    pub async fn run_benchmark_until(
+        &self,
+        framework_name: String,
+        cancel: CancellationToken,
+    ) -> Result<BenchmarkMetrics, BenchmarkError> {
+        self.check_config()?;
+
+        let session = self.login_if_needed().await?.map(Arc::new);
+        self.progress.reset();
+
         let mut metrics = BenchmarkMetrics::new(framework_name);
         
         println!("🚀 Starting benchmark for {} framework", metrics.framework);
@@ -239,66 +1111,85 @@ impl LoadTester {
                  self.config.duration_seconds, 
                  self.config.ramp_up_seconds);
 
-        let _start_time = Instant::now();
+        let warmup = std::time::Duration::from_secs(self.config.warmup_seconds);
+        let measured_from = Instant::now() + warmup;
         let benchmark_duration = std::time::Duration::from_secs(self.config.duration_seconds);
+        let run_duration = warmup + benchmark_duration;
+
+        // Metrics only cover the measured window, so shift the start past the warmup
+        metrics.start_time = Utc::now() + chrono::Duration::seconds(self.config.warmup_seconds as i64);
         
         // Create tasks for concurrent users
         let mut tasks = Vec::new();
         
         for user_id in 0..self.config.concurrent_users {
             let client = self.client.clone();
+            let router = self.router.clone();
             let config = self.config.clone();
+            let session = session.clone();
             let user_start_delay = (self.config.ramp_up_seconds * 1000 / self.config.concurrent_users as u64) * user_id as u64;
+            let mut rng = Self::user_rng(self.config.seed, user_id);
+            let cancel = cancel.clone();
+            let progress = self.progress.clone();
             
             let task = tokio::spawn(async move {
+                let mut user_metrics = Vec::new();
+
                 // Ramp-up delay
                 if user_start_delay > 0 {
-                    tokio::time::sleep(std::time::Duration::from_millis(user_start_delay)).await;
+                    tokio::select! {
+                        _ = cancel.cancelled() => return user_metrics,
+                        _ = tokio::time::sleep(std::time::Duration::from_millis(user_start_delay)) => {}
+                    }
                 }
                 
-                let mut user_metrics = Vec::new();
                 let user_start = Instant::now();
                 
-                while user_start.elapsed() < benchmark_duration {
+                while user_start.elapsed() < run_duration && !cancel.is_cancelled() {
                     // Select random endpoint based on weights
-                    let endpoint = Self::select_weighted_endpoint(&config.endpoints);
+                    let endpoint = Self::select_weighted_endpoint(&config.endpoints, &mut rng);
                     
                     let request_start = Instant::now();
-                    let mut request_builder = match endpoint.method.as_str() {
-                        "GET" => client.get(&format!("{}{}", config.target_url, endpoint.path)),
-                        "POST" => client.post(&format!("{}{}", config.target_url, endpoint.path)),
-                        "PUT" => client.put(&format!("{}{}", config.target_url, endpoint.path)),
-                        "DELETE" => client.delete(&format!("{}{}", config.target_url, endpoint.path)),
-                        _ => client.get(&format!("{}{}", config.target_url, endpoint.path)),
+                    let token = match &session {
+                        Some(session) if endpoint.requires_auth => Some(session.token().await),
+                        _ => None,
                     };
+                    let request_builder = Self::build_request(&client, &config, endpoint, token.as_deref());
 
-                    // Add headers
-                    for (key, value) in &endpoint.headers {
-                        request_builder = request_builder.header(key, value);
-                    }
+                    // Execute request
+                    let response = tokio::select! {
+                        _ = cancel.cancelled() => break,
+                        response = send(router.as_ref(), request_builder, false) => response,
+                    };
+                    match response {
+                        Ok(reply) => {
+                            let success = reply.is_success();
+                            progress.record(success);
 
-                    // Add body if present
-                    if let Some(body) = &endpoint.body {
-                        request_builder = request_builder.body(body.clone());
-                    }
+                            // The request still counts as failed; later ones use the new token
+                            if let (Some(session), Some(token), 401) = (&session, &token, reply.status) {
+                                if let Err(e) = session.refresh(token).await {
+                                    eprintln!("Token refresh failed: {}", e);
+                                }
+                            }
 
-                    // Execute request
-                    match request_builder.send().await {
-                        Ok(response) => {
-                            let status_code = response.status().as_u16();
-                            let response_size = response.content_length().unwrap_or(0) as usize;
-                            let success = response.status().is_success();
+                            let error_body = reply
+                                .body
+                                .filter(|_| !success)
+                                .map(|body| truncate_error_body(&String::from_utf8_lossy(&body)));
                             
                             user_metrics.push(RequestMetrics {
                                 start_time: request_start,
-                                end_time: Instant::now(),
-                                status_code,
-                                response_size,
+                                end_time: reply.received_at,
+                                status_code: reply.status,
+                                response_size: reply.size,
                                 endpoint: endpoint.path.clone(),
                                 success,
+                                error_body,
                             });
                         }
                         Err(_) => {
+                            progress.record(false);
                             user_metrics.push(RequestMetrics {
                                 start_time: request_start,
                                 end_time: Instant::now(),
@@ -306,12 +1197,18 @@ impl LoadTester {
                                 response_size: 0,
                                 endpoint: endpoint.path.clone(),
                                 success: false,
+                                error_body: None,
                             });
                         }
                     }
 
-                    // Small delay between requests
-                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    let think_time = config.think_time.sample(&mut rng);
+                    if !think_time.is_zero() {
+                        tokio::select! {
+                            _ = cancel.cancelled() => break,
+                            _ = tokio::time::sleep(think_time) => {}
+                        }
+                    }
                 }
                 
                 user_metrics
@@ -325,7 +1222,9 @@ impl LoadTester {
             match task.await {
                 Ok(user_metrics) => {
                     for request_metric in user_metrics {
-                        metrics.add_request(request_metric);
+                        if request_metric.start_time >= measured_from {
+                            metrics.add_request(request_metric);
+                        }
                     }
                 }
                 Err(e) => {
@@ -335,8 +1234,17 @@ impl LoadTester {
         }
 
         metrics.finalize();
+        metrics.interrupted = cancel.is_cancelled();
+        if metrics.end_time < metrics.start_time {
+            // Interrupted during the warmup, so nothing was measured
+            metrics.start_time = metrics.end_time;
+        }
         
-        println!("✅ Benchmark completed for {} framework", metrics.framework);
+        if metrics.interrupted {
+            println!("⚠️ Benchmark interrupted for {} framework, keeping partial results", metrics.framework);
+        } else {
+            println!("✅ Benchmark completed for {} framework", metrics.framework);
+        }
         println!("📈 Results: {:.2} req/s, {:.2}ms avg response time, {:.1}% success rate",
                  metrics.requests_per_second(),
                  metrics.average_response_time_ms(),
@@ -345,11 +1253,129 @@ impl LoadTester {
         Ok(metrics)
     }
 
-    fn select_weighted_endpoint(endpoints: &[EndpointConfig]) -> &EndpointConfig {
-        use rand::Rng;
-        
+    /// Sends each configured endpoint a single request instead of running the
+    /// load test, to check the target is reachable and the config is right.
+    pub async fn validate(&self) -> Result<Vec<EndpointCheck>, BenchmarkError> {
+        self.check_config()?;
+
+        let session = self.login_if_needed().await?;
+        let mut checks = Vec::with_capacity(self.config.endpoints.len());
+
+        for endpoint in &self.config.endpoints {
+            let token = match &session {
+                Some(session) if endpoint.requires_auth => Some(session.token().await),
+                _ => None,
+            };
+            let request = Self::build_request(&self.client, &self.config, endpoint, token.as_deref());
+            let outcome = send(self.router.as_ref(), request, false)
+                .await
+                .map(|reply| reply.status)
+                .map_err(|e| e.to_string());
+
+            checks.push(EndpointCheck {
+                method: endpoint.method.clone(),
+                path: endpoint.path.clone(),
+                outcome,
+            });
+        }
+
+        Ok(checks)
+    }
+
+    async fn login_if_needed(&self) -> Result<Option<AuthSession>, BenchmarkError> {
+        self.register_account().await;
+        if !self.config.endpoints.iter().any(|endpoint| endpoint.requires_auth) {
+            return Ok(None);
+        }
+
+        let session = AuthSession::login(
+            self.client.clone(),
+            self.router.clone(),
+            self.config.target_url.clone(),
+            self.config.credentials.clone(),
+        )
+        .await?;
+        Ok(Some(session))
+    }
+
+    /// Registers the benchmark account, so there's a user for the session or a
+    /// benchmarked login endpoint to log in as. The account already existing,
+    /// or the target not offering registration, is fine.
+    async fn register_account(&self) {
+        let logs_in = self
+            .config
+            .endpoints
+            .iter()
+            .any(|endpoint| endpoint.requires_auth || endpoint.path == LOGIN_PATH);
+        if !logs_in {
+            return;
+        }
+
+        let input = CreateUserInput {
+            email: self.config.credentials.email.clone(),
+            name: "Benchmark User".to_string(),
+            password: self.config.credentials.password.clone(),
+        };
+        let request = self
+            .client
+            .post(format!("{}{}", self.config.target_url, REGISTER_PATH))
+            .json(&input);
+        let _ = send(self.router.as_ref(), request, false).await;
+    }
+
+    fn build_request(
+        client: &reqwest::Client,
+        config: &BenchmarkConfig,
+        endpoint: &EndpointConfig,
+        token: Option<&str>,
+    ) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", config.target_url, endpoint.path);
+        let mut request_builder = match endpoint.method.as_str() {
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "DELETE" => client.delete(url),
+            _ => client.get(url),
+        };
+
+        // `header` appends, so merge first to let the endpoint's value replace
+        // a global one. Header names are case-insensitive.
+        let mut headers: HashMap<String, &str> = HashMap::new();
+        for (key, value) in config.global_headers.iter().chain(&endpoint.headers) {
+            headers.insert(key.to_ascii_lowercase(), value);
+        }
+        for (key, value) in headers {
+            request_builder = request_builder.header(key, value);
+        }
+
+        if let Some(token) = token {
+            request_builder = request_builder.bearer_auth(token);
+        }
+
+        if let Some(operation) = &endpoint.graphql {
+            request_builder = request_builder.json(&operation.request_body());
+        } else if let Some(body) = &endpoint.body {
+            request_builder = request_builder.body(body.clone());
+        }
+
+        request_builder
+    }
+
+    // Each virtual user gets its own generator; with a seed, user N always sees
+    // the same sequence regardless of how tasks are scheduled
+    fn user_rng(seed: Option<u64>, user_id: u32) -> StdRng {
+        match seed {
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(user_id as u64)),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    fn select_weighted_endpoint<'a>(endpoints: &'a [EndpointConfig], rng: &mut impl Rng) -> &'a EndpointConfig {
         let total_weight: f32 = endpoints.iter().map(|e| e.weight).sum();
-        let mut rng = rand::thread_rng();
+        if total_weight <= 0.0 {
+            // No usable weights, so treat every endpoint as equally likely
+            return &endpoints[rng.gen_range(0..endpoints.len())];
+        }
+
         let mut random_value: f32 = rng.gen_range(0.0..total_weight);
         
         for endpoint in endpoints {
@@ -364,10 +1390,60 @@ impl LoadTester {
     }
 }
 
+const UNKNOWN_BUILD_VALUE: &str = "unknown";
+
+/// The code version a comparison was produced by, so saved results can be
+/// compared across commits. Fields are "unknown" when the build couldn't tell.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub git_sha: String,
+    pub build_timestamp: String,
+    pub rustc_version: String,
+}
+
+impl Default for BuildInfo {
+    fn default() -> Self {
+        Self {
+            git_sha: UNKNOWN_BUILD_VALUE.to_string(),
+            build_timestamp: UNKNOWN_BUILD_VALUE.to_string(),
+            rustc_version: UNKNOWN_BUILD_VALUE.to_string(),
+        }
+    }
+}
+
+impl BuildInfo {
+    /// Values recorded by the build script for this binary
+    pub fn current() -> Self {
+        Self {
+            git_sha: build_value(option_env!("VERGEN_GIT_SHA")),
+            build_timestamp: build_value(option_env!("VERGEN_BUILD_TIMESTAMP")),
+            rustc_version: build_value(option_env!("VERGEN_RUSTC_SEMVER")),
+        }
+    }
+}
+
+// vergen emits a placeholder instead of failing when e.g. there's no git repository
+fn build_value(value: Option<&str>) -> String {
+    match value {
+        Some(value) if !value.is_empty() && value != "VERGEN_IDEMPOTENT_OUTPUT" => value.to_string(),
+        _ => UNKNOWN_BUILD_VALUE.to_string(),
+    }
+}
+
 // Comparison utilities
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FrameworkComparison {
     pub axum_results: Vec<BenchmarkResult>,
     pub loco_results: Vec<BenchmarkResult>,
+    // Absent from results saved before it was recorded
+    #[serde(default)]
+    pub build_info: BuildInfo,
+}
+
+impl Default for FrameworkComparison {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FrameworkComparison {
@@ -375,9 +1451,22 @@ impl FrameworkComparison {
         Self {
             axum_results: Vec::new(),
             loco_results: Vec::new(),
+            build_info: BuildInfo::current(),
         }
     }
 
+    /// Writes the raw results as JSON so reports can be regenerated later.
+    pub fn save_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn load_from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
     pub fn add_axum_result(&mut self, result: BenchmarkResult) {
         self.axum_results.push(result);
     }
@@ -391,40 +1480,43 @@ impl FrameworkComparison {
         
         report.push_str("# AXUM vs LOCO Performance Comparison Report\n\n");
         report.push_str(&format!("Generated at: {}\n\n", Utc::now().format("%Y-%m-%d %H:%M:%S UTC")));
+        report.push_str(&format!(
+            "Build: `{}` (built {}, rustc {})\n\n",
+            self.build_info.git_sha, self.build_info.build_timestamp, self.build_info.rustc_version
+        ));
 
         // Summary table
         report.push_str("## Summary\n\n");
-        report.push_str("| Framework | Avg RPS | Avg Response Time (ms) | P95 (ms) | P99 (ms) |\n");
-        report.push_str("|-----------|---------|------------------------|----------|----------|\n");
-
-        if let Some(axum_avg) = self.calculate_average_metrics(&self.axum_results) {
-            report.push_str(&format!("| AXUM      | {:.2}    | {:.2}                   | {:.2}     | {:.2}     |\n",
-                axum_avg.requests_per_second,
-                axum_avg.average_response_time_ms,
-                axum_avg.p95_response_time_ms,
-                axum_avg.p99_response_time_ms));
+        report.push_str(&summary_header());
+        for (label, average) in self.summary() {
+            report.push_str(&summary_row(label, &average));
         }
+        report.push('\n');
 
-        if let Some(loco_avg) = self.calculate_average_metrics(&self.loco_results) {
-            report.push_str(&format!("| LOCO      | {:.2}    | {:.2}                   | {:.2}     | {:.2}     |\n",
-                loco_avg.requests_per_second,
-                loco_avg.average_response_time_ms,
-                loco_avg.p95_response_time_ms,
-                loco_avg.p99_response_time_ms));
+        for warning in self.success_rate_warnings() {
+            report.push_str(&format!("> {}\n\n", warning.to_markdown()));
         }
 
-        report.push_str("\n## Detailed Results\n\n");
+        report.push_str("## Detailed Results\n\n");
 
         // AXUM results
         if !self.axum_results.is_empty() {
             report.push_str("### AXUM Framework Results\n\n");
             for result in &self.axum_results {
-                report.push_str(&format!("**{}**\n", result.test_name));
+                report.push_str(&format!("**{}**{}\n", result.test_name, interrupted_marker(result)));
                 report.push_str(&format!("- Requests/sec: {:.2}\n", result.requests_per_second));
                 report.push_str(&format!("- Avg response time: {:.2}ms\n", result.average_response_time_ms));
+                report.push_str(&format!("- Min/Max response time: {:.2}ms / {:.2}ms\n", result.min_response_time_ms, result.max_response_time_ms));
+                report.push_str(&format!("- Response time std dev: {:.2}ms\n", result.stddev_response_time_ms));
                 report.push_str(&format!("- P95 response time: {:.2}ms\n", result.p95_response_time_ms));
                 report.push_str(&format!("- P99 response time: {:.2}ms\n", result.p99_response_time_ms));
-                report.push_str("\n");
+                report.push_str(&format!("- Success rate: {:.1}%\n", result.success_rate));
+                if !result.error_counts.is_empty() {
+                    report.push_str(&format!("- Errors: {}\n", format_error_counts(&result.error_counts)));
+                }
+                push_endpoint_breakdown(&mut report, result);
+                push_histogram(&mut report, result);
+                report.push('\n');
             }
         }
 
@@ -432,45 +1524,78 @@ impl FrameworkComparison {
         if !self.loco_results.is_empty() {
             report.push_str("### LOCO Framework Results\n\n");
             for result in &self.loco_results {
-                report.push_str(&format!("**{}**\n", result.test_name));
+                report.push_str(&format!("**{}**{}\n", result.test_name, interrupted_marker(result)));
                 report.push_str(&format!("- Requests/sec: {:.2}\n", result.requests_per_second));
                 report.push_str(&format!("- Avg response time: {:.2}ms\n", result.average_response_time_ms));
+                report.push_str(&format!("- Min/Max response time: {:.2}ms / {:.2}ms\n", result.min_response_time_ms, result.max_response_time_ms));
+                report.push_str(&format!("- Response time std dev: {:.2}ms\n", result.stddev_response_time_ms));
                 report.push_str(&format!("- P95 response time: {:.2}ms\n", result.p95_response_time_ms));
                 report.push_str(&format!("- P99 response time: {:.2}ms\n", result.p99_response_time_ms));
-                report.push_str("\n");
+                report.push_str(&format!("- Success rate: {:.1}%\n", result.success_rate));
+                if !result.error_counts.is_empty() {
+                    report.push_str(&format!("- Errors: {}\n", format_error_counts(&result.error_counts)));
+                }
+                push_endpoint_breakdown(&mut report, result);
+                push_histogram(&mut report, result);
+                report.push('\n');
             }
         }
 
+        push_error_analysis(&mut report, &self.axum_results, &self.loco_results);
+
         // Winner analysis
         report.push_str("## Analysis\n\n");
-        if let (Some(axum_avg), Some(loco_avg)) = (
-            self.calculate_average_metrics(&self.axum_results),
-            self.calculate_average_metrics(&self.loco_results)
-        ) {
-            if axum_avg.requests_per_second > loco_avg.requests_per_second {
-                let diff = ((axum_avg.requests_per_second - loco_avg.requests_per_second) / loco_avg.requests_per_second) * 100.0;
-                report.push_str(&format!("🏆 **AXUM wins in throughput** by {:.1}% ({:.2} vs {:.2} req/s)\n\n",
-                    diff, axum_avg.requests_per_second, loco_avg.requests_per_second));
-            } else {
-                let diff = ((loco_avg.requests_per_second - axum_avg.requests_per_second) / axum_avg.requests_per_second) * 100.0;
-                report.push_str(&format!("🏆 **LOCO wins in throughput** by {:.1}% ({:.2} vs {:.2} req/s)\n\n",
-                    diff, loco_avg.requests_per_second, axum_avg.requests_per_second));
-            }
-
-            if axum_avg.average_response_time_ms < loco_avg.average_response_time_ms {
-                let diff = ((loco_avg.average_response_time_ms - axum_avg.average_response_time_ms) / loco_avg.average_response_time_ms) * 100.0;
-                report.push_str(&format!("⚡ **AXUM wins in response time** by {:.1}% ({:.2}ms vs {:.2}ms)\n\n",
-                    diff, axum_avg.average_response_time_ms, loco_avg.average_response_time_ms));
-            } else {
-                let diff = ((axum_avg.average_response_time_ms - loco_avg.average_response_time_ms) / axum_avg.average_response_time_ms) * 100.0;
-                report.push_str(&format!("⚡ **LOCO wins in response time** by {:.1}% ({:.2}ms vs {:.2}ms)\n\n",
-                    diff, loco_avg.average_response_time_ms, axum_avg.average_response_time_ms));
-            }
+        for note in self.analysis() {
+            report.push_str(&format!("{}\n\n", note.to_markdown()));
         }
 
         report
     }
 
+    /// Each framework with results, labelled, alongside the average of its runs
+    pub fn summary(&self) -> Vec<(&'static str, BenchmarkResult)> {
+        [("AXUM", &self.axum_results), ("LOCO", &self.loco_results)]
+            .into_iter()
+            .filter_map(|(label, results)| Some((label, self.calculate_average_metrics(results)?)))
+            .collect()
+    }
+
+    /// A warning for each framework whose average success rate is too low for
+    /// its other numbers to be trusted
+    pub fn success_rate_warnings(&self) -> Vec<ReportNote> {
+        self.summary()
+            .iter()
+            .filter_map(|(label, average)| success_rate_warning(label, average))
+            .collect()
+    }
+
+    /// The throughput and response time winners, once both frameworks have results
+    pub fn analysis(&self) -> Vec<ReportNote> {
+        let (Some(axum_avg), Some(loco_avg)) = (
+            self.calculate_average_metrics(&self.axum_results),
+            self.calculate_average_metrics(&self.loco_results),
+        ) else {
+            return Vec::new();
+        };
+
+        vec![
+            winner_note(
+                "🏆",
+                "throughput",
+                (axum_avg.requests_per_second, loco_avg.requests_per_second),
+                true,
+                |winning, losing| format!("{:.2} vs {:.2} req/s", winning, losing),
+            ),
+            winner_note(
+                "⚡",
+                "response time",
+                (axum_avg.average_response_time_ms, loco_avg.average_response_time_ms),
+                false,
+                |winning, losing| format!("{:.2}ms vs {:.2}ms", winning, losing),
+            ),
+        ]
+    }
+
     fn calculate_average_metrics(&self, results: &[BenchmarkResult]) -> Option<BenchmarkResult> {
         if results.is_empty() {
             return None;
@@ -482,11 +1607,708 @@ impl FrameworkComparison {
             test_name: "Average".to_string(),
             requests_per_second: results.iter().map(|r| r.requests_per_second).sum::<f64>() / count,
             average_response_time_ms: results.iter().map(|r| r.average_response_time_ms).sum::<f64>() / count,
+            min_response_time_ms: results.iter().map(|r| r.min_response_time_ms).fold(f64::INFINITY, f64::min),
+            max_response_time_ms: results.iter().map(|r| r.max_response_time_ms).fold(0.0, f64::max),
+            stddev_response_time_ms: results.iter().map(|r| r.stddev_response_time_ms).sum::<f64>() / count,
             p95_response_time_ms: results.iter().map(|r| r.p95_response_time_ms).sum::<f64>() / count,
             p99_response_time_ms: results.iter().map(|r| r.p99_response_time_ms).sum::<f64>() / count,
             memory_usage_mb: results.iter().map(|r| r.memory_usage_mb).sum::<f64>() / count,
             cpu_usage_percent: results.iter().map(|r| r.cpu_usage_percent).sum::<f64>() / count,
+            // Bucket boundaries differ between runs, so they can't be averaged
+            response_time_histogram: Vec::new(),
+            // Scenarios hit different endpoints, so the breakdown stays per run
+            endpoint_stats: HashMap::new(),
+            top_errors: Vec::new(),
+            success_rate: results.iter().map(|r| r.success_rate).sum::<f64>() / count,
+            error_counts: results.iter().fold(HashMap::new(), |mut totals, r| {
+                for (code, count) in &r.error_counts {
+                    *totals.entry(code.clone()).or_insert(0) += count;
+                }
+                totals
+            }),
+            interrupted: results.iter().any(|r| r.interrupted),
             timestamp: Utc::now(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn metrics_with_durations(durations_ms: impl IntoIterator<Item = u64>) -> BenchmarkMetrics {
+        let mut metrics = BenchmarkMetrics::new("test".to_string());
+        let start_time = Instant::now();
+
+        for duration_ms in durations_ms {
+            metrics.add_request(RequestMetrics {
+                start_time,
+                end_time: start_time + Duration::from_millis(duration_ms),
+                status_code: 200,
+                response_size: 0,
+                endpoint: "/health".to_string(),
+                success: true,
+                error_body: None,
+            });
+        }
+
+        metrics
+    }
+
+    #[test]
+    fn test_sub_millisecond_duration_keeps_its_fraction() {
+        let start_time = Instant::now();
+        let request = RequestMetrics {
+            start_time,
+            end_time: start_time + Duration::from_micros(250),
+            status_code: 200,
+            response_size: 0,
+            endpoint: "/health".to_string(),
+            success: true,
+            error_body: None,
+        };
+
+        assert!((request.duration_ms() - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_percentiles_nearest_rank() {
+        let metrics = metrics_with_durations(1..=100);
+
+        assert_eq!(metrics.percentile_response_time_ms(50.0), 50.0);
+        assert_eq!(metrics.percentile_response_time_ms(95.0), 95.0);
+        assert_eq!(metrics.percentile_response_time_ms(99.0), 99.0);
+        assert_eq!(metrics.percentile_response_time_ms(100.0), 100.0);
+        assert_eq!(metrics.percentile_response_time_ms(0.0), 1.0);
+
+        let percentiles = metrics.percentiles(&[50.0, 90.0, 99.9]);
+        assert_eq!(percentiles["p50"], 50.0);
+        assert_eq!(percentiles["p90"], 90.0);
+        assert_eq!(percentiles["p99.9"], 100.0);
+    }
+
+    #[test]
+    fn test_response_time_spread() {
+        let metrics = metrics_with_durations([2, 4, 4, 4, 5, 5, 7, 9]);
+
+        // Mean is 5, squared deviations sum to 32, so the variance is 32 / 8 = 4
+        assert_eq!(metrics.min_response_time_ms(), 2.0);
+        assert_eq!(metrics.max_response_time_ms(), 9.0);
+        assert!((metrics.stddev_response_time_ms() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_comparison_report_table_alignment() {
+        let mut comparison = FrameworkComparison::new();
+        assert!(comparison.generate_comparison_report().contains("## Summary"));
+
+        let result = metrics_with_durations([2, 4, 4, 4, 5, 5, 7, 9])
+            .to_benchmark_result("Health Check".to_string(), None);
+        comparison.add_axum_result(result.clone());
+        comparison.add_loco_result(result);
+
+        let report = comparison.generate_comparison_report();
+        // The summary is the first table; per-endpoint breakdowns follow it
+        let table: Vec<&str> = report
+            .lines()
+            .skip_while(|line| !line.starts_with('|'))
+            .take_while(|line| line.starts_with('|'))
+            .collect();
+        assert_eq!(table.len(), 4);
+        assert!(table.iter().all(|line| line.len() == table[0].len()));
+        assert!(table[2].contains("2.00"));
+    }
+
+    #[test]
+    fn test_failing_framework_is_flagged_despite_higher_throughput() {
+        let mut failing = metrics_with_durations(vec![1; 40]);
+        let start_time = Instant::now();
+        for _ in 0..60 {
+            failing.add_request(RequestMetrics {
+                start_time,
+                end_time: start_time + Duration::from_millis(1),
+                status_code: 500,
+                response_size: 0,
+                endpoint: "/api/v1/products".to_string(),
+                success: false,
+                error_body: None,
+            });
+        }
+        failing.end_time = failing.start_time + chrono::Duration::seconds(1);
+        let mut healthy = metrics_with_durations(vec![5; 50]);
+        healthy.end_time = healthy.start_time + chrono::Duration::seconds(1);
+
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(healthy.to_benchmark_result("REST API".to_string(), None));
+        comparison.add_loco_result(failing.to_benchmark_result("REST API".to_string(), None));
+        assert_eq!(comparison.loco_results[0].success_rate, 40.0);
+        assert!(comparison.loco_results[0].requests_per_second > comparison.axum_results[0].requests_per_second);
+
+        let report = comparison.generate_comparison_report();
+        assert!(report.contains("LOCO wins in throughput"));
+        assert!(report.contains("> ⚠️ **LOCO success rate is 40.0%**"));
+        assert!(report.contains("HTTP_500 × 60"));
+        assert!(!report.contains("**AXUM success rate"));
+    }
+
+    #[test]
+    fn test_per_endpoint_stats_are_isolated() {
+        let mut metrics = BenchmarkMetrics::new("test".to_string());
+        let start_time = Instant::now();
+        let requests = [("/health", 1, true), ("/health", 3, true), ("/graphql", 40, true), ("/graphql", 60, false)];
+
+        for (endpoint, duration_ms, success) in requests {
+            metrics.add_request(RequestMetrics {
+                start_time,
+                end_time: start_time + Duration::from_millis(duration_ms),
+                status_code: if success { 200 } else { 500 },
+                response_size: 0,
+                endpoint: endpoint.to_string(),
+                success,
+                error_body: None,
+            });
+        }
+
+        let stats = metrics.per_endpoint_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats["/health"],
+            EndpointStats {
+                count: 2,
+                average_response_time_ms: 2.0,
+                p95_response_time_ms: 3.0,
+                p99_response_time_ms: 3.0,
+                error_rate_percent: 0.0,
+            }
+        );
+        assert_eq!(
+            stats["/graphql"],
+            EndpointStats {
+                count: 2,
+                average_response_time_ms: 50.0,
+                p95_response_time_ms: 60.0,
+                p99_response_time_ms: 60.0,
+                error_rate_percent: 50.0,
+            }
+        );
+
+        // The aggregate still covers every request
+        assert_eq!(metrics.average_response_time_ms(), 26.0);
+
+        let result = metrics.to_benchmark_result("Mixed Load".to_string(), None);
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(result);
+        assert!(comparison.generate_comparison_report().contains("| /graphql | 2 | 50.00 | 60.00 | 60.00 | 50.0% |"));
+    }
+
+    #[test]
+    fn test_histogram_counts_sum_to_total_requests() {
+        let metrics = metrics_with_durations([1, 2, 2, 3, 5, 8, 13, 21, 34, 55]);
+
+        let histogram = metrics.response_time_histogram(5);
+        assert_eq!(histogram.len(), 5);
+        assert_eq!(histogram[0].0, 1.0);
+        assert_eq!(histogram[4].1, 55.0);
+        assert_eq!(histogram.iter().map(|(_, _, count)| count).sum::<u64>(), metrics.total_requests);
+
+        assert!(metrics_with_durations([]).response_time_histogram(5).is_empty());
+        assert_eq!(metrics_with_durations([4, 4]).response_time_histogram(5), vec![(4.0, 4.0, 2)]);
+    }
+
+    fn endpoint(path: &str, weight: f32) -> EndpointConfig {
+        EndpointConfig {
+            path: path.to_string(),
+            method: "GET".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            weight,
+            requires_auth: false,
+            graphql: None,
+        }
+    }
+
+    #[test]
+    fn test_global_headers_are_merged_with_endpoint_headers() {
+        let config = BenchmarkConfig {
+            global_headers: HashMap::from([
+                ("X-Benchmark-Run".to_string(), "run-1".to_string()),
+                ("User-Agent".to_string(), "global-agent".to_string()),
+            ]),
+            ..BenchmarkConfig::default()
+        };
+        let endpoint = EndpointConfig {
+            headers: HashMap::from([("user-agent".to_string(), "endpoint-agent".to_string())]),
+            ..endpoint("/health", 1.0)
+        };
+
+        let request = LoadTester::build_request(&reqwest::Client::new(), &config, &endpoint, None)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.headers()["X-Benchmark-Run"], "run-1");
+        let user_agents: Vec<_> = request.headers().get_all("User-Agent").iter().collect();
+        assert_eq!(user_agents, ["endpoint-agent"]);
+    }
+
+    #[test]
+    fn test_graphql_endpoint_body_includes_variables() {
+        let endpoint = EndpointConfig {
+            method: "POST".to_string(),
+            graphql: Some(
+                GraphQLOperation::new("query Product($id: UUID!) { product(id: $id) { name } }")
+                    .with_variables(serde_json::json!({ "id": "00000000-0000-0000-0000-000000000001" }))
+                    .with_operation_name("Product"),
+            ),
+            ..endpoint("/graphql", 1.0)
+        };
+
+        let request = LoadTester::build_request(&reqwest::Client::new(), &BenchmarkConfig::default(), &endpoint, None)
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["Content-Type"], "application/json");
+
+        let body: serde_json::Value = serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["query"], "query Product($id: UUID!) { product(id: $id) { name } }");
+        assert_eq!(body["variables"]["id"], "00000000-0000-0000-0000-000000000001");
+        assert_eq!(body["operationName"], "Product");
+
+        let body = GraphQLOperation::new("{ health }").request_body();
+        assert_eq!(body, serde_json::json!({ "query": "{ health }" }));
+    }
+
+    #[test]
+    fn test_seeded_endpoint_selection_is_reproducible() {
+        let endpoints = vec![endpoint("/a", 0.5), endpoint("/b", 0.3), endpoint("/c", 0.2)];
+        let select_sequence = || {
+            let mut rng = LoadTester::user_rng(Some(42), 0);
+            (0..50)
+                .map(|_| LoadTester::select_weighted_endpoint(&endpoints, &mut rng).path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first_run = select_sequence();
+        assert_eq!(first_run, select_sequence());
+        assert!(first_run.iter().any(|path| path != &first_run[0]));
+    }
+
+    #[test]
+    fn test_exponential_think_time_averages_to_mean() {
+        let mut rng = LoadTester::user_rng(Some(42), 0);
+        let samples = 20_000;
+
+        let total_ms: f64 = (0..samples)
+            .map(|_| ThinkTime::Exponential(100).sample(&mut rng).as_secs_f64() * 1000.0)
+            .sum();
+        let mean_ms = total_ms / samples as f64;
+
+        assert!((mean_ms - 100.0).abs() < 5.0, "mean was {}ms", mean_ms);
+    }
+
+    #[test]
+    fn test_think_time_samples_stay_in_range() {
+        use std::time::Duration;
+
+        let mut rng = LoadTester::user_rng(Some(7), 0);
+
+        assert_eq!(ThinkTime::None.sample(&mut rng), Duration::ZERO);
+        assert_eq!(ThinkTime::Fixed(25).sample(&mut rng), Duration::from_millis(25));
+        assert_eq!(ThinkTime::Uniform(30, 10).sample(&mut rng), Duration::from_millis(30));
+        for _ in 0..100 {
+            let pause = ThinkTime::Uniform(10, 20).sample(&mut rng);
+            assert!(pause >= Duration::from_millis(10) && pause <= Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_zero_weight_endpoints_do_not_panic() {
+        let endpoints = vec![endpoint("/a", 0.0), endpoint("/b", 0.0)];
+        let mut rng = LoadTester::user_rng(Some(7), 0);
+
+        for _ in 0..10 {
+            let selected = LoadTester::select_weighted_endpoint(&endpoints, &mut rng);
+            assert!(selected.path == "/a" || selected.path == "/b");
+        }
+    }
+
+    // Serves `/health` on an ephemeral port and returns its base URL
+    async fn spawn_health_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_zero_concurrent_users_is_rejected() {
+        let config = BenchmarkConfig {
+            target_url: "http://127.0.0.1:1".to_string(),
+            concurrent_users: 0,
+            duration_seconds: 1,
+            ramp_up_seconds: 1,
+            seed: Some(1),
+            think_time: ThinkTime::None,
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0)],
+        };
+        let load_tester = LoadTester::new(config);
+
+        assert!(matches!(load_tester.run_benchmark("test".to_string()).await, Err(BenchmarkError::InvalidConfig)));
+        assert!(matches!(load_tester.validate().await, Err(BenchmarkError::InvalidConfig)));
+    }
+
+    #[test]
+    fn test_connection_pool_scales_with_concurrent_users() {
+        assert_eq!(LoadTester::pool_size(1), 1);
+        assert_eq!(LoadTester::pool_size(100), 100);
+        assert_eq!(LoadTester::pool_size(5_000), 5_000);
+        // Zero is rejected before a run, but still builds a usable client
+        assert_eq!(LoadTester::pool_size(0), 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_process_benchmark_calls_router_without_network() {
+        let app = axum::Router::new()
+            .route("/health", axum::routing::get(|| async { "ok" }))
+            .route(
+                "/echo",
+                axum::routing::post(|axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    (axum::http::StatusCode::UNPROCESSABLE_ENTITY, body.to_string())
+                }),
+            );
+        let echo = EndpointConfig {
+            method: "POST".to_string(),
+            graphql: Some(GraphQLOperation::new("query { health }")),
+            ..endpoint("/echo", 1.0)
+        };
+        let config = BenchmarkConfig {
+            // Nothing listens here, so any request sent over the network would fail
+            target_url: "http://127.0.0.1:1".to_string(),
+            concurrent_users: 2,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(5),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0), echo],
+        };
+
+        let metrics = LoadTester::in_process(config, app).run_benchmark("test".to_string()).await.unwrap();
+
+        let endpoints = metrics.per_endpoint_stats();
+        assert!(endpoints["/health"].count > 0);
+        assert_eq!(endpoints["/health"].error_rate_percent, 0.0);
+        assert!(metrics.request_metrics.iter().any(|m| m.endpoint == "/health" && m.response_size == 2));
+        // Request bodies reach the handler too
+        let echoed = metrics.request_metrics.iter().find(|m| m.endpoint == "/echo").unwrap();
+        assert_eq!(echoed.status_code, 422);
+        assert!(echoed.error_body.as_deref().unwrap().contains("query { health }"));
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_counted_while_running() {
+        let app = axum::Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let config = BenchmarkConfig {
+            target_url: "http://127.0.0.1:1".to_string(),
+            concurrent_users: 2,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(5),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0), endpoint("/missing", 1.0)],
+        };
+        let load_tester = LoadTester::in_process(config, app);
+        let progress = load_tester.progress();
+
+        let run = tokio::spawn(async move { load_tester.run_benchmark("test".to_string()).await });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let update = progress.update(None);
+        assert!(update.requests > 0);
+        assert!(update.current_rps > 0.0);
+
+        let metrics = run.await.unwrap().unwrap();
+        let last = progress.update(Some(&update));
+        assert_eq!(last.requests, metrics.request_metrics.len() as u64);
+        assert_eq!(last.errors, metrics.request_metrics.iter().filter(|m| !m.success).count() as u64);
+        assert!(last.errors > 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_think_time_is_not_throttled() {
+        let config = BenchmarkConfig {
+            target_url: spawn_health_server().await,
+            concurrent_users: 1,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(0),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0)],
+        };
+
+        let metrics = LoadTester::new(config).run_benchmark("test".to_string()).await.unwrap();
+
+        // A single user sleeping 10ms between requests can't exceed 100 req/s
+        assert_eq!(metrics.failed_requests, 0);
+        assert!(metrics.requests_per_second() > 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_error_bodies_are_captured_and_truncated() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().route(
+            "/fail",
+            axum::routing::get(|| async { (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "é".repeat(400)) }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = BenchmarkConfig {
+            target_url: format!("http://{}", addr),
+            concurrent_users: 1,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(50),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/fail", 1.0)],
+        };
+
+        let metrics = LoadTester::new(config).run_benchmark("test".to_string()).await.unwrap();
+
+        assert!(metrics.failed_requests > 0);
+        let body = metrics.request_metrics[0].error_body.as_deref().unwrap();
+        // 800 bytes of two-byte chars, cut at the last boundary within the limit
+        assert_eq!(body.len(), MAX_ERROR_BODY_BYTES);
+        assert!(body.chars().all(|c| c == 'é'));
+
+        let top_errors = metrics.top_errors(5);
+        assert_eq!(top_errors.len(), 1);
+        assert_eq!(top_errors[0].status_code, 500);
+        assert_eq!(top_errors[0].count, metrics.failed_requests);
+
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(metrics.to_benchmark_result("Failures".to_string(), None));
+        assert!(comparison.generate_comparison_report().contains("## Error Analysis"));
+    }
+
+    #[tokio::test]
+    async fn test_warmup_is_excluded_from_duration() {
+        let config = BenchmarkConfig {
+            target_url: spawn_health_server().await,
+            concurrent_users: 1,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(5),
+            warmup_seconds: 1,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0)],
+        };
+
+        let run_start = Instant::now();
+        let metrics = LoadTester::new(config).run_benchmark("test".to_string()).await.unwrap();
+
+        assert!(run_start.elapsed().as_secs_f64() >= 2.0);
+        assert!(metrics.duration_seconds() < 1.5);
+        assert!(metrics.total_requests > 0);
+        assert!(metrics
+            .request_metrics
+            .iter()
+            .all(|m| m.start_time.duration_since(run_start).as_secs_f64() >= 1.0));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_benchmark_returns_partial_results() {
+        let config = BenchmarkConfig {
+            target_url: spawn_health_server().await,
+            concurrent_users: 2,
+            duration_seconds: 60,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(5),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![endpoint("/health", 1.0)],
+        };
+        let cancel = CancellationToken::new();
+        tokio::spawn({
+            let cancel = cancel.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                cancel.cancel();
+            }
+        });
+
+        let run_start = Instant::now();
+        let metrics = LoadTester::new(config)
+            .run_benchmark_until("test".to_string(), cancel)
+            .await
+            .unwrap();
+
+        assert!(run_start.elapsed() < Duration::from_secs(5));
+        assert!(metrics.interrupted);
+        assert!(metrics.total_requests > 0);
+        assert_eq!(metrics.failed_requests, 0);
+
+        let result = metrics.to_benchmark_result("Health Check".to_string(), None);
+        assert!(result.interrupted);
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(result);
+        assert!(comparison.generate_comparison_report().contains("**Health Check** (interrupted, partial results)"));
+    }
+
+    // Login issues `login_token` and refreshing issues `valid_token`;
+    // `/api/v1/users/me` only accepts `valid_token`
+    async fn spawn_auth_server(login_token: &'static str, valid_token: &'static str) -> String {
+        use axum::{http::{HeaderMap, StatusCode}, routing::{get, post}, Json};
+
+        let login = move || async move {
+            Json(ApiResponse::success(AuthResponse {
+                token: login_token.to_string(),
+                refresh_token: "refresh-token".to_string(),
+                user: crate::models::User {
+                    id: uuid::Uuid::new_v4(),
+                    email: "benchmark@example.com".to_string(),
+                    name: "Benchmark User".to_string(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                },
+            }))
+        };
+        let refresh = move || async move {
+            Json(ApiResponse::success(RefreshTokenResponse {
+                token: valid_token.to_string(),
+                refresh_token: "refresh-token".to_string(),
+            }))
+        };
+        let me = move |headers: HeaderMap| async move {
+            let expected = format!("Bearer {}", valid_token);
+            match headers.get("Authorization").and_then(|value| value.to_str().ok()) {
+                Some(authorization) if authorization == expected => StatusCode::OK,
+                _ => StatusCode::UNAUTHORIZED,
+            }
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new()
+            .route(LOGIN_PATH, post(login))
+            .route(REFRESH_PATH, post(refresh))
+            .route("/api/v1/users/me", get(me));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn authenticated_config(target_url: String) -> BenchmarkConfig {
+        BenchmarkConfig {
+            target_url,
+            concurrent_users: 1,
+            duration_seconds: 1,
+            ramp_up_seconds: 0,
+            seed: Some(1),
+            think_time: ThinkTime::Fixed(5),
+            warmup_seconds: 0,
+            credentials: BenchmarkCredentials::default(),
+            global_headers: HashMap::new(),
+            endpoints: vec![EndpointConfig {
+                requires_auth: true,
+                ..endpoint("/api/v1/users/me", 1.0)
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_endpoints_send_token_from_login() {
+        let config = authenticated_config(spawn_auth_server("issued-token", "issued-token").await);
+
+        let metrics = LoadTester::new(config).run_benchmark("test".to_string()).await.unwrap();
+
+        assert!(metrics.total_requests > 0);
+        assert_eq!(metrics.failed_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_token_is_refreshed_mid_run() {
+        let config = authenticated_config(spawn_auth_server("expired-token", "fresh-token").await);
+
+        let metrics = LoadTester::new(config).run_benchmark("test".to_string()).await.unwrap();
+
+        // Only the first request goes out with the expired token
+        assert_eq!(metrics.failed_requests, 1);
+        assert!(metrics.successful_requests > 0);
+    }
+
+    #[test]
+    fn test_comparison_file_round_trip() {
+        let mut comparison = FrameworkComparison::new();
+        comparison.add_axum_result(
+            metrics_with_durations([2, 4, 6]).to_benchmark_result("Health Check".to_string(), None),
+        );
+
+        let path = std::env::temp_dir().join(format!("comparison_{}.json", uuid::Uuid::new_v4()));
+        comparison.save_to_file(&path).unwrap();
+        let loaded = FrameworkComparison::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.axum_results.len(), 1);
+        assert!(loaded.loco_results.is_empty());
+        assert_eq!(loaded.axum_results[0].test_name, "Health Check");
+        assert_eq!(loaded.axum_results[0].max_response_time_ms, 6.0);
+        assert_eq!(loaded.axum_results[0].response_time_histogram.len(), HISTOGRAM_BUCKETS);
+        assert_eq!(loaded.build_info, comparison.build_info);
+    }
+
+    #[test]
+    fn test_comparison_records_build_info() {
+        let comparison = FrameworkComparison::new();
+        let json = serde_json::to_value(&comparison).unwrap();
+
+        let build_info = &json["build_info"];
+        for field in ["git_sha", "build_timestamp", "rustc_version"] {
+            assert!(!build_info[field].as_str().unwrap().is_empty(), "{}", field);
+        }
+        assert!(comparison.generate_comparison_report().contains(&format!("Build: `{}`", comparison.build_info.git_sha)));
+
+        // Results saved before build info was recorded still load
+        let loaded: FrameworkComparison = serde_json::from_str(r#"{"axum_results": [], "loco_results": []}"#).unwrap();
+        assert_eq!(loaded.build_info, BuildInfo::default());
+
+        // Results saved before success rates were recorded count as fully successful
+        let mut saved = serde_json::to_value(
+            metrics_with_durations([1]).to_benchmark_result("Health Check".to_string(), None),
+        )
+        .unwrap();
+        saved.as_object_mut().unwrap().remove("success_rate");
+        let loaded: BenchmarkResult = serde_json::from_value(saved).unwrap();
+        assert_eq!(loaded.success_rate, 100.0);
+        assert_eq!(build_value(Some("VERGEN_IDEMPOTENT_OUTPUT")), "unknown");
+    }
+
+    #[test]
+    fn test_percentiles_empty_metrics() {
+        let metrics = metrics_with_durations([]);
+
+        assert_eq!(metrics.percentile_response_time_ms(95.0), 0.0);
+        assert_eq!(metrics.percentiles(&[50.0])["p50"], 0.0);
+        assert_eq!(metrics.min_response_time_ms(), 0.0);
+        assert_eq!(metrics.max_response_time_ms(), 0.0);
+        assert_eq!(metrics.stddev_response_time_ms(), 0.0);
+    }
+}
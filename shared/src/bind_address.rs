@@ -0,0 +1,61 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use thiserror::Error;
+
+pub const HOST_ENV: &str = "HOST";
+pub const PORT_ENV: &str = "PORT";
+pub const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BindAddressError {
+    #[error("Invalid HOST {0:?}: expected an IP address or \"localhost\"")]
+    InvalidHost(String),
+    #[error("Invalid PORT {0:?}: expected a number from 1 to 65535")]
+    InvalidPort(String),
+}
+
+/// Reads `HOST` and `PORT`, defaulting to all interfaces on `default_port`
+pub fn bind_address_from_env(default_port: u16) -> Result<SocketAddr, BindAddressError> {
+    parse_bind_address(
+        std::env::var(HOST_ENV).ok().as_deref(),
+        std::env::var(PORT_ENV).ok().as_deref(),
+        default_port,
+    )
+}
+
+pub fn parse_bind_address(host: Option<&str>, port: Option<&str>, default_port: u16) -> Result<SocketAddr, BindAddressError> {
+    let host = match host.map(str::trim) {
+        None | Some("") => DEFAULT_HOST,
+        Some("localhost") => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        Some(host) => host.parse().map_err(|_| BindAddressError::InvalidHost(host.to_string()))?,
+    };
+
+    let port = match port.map(str::trim) {
+        None | Some("") => default_port,
+        // Port 0 would bind a random port, which is never what's wanted here
+        Some(port) => port
+            .parse::<u16>()
+            .ok()
+            .filter(|&port| port != 0)
+            .ok_or_else(|| BindAddressError::InvalidPort(port.to_string()))?,
+    };
+
+    Ok(SocketAddr::new(host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bind_address() {
+        assert_eq!(parse_bind_address(None, None, 3000), Ok("0.0.0.0:3000".parse().unwrap()));
+        assert_eq!(parse_bind_address(Some("127.0.0.1"), Some("8080"), 3000), Ok("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(parse_bind_address(Some("localhost"), Some(" 5150 "), 3000), Ok("127.0.0.1:5150".parse().unwrap()));
+        assert_eq!(parse_bind_address(Some("::1"), None, 3000), Ok("[::1]:3000".parse().unwrap()));
+
+        assert_eq!(parse_bind_address(None, Some("65536"), 3000), Err(BindAddressError::InvalidPort("65536".to_string())));
+        assert_eq!(parse_bind_address(None, Some("0"), 3000), Err(BindAddressError::InvalidPort("0".to_string())));
+        assert_eq!(parse_bind_address(None, Some("http"), 3000), Err(BindAddressError::InvalidPort("http".to_string())));
+        assert_eq!(parse_bind_address(Some("example"), None, 3000), Err(BindAddressError::InvalidHost("example".to_string())));
+    }
+}
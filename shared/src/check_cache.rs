@@ -0,0 +1,70 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Holds the last result of a health or readiness check for a short window,
+/// so a burst of probes runs the underlying check once instead of per request.
+#[derive(Debug)]
+pub struct CheckCache<T> {
+    ttl: Duration,
+    last: RwLock<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> CheckCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: RwLock::new(None),
+        }
+    }
+
+    /// Cached result if it's still fresh, otherwise runs `check` and caches its result
+    pub async fn get_or_refresh<F, Fut>(&self, check: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if let Some(value) = self.fresh(&*self.last.read().await) {
+            return value;
+        }
+
+        let mut last = self.last.write().await;
+        // Another request may have refreshed it while we waited for the lock
+        if let Some(value) = self.fresh(&last) {
+            return value;
+        }
+
+        let value = check().await;
+        *last = Some((Instant::now(), value.clone()));
+        value
+    }
+
+    fn fresh(&self, last: &Option<(Instant, T)>) -> Option<T> {
+        last.as_ref()
+            .filter(|(checked_at, _)| checked_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_check_runs_once_within_ttl_and_again_after_expiry() {
+        let runs = AtomicUsize::new(0);
+        let counter = &runs;
+        let check = move || async move { counter.fetch_add(1, Ordering::SeqCst) };
+
+        let cache = CheckCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get_or_refresh(check).await, 0);
+        assert_eq!(cache.get_or_refresh(check).await, 0);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let expired = CheckCache::new(Duration::ZERO);
+        expired.get_or_refresh(check).await;
+        expired.get_or_refresh(check).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+}
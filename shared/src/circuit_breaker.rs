@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::shopify::{ShopifyBackend, ShopifyError, ShopifyOrder, ShopifyProduct};
+
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+pub const DEFAULT_CIRCUIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    // Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    // How long an open circuit fails fast before letting a probe through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_CIRCUIT_COOLDOWN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    // When the circuit opened, or when the half-open probe was let through
+    changed_at: Instant,
+}
+
+/// Stops calling a backend that keeps failing. After `failure_threshold`
+/// consecutive failures the circuit opens and calls fail fast; once `cooldown`
+/// has passed one probe call is let through, which closes the circuit again on
+/// success or reopens it on failure.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuit: Mutex::new(Circuit {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                changed_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.circuit.lock().unwrap().state
+    }
+
+    /// Runs `call` unless the circuit is open, recording whether it failed
+    pub async fn call<T, F>(&self, call: F) -> Result<T, ShopifyError>
+    where
+        F: Future<Output = Result<T, ShopifyError>>,
+    {
+        self.acquire()?;
+        let result = call.await;
+        match &result {
+            Err(e) if is_outage(e) => self.record_failure(),
+            _ => self.record_success(),
+        }
+        result
+    }
+
+    fn acquire(&self) -> Result<(), ShopifyError> {
+        let mut circuit = self.circuit.lock().unwrap();
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            // A probe that never reports back (e.g. a cancelled request) must
+            // not hold the circuit half-open forever, so allow another after
+            // a further cooldown
+            CircuitState::Open | CircuitState::HalfOpen if circuit.changed_at.elapsed() >= self.config.cooldown => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.changed_at = Instant::now();
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => Err(ShopifyError::ApiError("circuit open".to_string())),
+        }
+    }
+
+    fn record_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        if circuit.state != CircuitState::Closed {
+            info!("Shopify circuit closed");
+        }
+        circuit.state = CircuitState::Closed;
+        circuit.consecutive_failures = 0;
+    }
+
+    fn record_failure(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures += 1;
+
+        let should_open = match circuit.state {
+            CircuitState::HalfOpen => true,
+            CircuitState::Closed => circuit.consecutive_failures >= self.config.failure_threshold,
+            CircuitState::Open => false,
+        };
+        if should_open {
+            warn!(
+                "Shopify circuit opened after {} consecutive failures",
+                circuit.consecutive_failures
+            );
+            circuit.state = CircuitState::Open;
+            circuit.changed_at = Instant::now();
+        }
+    }
+}
+
+// Errors that say the backend is unhealthy, as opposed to a bad request for
+// something like a missing product
+fn is_outage(error: &ShopifyError) -> bool {
+    matches!(
+        error,
+        ShopifyError::HttpError(_) | ShopifyError::ApiError(_) | ShopifyError::RateLimitExceeded
+    )
+}
+
+/// A `ShopifyBackend` whose calls all go through a `CircuitBreaker`
+pub struct CircuitBreakerBackend {
+    inner: Arc<dyn ShopifyBackend>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl CircuitBreakerBackend {
+    pub fn new(inner: Arc<dyn ShopifyBackend>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl ShopifyBackend for CircuitBreakerBackend {
+    async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        self.breaker.call(self.inner.get_products()).await
+    }
+
+    async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
+        self.breaker.call(self.inner.get_products_paginated(limit, page_info)).await
+    }
+
+    async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        self.breaker.call(self.inner.get_product(product_id)).await
+    }
+
+    async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        self.breaker.call(self.inner.get_products_by_ids(product_ids)).await
+    }
+
+    async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        self.breaker.call(self.inner.create_product(product)).await
+    }
+
+    async fn create_products(&self, products: Vec<ShopifyProduct>) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        self.breaker.call(self.inner.create_products(products)).await
+    }
+
+    async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        self.breaker.call(self.inner.update_product(product_id, product)).await
+    }
+
+    async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError> {
+        self.breaker.call(self.inner.delete_product(product_id)).await
+    }
+
+    async fn archive_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        self.breaker.call(self.inner.archive_product(product_id)).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
+        self.breaker.call(self.inner.get_orders()).await
+    }
+
+    async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+        self.breaker.call(self.inner.create_order(order)).await
+    }
+
+    async fn reserve_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        self.breaker.call(self.inner.reserve_inventory(items)).await
+    }
+
+    // Not gated: it undoes a reservation after a failed call, which may be the
+    // very failure that just opened the circuit
+    async fn release_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        self.inner.release_inventory(items).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shopify::MockShopifyClient;
+
+    const COOLDOWN: Duration = Duration::from_millis(50);
+
+    fn breaker_backend(mock: Arc<MockShopifyClient>) -> (Arc<CircuitBreaker>, CircuitBreakerBackend) {
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: COOLDOWN,
+        }));
+        (breaker.clone(), CircuitBreakerBackend::new(mock, breaker))
+    }
+
+    fn is_circuit_open(result: Result<ShopifyProduct, ShopifyError>) -> bool {
+        matches!(result, Err(ShopifyError::ApiError(message)) if message == "circuit open")
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_then_recovers_through_half_open() {
+        let mock = Arc::new(MockShopifyClient::new());
+        let (breaker, backend) = breaker_backend(mock.clone());
+        mock.set_available(false);
+
+        // Closed: failures reach the backend until the threshold
+        for _ in 0..2 {
+            assert_eq!(breaker.state(), CircuitState::Closed);
+            assert!(backend.get_product(1).await.is_err());
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(mock.api_call_count(), 2);
+
+        // Open: calls fail fast without touching the backend
+        assert!(is_circuit_open(backend.get_product(1).await));
+        assert_eq!(mock.api_call_count(), 2);
+
+        // Half-open: after the cooldown one failing probe reopens the circuit
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(!is_circuit_open(backend.get_product(1).await));
+        assert_eq!(mock.api_call_count(), 3);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(is_circuit_open(backend.get_product(1).await));
+
+        // A successful probe closes it again
+        mock.set_available(true);
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(backend.get_product(1).await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(backend.get_product(1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_not_found_does_not_open_circuit() {
+        let (breaker, backend) = breaker_backend(Arc::new(MockShopifyClient::new()));
+
+        for _ in 0..3 {
+            assert!(matches!(backend.get_product(999).await, Err(ShopifyError::ProductNotFound)));
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_failure_count() {
+        let mock = Arc::new(MockShopifyClient::new());
+        let (breaker, backend) = breaker_backend(mock.clone());
+
+        mock.set_available(false);
+        assert!(backend.get_product(1).await.is_err());
+        mock.set_available(true);
+        assert!(backend.get_product(1).await.is_ok());
+        mock.set_available(false);
+        assert!(backend.get_product(1).await.is_err());
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
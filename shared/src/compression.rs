@@ -0,0 +1,147 @@
+use tower_http::compression::predicate::{And, DefaultPredicate, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::CompressionLevel;
+use tracing::warn;
+
+pub const COMPRESSION_ALGORITHMS_ENV: &str = "COMPRESSION_ALGORITHMS";
+pub const COMPRESSION_LEVEL_ENV: &str = "COMPRESSION_LEVEL";
+pub const COMPRESSION_MIN_SIZE_ENV: &str = "COMPRESSION_MIN_SIZE";
+
+// Below about a kilobyte the gzip framing and CPU cost outweigh the savings
+pub const DEFAULT_COMPRESSION_MIN_SIZE: u16 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Gzip,
+    Deflate,
+}
+
+impl CompressionAlgorithm {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "gzip" => Some(Self::Gzip),
+            "deflate" => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    // Encodings offered; the client's Accept-Encoding picks among them
+    pub algorithms: Vec<CompressionAlgorithm>,
+    pub level: CompressionLevel,
+    // Responses smaller than this many bytes are sent uncompressed
+    pub min_size: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Deflate],
+            level: CompressionLevel::Default,
+            min_size: DEFAULT_COMPRESSION_MIN_SIZE,
+        }
+    }
+}
+
+pub type ResponseCompressionLayer = CompressionLayer<And<DefaultPredicate, SizeAbove>>;
+
+impl CompressionConfig {
+    /// Reads `COMPRESSION_ALGORITHMS` (a comma-separated list of `gzip` and
+    /// `deflate`, or `none`), `COMPRESSION_LEVEL` (`fastest`, `best`, `default`
+    /// or a number) and `COMPRESSION_MIN_SIZE` in bytes
+    pub fn from_env() -> Self {
+        Self::from_env_values(
+            std::env::var(COMPRESSION_ALGORITHMS_ENV).ok().as_deref(),
+            std::env::var(COMPRESSION_LEVEL_ENV).ok().as_deref(),
+            std::env::var(COMPRESSION_MIN_SIZE_ENV).ok().as_deref(),
+        )
+    }
+
+    pub fn from_env_values(algorithms: Option<&str>, level: Option<&str>, min_size: Option<&str>) -> Self {
+        let defaults = Self::default();
+
+        let algorithms = match algorithms.map(str::trim) {
+            None | Some("") => defaults.algorithms,
+            Some(value) if value.eq_ignore_ascii_case("none") => Vec::new(),
+            Some(value) => {
+                let parsed: Option<Vec<_>> = value.split(',').map(CompressionAlgorithm::parse).collect();
+                match parsed {
+                    Some(algorithms) => algorithms,
+                    None => {
+                        warn!("Invalid {} {:?}, using gzip and deflate", COMPRESSION_ALGORITHMS_ENV, value);
+                        defaults.algorithms
+                    }
+                }
+            }
+        };
+
+        let level = match level.map(|level| level.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("default") => CompressionLevel::Default,
+            Some("fastest") => CompressionLevel::Fastest,
+            Some("best") => CompressionLevel::Best,
+            Some(value) => match value.parse::<i32>() {
+                Ok(level) => CompressionLevel::Precise(level),
+                Err(_) => {
+                    warn!("Invalid {} {:?}, using the default level", COMPRESSION_LEVEL_ENV, value);
+                    CompressionLevel::Default
+                }
+            },
+        };
+
+        let min_size = match min_size.map(str::trim) {
+            None | Some("") => defaults.min_size,
+            Some(value) => value.parse::<u16>().unwrap_or_else(|_| {
+                warn!(
+                    "Invalid {} {:?}, using {}",
+                    COMPRESSION_MIN_SIZE_ENV, value, DEFAULT_COMPRESSION_MIN_SIZE
+                );
+                DEFAULT_COMPRESSION_MIN_SIZE
+            }),
+        };
+
+        Self { algorithms, level, min_size }
+    }
+
+    /// Response compression with these settings. Content that shouldn't be
+    /// compressed (images, gRPC, event streams) is still skipped as by default.
+    pub fn layer(&self) -> ResponseCompressionLayer {
+        CompressionLayer::new()
+            .gzip(self.algorithms.contains(&CompressionAlgorithm::Gzip))
+            .deflate(self.algorithms.contains(&CompressionAlgorithm::Deflate))
+            .quality(self.level)
+            .compress_when(DefaultPredicate::new().and(SizeAbove::new(self.min_size)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_config_from_env_values() {
+        let config = CompressionConfig::from_env_values(None, None, None);
+        assert_eq!(config.algorithms, vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Deflate]);
+        assert!(matches!(config.level, CompressionLevel::Default));
+        assert_eq!(config.min_size, DEFAULT_COMPRESSION_MIN_SIZE);
+
+        let config = CompressionConfig::from_env_values(Some("GZIP"), Some("fastest"), Some(" 256 "));
+        assert_eq!(config.algorithms, vec![CompressionAlgorithm::Gzip]);
+        assert!(matches!(config.level, CompressionLevel::Fastest));
+        assert_eq!(config.min_size, 256);
+
+        let config = CompressionConfig::from_env_values(Some("none"), Some("6"), None);
+        assert!(config.algorithms.is_empty());
+        assert!(matches!(config.level, CompressionLevel::Precise(6)));
+    }
+
+    #[test]
+    fn test_invalid_compression_values_fall_back_to_defaults() {
+        let config = CompressionConfig::from_env_values(Some("gzip,zip"), Some("max"), Some("-1"));
+
+        assert_eq!(config.algorithms, CompressionConfig::default().algorithms);
+        assert!(matches!(config.level, CompressionLevel::Default));
+        assert_eq!(config.min_size, DEFAULT_COMPRESSION_MIN_SIZE);
+    }
+}
@@ -0,0 +1,54 @@
+use axum::BoxError;
+use tower::load_shed::error::Overloaded;
+use tracing::{error, warn};
+
+use crate::errors::AppError;
+
+pub const MAX_CONCURRENT_REQUESTS_ENV: &str = "MAX_CONCURRENT_REQUESTS";
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 1024;
+
+/// Reads `MAX_CONCURRENT_REQUESTS`, the number of requests processed at once
+/// before new ones are turned away with `503 Service Unavailable`
+pub fn max_concurrent_requests_from_env() -> usize {
+    parse_max_concurrent_requests(std::env::var(MAX_CONCURRENT_REQUESTS_ENV).ok().as_deref())
+}
+
+pub fn parse_max_concurrent_requests(value: Option<&str>) -> usize {
+    match value.map(str::trim) {
+        None | Some("") => DEFAULT_MAX_CONCURRENT_REQUESTS,
+        Some(value) => match value.parse::<usize>() {
+            Ok(max) if max > 0 => max,
+            _ => {
+                warn!(
+                    "Invalid {} {:?}, using {}",
+                    MAX_CONCURRENT_REQUESTS_ENV, value, DEFAULT_MAX_CONCURRENT_REQUESTS
+                );
+                DEFAULT_MAX_CONCURRENT_REQUESTS
+            }
+        },
+    }
+}
+
+/// Error handler for the load-shedding concurrency limit: requests arriving
+/// while every permit is taken are rejected rather than queued
+pub async fn handle_overload(err: BoxError) -> AppError {
+    if err.is::<Overloaded>() {
+        AppError::Overloaded
+    } else {
+        error!("Unhandled middleware error: {}", err);
+        AppError::Internal(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_max_concurrent_requests() {
+        assert_eq!(parse_max_concurrent_requests(None), DEFAULT_MAX_CONCURRENT_REQUESTS);
+        assert_eq!(parse_max_concurrent_requests(Some(" 64 ")), 64);
+        assert_eq!(parse_max_concurrent_requests(Some("0")), DEFAULT_MAX_CONCURRENT_REQUESTS);
+        assert_eq!(parse_max_concurrent_requests(Some("lots")), DEFAULT_MAX_CONCURRENT_REQUESTS);
+    }
+}
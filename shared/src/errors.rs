@@ -0,0 +1,179 @@
+use axum::{
+    extract::rejection::JsonRejection,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use thiserror::Error;
+use tracing::error;
+
+use crate::auth::AuthError;
+use crate::models::ApiResponse;
+use crate::shopify::ShopifyError;
+
+// Error type for REST handlers, rendered as an `ApiResponse<()>` with a
+// machine-readable `error_code`
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Shopify(#[from] ShopifyError),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error("{0}")]
+    Validation(String),
+    #[error("{0}")]
+    BadRequest(String),
+    // A JSON request body that couldn't be read or deserialized
+    #[error("{}", .0.body_text())]
+    InvalidJson(#[from] JsonRejection),
+    #[error("Authentication required")]
+    Unauthorized,
+    // Authenticated, but without the role the endpoint needs
+    #[error("Insufficient permissions")]
+    Forbidden,
+    #[error("Too many requests")]
+    TooManyRequests,
+    // Every request slot is in use; see `concurrency_limit`
+    #[error("Server is overloaded")]
+    Overloaded,
+    #[error("Internal server error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Shopify(e) => match e {
+                ShopifyError::ProductNotFound | ShopifyError::OrderNotFound => StatusCode::NOT_FOUND,
+                ShopifyError::InvalidWebhookSignature | ShopifyError::StaleWebhook => {
+                    StatusCode::UNAUTHORIZED
+                }
+                ShopifyError::InvalidWebhookPayload(_) | ShopifyError::InvalidGid { .. } => StatusCode::BAD_REQUEST,
+                ShopifyError::InsufficientInventory { .. } => StatusCode::CONFLICT,
+                ShopifyError::RateLimitExceeded => StatusCode::SERVICE_UNAVAILABLE,
+                ShopifyError::HttpError(_)
+                | ShopifyError::AuthenticationFailed
+                | ShopifyError::ApiError(_) => StatusCode::BAD_GATEWAY,
+            },
+            AppError::Auth(e) => match e {
+                AuthError::InvalidCredentials
+                | AuthError::AccountLocked { .. }
+                | AuthError::TokenExpired
+                | AuthError::InvalidToken => StatusCode::UNAUTHORIZED,
+                AuthError::UserNotFound => StatusCode::NOT_FOUND,
+                AuthError::EmailAlreadyExists => StatusCode::CONFLICT,
+                AuthError::PasswordHashingFailed
+                | AuthError::InvalidBcryptCost(_)
+                | AuthError::JwtError(_) => {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            },
+            AppError::Validation(_) | AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            // 400 for malformed JSON, 422 for JSON of the wrong shape, 415 without
+            // a JSON content type
+            AppError::InvalidJson(e) => e.status(),
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            AppError::Shopify(e) => match e {
+                ShopifyError::ProductNotFound => "PRODUCT_NOT_FOUND",
+                ShopifyError::OrderNotFound => "ORDER_NOT_FOUND",
+                ShopifyError::InvalidWebhookSignature => "INVALID_WEBHOOK_SIGNATURE",
+                ShopifyError::InvalidWebhookPayload(_) => "INVALID_WEBHOOK_PAYLOAD",
+                ShopifyError::StaleWebhook => "STALE_WEBHOOK",
+                ShopifyError::InvalidGid { .. } => "INVALID_GID",
+                ShopifyError::InsufficientInventory { .. } => "INSUFFICIENT_INVENTORY",
+                ShopifyError::RateLimitExceeded => "SHOPIFY_RATE_LIMITED",
+                ShopifyError::HttpError(_)
+                | ShopifyError::AuthenticationFailed
+                | ShopifyError::ApiError(_) => "SHOPIFY_ERROR",
+            },
+            AppError::Auth(e) => match e {
+                AuthError::InvalidCredentials => "INVALID_CREDENTIALS",
+                AuthError::AccountLocked { .. } => "ACCOUNT_LOCKED",
+                AuthError::TokenExpired => "TOKEN_EXPIRED",
+                AuthError::InvalidToken => "INVALID_TOKEN",
+                AuthError::UserNotFound => "USER_NOT_FOUND",
+                AuthError::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+                AuthError::PasswordHashingFailed
+                | AuthError::InvalidBcryptCost(_)
+                | AuthError::JwtError(_) => "INTERNAL_ERROR",
+            },
+            AppError::Validation(_) => "VALIDATION_ERROR",
+            AppError::BadRequest(_) => "BAD_REQUEST",
+            AppError::InvalidJson(e) => match e {
+                JsonRejection::JsonSyntaxError(_) => "MALFORMED_JSON",
+                JsonRejection::JsonDataError(_) => "INVALID_JSON_BODY",
+                JsonRejection::MissingJsonContentType(_) => "UNSUPPORTED_MEDIA_TYPE",
+                _ if e.status() == StatusCode::PAYLOAD_TOO_LARGE => "PAYLOAD_TOO_LARGE",
+                _ => "BAD_REQUEST",
+            },
+            AppError::Unauthorized => "UNAUTHORIZED",
+            AppError::Forbidden => "FORBIDDEN",
+            AppError::TooManyRequests => "TOO_MANY_REQUESTS",
+            AppError::Overloaded => "OVERLOADED",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+
+        // Don't leak internal details to clients, but keep them in the logs
+        let message = if status.is_server_error() {
+            error!("Request failed: {}", self);
+            status.canonical_reason().unwrap_or("Internal Server Error").to_string()
+        } else {
+            self.to_string()
+        };
+
+        let body = ApiResponse::<()>::error_with_code(self.error_code(), message);
+        let mut response = (status, Json(body)).into_response();
+        if let AppError::Auth(AuthError::AccountLocked { retry_after_secs }) = self {
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shopify_errors_map_to_status_and_code() {
+        let error = AppError::from(ShopifyError::ProductNotFound);
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(error.error_code(), "PRODUCT_NOT_FOUND");
+
+        let error = AppError::from(ShopifyError::ApiError("HTTP 500".to_string()));
+        assert_eq!(error.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(error.error_code(), "SHOPIFY_ERROR");
+
+        let error = AppError::from(ShopifyError::InsufficientInventory { product_id: 1, requested: 2, available: 1 });
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.error_code(), "INSUFFICIENT_INVENTORY");
+    }
+
+    #[test]
+    fn test_auth_errors_map_to_status_and_code() {
+        let error = AppError::from(AuthError::TokenExpired);
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(error.error_code(), "TOKEN_EXPIRED");
+
+        let error = AppError::from(AuthError::EmailAlreadyExists);
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+
+        let response = AppError::from(AuthError::AccountLocked { retry_after_secs: 90 }).into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[RETRY_AFTER], "90");
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::shopify::ShopifyError;
+
+const GID_PREFIX: &str = "gid://shopify/";
+
+/// A Shopify global ID, `gid://shopify/{Type}/{id}`, as used by the Admin
+/// GraphQL API and in `admin_graphql_api_id` fields
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Gid {
+    resource_type: String,
+    id: i64,
+}
+
+impl Gid {
+    pub fn new(resource_type: impl Into<String>, id: i64) -> Self {
+        Self {
+            resource_type: resource_type.into(),
+            id,
+        }
+    }
+
+    /// Parses `gid`, also requiring it to name a `resource_type`, e.g. "Product"
+    pub fn parse_as(gid: &str, resource_type: &str) -> Result<Self, ShopifyError> {
+        let parsed: Self = gid.parse()?;
+        if parsed.resource_type != resource_type {
+            return Err(invalid(gid, format!("expected a {} ID", resource_type)));
+        }
+        Ok(parsed)
+    }
+
+    pub fn resource_type(&self) -> &str {
+        &self.resource_type
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+fn invalid(gid: &str, reason: impl Into<String>) -> ShopifyError {
+    ShopifyError::InvalidGid {
+        gid: gid.to_string(),
+        reason: reason.into(),
+    }
+}
+
+impl FromStr for Gid {
+    type Err = ShopifyError;
+
+    fn from_str(gid: &str) -> Result<Self, Self::Err> {
+        let rest = gid
+            .strip_prefix(GID_PREFIX)
+            .ok_or_else(|| invalid(gid, format!("expected it to start with {}", GID_PREFIX)))?;
+        let (resource_type, id) = rest
+            .split_once('/')
+            .ok_or_else(|| invalid(gid, "expected a resource type and an ID"))?;
+
+        if resource_type.is_empty() || !resource_type.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid(gid, "the resource type must be alphanumeric"));
+        }
+        // Checked by hand since `parse` would also take a sign
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid(gid, "the ID must be numeric"));
+        }
+        let id = id.parse().map_err(|_| invalid(gid, "the ID is out of range"))?;
+
+        Ok(Self::new(resource_type, id))
+    }
+}
+
+impl fmt::Display for Gid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", GID_PREFIX, self.resource_type, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_gid() {
+        let gid: Gid = "gid://shopify/Product/123".parse().unwrap();
+
+        assert_eq!(gid.resource_type(), "Product");
+        assert_eq!(gid.id(), 123);
+        assert_eq!(gid.to_string(), "gid://shopify/Product/123");
+        assert_eq!(Gid::new("Order", 1001).to_string().parse::<Gid>().unwrap(), Gid::new("Order", 1001));
+    }
+
+    #[test]
+    fn test_gid_with_wrong_scheme_is_rejected() {
+        for gid in ["https://shopify/Product/123", "gid://other/Product/123", "Product/123"] {
+            assert!(matches!(gid.parse::<Gid>(), Err(ShopifyError::InvalidGid { .. })), "{}", gid);
+        }
+    }
+
+    #[test]
+    fn test_gid_with_non_numeric_id_is_rejected() {
+        for gid in ["gid://shopify/Product/abc", "gid://shopify/Product/", "gid://shopify/Product/-1", "gid://shopify/Product/1/2"] {
+            match gid.parse::<Gid>() {
+                Err(ShopifyError::InvalidGid { reason, .. }) => assert_eq!(reason, "the ID must be numeric", "{}", gid),
+                other => panic!("expected {} to be rejected, got {:?}", gid, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_as_checks_resource_type() {
+        assert_eq!(Gid::parse_as("gid://shopify/Order/7", "Order").unwrap().id(), 7);
+        assert!(matches!(
+            Gid::parse_as("gid://shopify/Order/7", "Product"),
+            Err(ShopifyError::InvalidGid { .. })
+        ));
+    }
+}
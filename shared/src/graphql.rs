@@ -1,13 +1,151 @@
-use async_graphql::{Context, Object, Schema, Subscription, Result};
-use chrono::Utc;
+use async_graphql::{Context, ErrorExtensions, Guard, Object, Schema, SimpleObject, Subscription, Result};
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use async_graphql::extensions::apollo_persisted_queries::{ApolloPersistedQueries, LruCacheStorage};
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio_stream::Stream;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 use futures_util::stream;
+use tracing::warn;
 
 use crate::models::*;
 use crate::auth::*;
 use crate::shopify::*;
+use crate::request_id::RequestId;
+use crate::users::{email_key, InMemoryUserRepository, UserRepository};
+
+/// Resolver failures. Each is returned with a stable `code` extension clients
+/// can branch on, instead of matching on the message.
+#[derive(Debug, thiserror::Error)]
+pub enum GraphQLError {
+    #[error("Authentication required")]
+    Unauthenticated,
+    #[error("Insufficient permissions")]
+    Unauthorized,
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Validation(String),
+    #[error("Cannot change order status from {from:?} to {to:?}")]
+    InvalidStatusTransition { from: OrderStatus, to: OrderStatus },
+    #[error("Shopify error: {0}")]
+    Shopify(Arc<ShopifyError>),
+    #[error("{0}")]
+    Auth(Arc<AuthError>),
+    #[error("Too many requests")]
+    TooManyRequests,
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl GraphQLError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            GraphQLError::Unauthenticated => "UNAUTHENTICATED",
+            GraphQLError::Unauthorized => "UNAUTHORIZED",
+            GraphQLError::NotFound(_) => "NOT_FOUND",
+            GraphQLError::Validation(_) => "VALIDATION_ERROR",
+            GraphQLError::InvalidStatusTransition { .. } => "INVALID_STATUS_TRANSITION",
+            GraphQLError::Shopify(e) => match e.as_ref() {
+                ShopifyError::ProductNotFound | ShopifyError::OrderNotFound => "NOT_FOUND",
+                ShopifyError::InsufficientInventory { .. } => "INSUFFICIENT_INVENTORY",
+                ShopifyError::InvalidGid { .. } => "VALIDATION_ERROR",
+                _ => "SHOPIFY_ERROR",
+            },
+            GraphQLError::Auth(e) => match e.as_ref() {
+                AuthError::InvalidCredentials => "INVALID_CREDENTIALS",
+                AuthError::AccountLocked { .. } => "ACCOUNT_LOCKED",
+                AuthError::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+                AuthError::TokenExpired | AuthError::InvalidToken => "UNAUTHENTICATED",
+                AuthError::UserNotFound => "NOT_FOUND",
+                _ => "INTERNAL_ERROR",
+            },
+            GraphQLError::TooManyRequests => "TOO_MANY_REQUESTS",
+            GraphQLError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl ErrorExtensions for GraphQLError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| e.set("code", self.code()))
+    }
+}
+
+impl From<ShopifyError> for GraphQLError {
+    fn from(e: ShopifyError) -> Self {
+        GraphQLError::Shopify(Arc::new(e))
+    }
+}
+
+impl From<AuthError> for GraphQLError {
+    fn from(e: AuthError) -> Self {
+        GraphQLError::Auth(Arc::new(e))
+    }
+}
+
+// The product loader shares one error between every key in a batch
+impl From<Arc<ShopifyError>> for GraphQLError {
+    fn from(e: Arc<ShopifyError>) -> Self {
+        GraphQLError::Shopify(e)
+    }
+}
+
+/// Adds the request's ID to every error of every operation in the batch, so a
+/// client's report can be matched to the server logs for that request
+pub fn with_request_id(mut response: async_graphql::BatchResponse, request_id: &RequestId) -> async_graphql::BatchResponse {
+    let responses = match &mut response {
+        async_graphql::BatchResponse::Single(response) => std::slice::from_mut(response),
+        async_graphql::BatchResponse::Batch(responses) => responses.as_mut_slice(),
+    };
+
+    for error in responses.iter_mut().flat_map(|response| response.errors.iter_mut()) {
+        error
+            .extensions
+            .get_or_insert_with(Default::default)
+            .set("request_id", request_id.as_str());
+    }
+    response
+}
+
+// Batches product lookups within a request into a single Shopify call
+pub struct ProductLoader {
+    shopify_client: Arc<dyn ShopifyBackend>,
+}
+
+impl ProductLoader {
+    pub fn new(shopify_client: Arc<dyn ShopifyBackend>) -> Self {
+        Self { shopify_client }
+    }
+}
+
+impl Loader<Uuid> for ProductLoader {
+    type Value = Product;
+    type Error = Arc<ShopifyError>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Product>, Self::Error> {
+        let shopify_ids: Vec<i64> = keys
+            .iter()
+            .filter_map(|id| shopify_id_from_product_uuid(*id))
+            .collect();
+
+        if shopify_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let products = self.shopify_client.get_products_by_ids(&shopify_ids).await
+            .map_err(Arc::new)?;
+
+        Ok(products
+            .into_iter()
+            .map(product_from_shopify)
+            .map(|product| (product.id, product))
+            .collect())
+    }
+}
 
 // GraphQL Context
 #[derive(Clone)]
@@ -15,23 +153,203 @@ pub struct GraphQLContext {
     #[allow(dead_code)]
     pub auth_service: Arc<AuthService>,
     #[allow(dead_code)]
-    pub shopify_client: Arc<MockShopifyClient>,
+    pub shopify_client: Arc<dyn ShopifyBackend>,
+    pub product_loader: Arc<DataLoader<ProductLoader, HashMapCache>>,
+    pub order_events: broadcast::Sender<Order>,
+    pub users: Arc<dyn UserRepository>,
+    pub login_rate_limiter: Arc<RateLimiter>,
+    // Also create new orders in Shopify, as the REST endpoint does
+    pub sync_orders_to_shopify: bool,
     pub current_user: Option<AuthenticatedUser>,
 }
 
+// Slow subscribers that fall further behind than this skip the missed orders
+pub const ORDER_EVENTS_CAPACITY: usize = 256;
+
+/// Channel that order mutations publish to and `orderUpdates` subscribers read
+/// from. Create one per server and share it with every request context.
+pub fn order_events_channel() -> broadcast::Sender<Order> {
+    broadcast::channel(ORDER_EVENTS_CAPACITY).0
+}
+
 impl GraphQLContext {
-    pub fn new(auth_service: Arc<AuthService>, shopify_client: Arc<MockShopifyClient>) -> Self {
+    pub fn new(auth_service: Arc<AuthService>, shopify_client: Arc<dyn ShopifyBackend>) -> Self {
+        let product_loader = DataLoader::with_cache(
+            ProductLoader::new(shopify_client.clone()),
+            tokio::spawn,
+            HashMapCache::default(),
+        );
+
         Self {
+            users: Arc::new(InMemoryUserRepository::new(auth_service.clone())),
             auth_service,
             shopify_client,
+            product_loader: Arc::new(product_loader),
+            order_events: order_events_channel(),
+            login_rate_limiter: Arc::new(RateLimiter::new(5, 15)),
+            sync_orders_to_shopify: false,
             current_user: None,
         }
     }
 
+    /// Uses the server's user store and login rate limiter, so GraphQL and
+    /// REST logins see the same users and count the same failures
+    pub fn with_users(mut self, users: Arc<dyn UserRepository>, login_rate_limiter: Arc<RateLimiter>) -> Self {
+        self.users = users;
+        self.login_rate_limiter = login_rate_limiter;
+        self
+    }
+
+    pub fn with_order_events(mut self, order_events: broadcast::Sender<Order>) -> Self {
+        self.order_events = order_events;
+        self
+    }
+
+    pub fn with_order_sync(mut self, sync_orders_to_shopify: bool) -> Self {
+        self.sync_orders_to_shopify = sync_orders_to_shopify;
+        self
+    }
+
     pub fn with_user(mut self, user: AuthenticatedUser) -> Self {
         self.current_user = Some(user);
         self
     }
+
+    /// Authenticates a GraphQL-over-WebSocket connection from its
+    /// `connection_init` payload (`{"Authorization": "Bearer <token>"}`).
+    /// Without a token the connection is anonymous; a bad token rejects it.
+    pub fn with_init_payload(self, init_payload: &serde_json::Value) -> Result<Self> {
+        let Some(token) = bearer_token_from_init_payload(init_payload) else {
+            return Ok(self);
+        };
+
+        let user = self
+            .auth_service
+            .verify_token(token)
+            .and_then(AuthenticatedUser::from_claims)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        Ok(self.with_user(user))
+    }
+}
+
+// Clients differ in how they capitalise the key
+fn bearer_token_from_init_payload(payload: &serde_json::Value) -> Option<&str> {
+    ["Authorization", "authorization"]
+        .iter()
+        .find_map(|key| payload.get(key))
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Rejects callers whose role is below `role`, with an `UNAUTHENTICATED` or
+/// `UNAUTHORIZED` extension code.
+pub struct RoleGuard {
+    role: Role,
+}
+
+impl RoleGuard {
+    pub fn new(role: Role) -> Self {
+        Self { role }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        let context = ctx.data::<GraphQLContext>()?;
+
+        let error = match &context.current_user {
+            Some(user) if user.role >= self.role => return Ok(()),
+            Some(_) => GraphQLError::Unauthorized,
+            None => GraphQLError::Unauthenticated,
+        };
+
+        Err(error.extend())
+    }
+}
+
+// Relay-style pagination types
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ProductEdge {
+    pub cursor: String,
+    pub node: Product,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ProductConnection {
+    pub edges: Vec<ProductEdge>,
+    pub page_info: PageInfo,
+    pub total_count: i32,
+}
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+// Fixed complexity cost of a field that fetches a whole listing, where a
+// plain field costs 1
+const LIST_FIELD_COST: usize = 10;
+
+// A page's selection is resolved once per requested item, so its cost scales
+// with `first` and a huge page can't slip under the complexity limit
+fn page_complexity(first: Option<i32>, child_complexity: usize) -> usize {
+    let first = first.map_or(DEFAULT_PAGE_SIZE, |first| first.max(0) as usize);
+    LIST_FIELD_COST.saturating_add(first.saturating_mul(child_complexity))
+}
+
+// Cursors are opaque base64-encoded list indices
+fn encode_cursor(index: usize) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(index.to_string())
+}
+
+fn decode_cursor(cursor: &str) -> Result<usize> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| GraphQLError::Validation("Invalid cursor".to_string()).extend())
+}
+
+fn paginate_products(products: Vec<Product>, first: Option<i32>, after: Option<String>) -> Result<ProductConnection> {
+    let first = match first {
+        Some(first) if first < 0 => return Err(GraphQLError::Validation("`first` must not be negative".to_string()).extend()),
+        Some(first) => first as usize,
+        None => DEFAULT_PAGE_SIZE,
+    };
+    let start = match after {
+        Some(cursor) => decode_cursor(&cursor)? + 1,
+        None => 0,
+    };
+
+    let total_count = products.len();
+    let edges: Vec<ProductEdge> = products
+        .into_iter()
+        .enumerate()
+        .skip(start)
+        .take(first)
+        .map(|(index, node)| ProductEdge {
+            cursor: encode_cursor(index),
+            node,
+        })
+        .collect();
+
+    let end_cursor = edges.last().map(|edge| edge.cursor.clone());
+    let has_next_page = start.saturating_add(edges.len()) < total_count;
+
+    Ok(ProductConnection {
+        edges,
+        page_info: PageInfo {
+            has_next_page,
+            end_cursor,
+        },
+        total_count: total_count as i32,
+    })
 }
 
 // Query Root
@@ -57,6 +375,7 @@ impl QueryRoot {
     }
 
     /// Get all users (admin only)
+    #[graphql(guard = "RoleGuard::new(Role::Admin)", complexity = "LIST_FIELD_COST + child_complexity")]
     async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
         let _context = ctx.data::<GraphQLContext>()?;
         
@@ -79,65 +398,82 @@ impl QueryRoot {
         ])
     }
 
-    /// Get all products
-    async fn products(&self, ctx: &Context<'_>) -> Result<Vec<Product>> {
+    /// Get a page of products. If Shopify can't be reached this is an empty
+    /// page plus a `SHOPIFY_ERROR` entry in `errors`, so the rest of the query
+    /// still resolves.
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn products(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        #[graphql(default, desc = "Also return archived products")] include_archived: bool,
+    ) -> Result<ProductConnection> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        let shopify_products = context.shopify_client.get_products().await
-            .map_err(|e| async_graphql::Error::new(format!("Shopify error: {}", e)))?;
+        let shopify_products = match context.shopify_client.get_products().await {
+            Ok(products) => products,
+            Err(e) => {
+                warn!(error = %e, "failed to fetch products from Shopify");
+                let error = GraphQLError::from(e).extend().into_server_error(ctx.item.pos);
+                ctx.add_error(ctx.set_error_path(error));
+                Vec::new()
+            }
+        };
 
-        let products = shopify_products
+        let products: Vec<Product> = shopify_products
             .into_iter()
-            .map(|sp| Product {
-                id: Uuid::new_v4(),
-                name: sp.title,
-                description: sp.body_html,
-                price: 99.99, // Mock price
-                shopify_id: sp.id.map(|id| id.to_string()),
-                created_at: sp.created_at.unwrap_or_else(Utc::now),
-                updated_at: sp.updated_at.unwrap_or_else(Utc::now),
-            })
+            .map(product_from_shopify)
             .collect();
 
-        Ok(products)
+        // Prime the loader so later `product(id)` lookups in this request are served from cache
+        context.product_loader
+            .feed_many(products.iter().map(|product| (product.id, product.clone())))
+            .await;
+
+        let products = products
+            .into_iter()
+            .filter(|product| include_archived || !product.archived)
+            .collect();
+        paginate_products(products, first, after)
     }
 
     /// Get product by ID
     async fn product(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Product>> {
-        let _context = ctx.data::<GraphQLContext>()?;
+        let context = ctx.data::<GraphQLContext>()?;
         
-        // Mock product lookup
-        Ok(Some(Product {
-            id,
-            name: "Mock Product".to_string(),
-            description: Some("This is a mock product for demo".to_string()),
-            price: 99.99,
-            shopify_id: Some("1".to_string()),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        }))
+        context.product_loader.load_one(id).await
+            .map_err(|e| GraphQLError::from(e).extend())
     }
 
-    /// Get all orders for current user
-    async fn my_orders(&self, ctx: &Context<'_>) -> Result<Vec<Order>> {
+    /// Get the current user's orders, optionally filtered by status and creation
+    /// date. `createdAfter` is inclusive and `createdBefore` is exclusive.
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn my_orders(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        status: Option<OrderStatus>,
+        #[graphql(desc = "Only orders created at or after this time")] created_after: Option<DateTime<Utc>>,
+        #[graphql(desc = "Only orders created before this time")] created_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Order>> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        if let Some(current_user) = &context.current_user {
-            // Mock orders for demo
-            Ok(vec![
-                Order {
-                    id: Uuid::new_v4(),
-                    user_id: current_user.id,
-                    total_amount: 199.98,
-                    status: OrderStatus::Processing,
-                    shopify_order_id: Some("1001".to_string()),
-                    created_at: Utc::now(),
-                    updated_at: Utc::now(),
-                },
-            ])
-        } else {
-            Err(async_graphql::Error::new("Authentication required"))
-        }
+        let current_user = context.current_user.as_ref()
+            .ok_or_else(|| GraphQLError::Unauthenticated.extend())?;
+
+        let first = match first {
+            Some(first) if first < 0 => return Err(GraphQLError::Validation("`first` must not be negative".to_string()).extend()),
+            Some(first) => first as usize,
+            None => DEFAULT_PAGE_SIZE,
+        };
+        let filter = OrderFilter { status, created_after, created_before };
+
+        Ok(mock_orders_for_user(current_user.id)
+            .into_iter()
+            .filter(|order| filter.matches(order))
+            .take(first)
+            .collect())
     }
 
     /// Get order by ID
@@ -145,19 +481,11 @@ impl QueryRoot {
         let context = ctx.data::<GraphQLContext>()?;
         
         if context.current_user.is_none() {
-            return Err(async_graphql::Error::new("Authentication required"));
+            return Err(GraphQLError::Unauthenticated.extend());
         }
 
         // Mock order lookup
-        Ok(Some(Order {
-            id,
-            user_id: Uuid::new_v4(),
-            total_amount: 99.99,
-            status: OrderStatus::Delivered,
-            shopify_order_id: Some("1002".to_string()),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        }))
+        Ok(Some(mock_order(id, Uuid::new_v4())))
     }
 
     /// Health check
@@ -175,54 +503,57 @@ impl MutationRoot {
     async fn register(&self, ctx: &Context<'_>, input: CreateUserInput) -> Result<AuthResponse> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        // Validate password
-        if let Err(errors) = PasswordValidator::validate(&input.password) {
-            return Err(async_graphql::Error::new(format!("Password validation failed: {}", errors.join(", "))));
+        // Validate email and password
+        if let Err(errors) = input.validate() {
+            return Err(GraphQLError::Validation(format!("Validation failed: {}", errors.join(", "))).extend());
         }
 
-        // Hash password
-        let _password_hash = context.auth_service.hash_password(&input.password)
-            .map_err(|e| async_graphql::Error::new(format!("Password hashing failed: {}", e)))?;
-
-        // Create user (mock implementation)
-        let user_id = Uuid::new_v4();
-        let user = User {
-            id: user_id,
-            email: input.email.clone(),
-            name: input.name.clone(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        let user = context.users.create(&input).await
+            .map_err(|e| GraphQLError::from(e).extend())?;
 
         // Generate JWT token
-        let claims = Claims::new(user_id, input.email, input.name, 24);
+        let claims = Claims::new(user.id, user.email.clone(), user.name.clone(), context.auth_service.token_expiry_hours());
         let token = context.auth_service.generate_token(&claims)
-            .map_err(|e| async_graphql::Error::new(format!("Token generation failed: {}", e)))?;
+            .map_err(|e| GraphQLError::Internal(format!("Token generation failed: {}", e)).extend())?;
+        let refresh_token = context.auth_service.generate_refresh_token(&claims)
+            .map_err(|e| GraphQLError::Internal(format!("Token generation failed: {}", e)).extend())?;
 
-        Ok(AuthResponse { token, user })
+        Ok(AuthResponse { token, refresh_token, user })
     }
 
     /// Login user
     async fn login(&self, ctx: &Context<'_>, input: LoginInput) -> Result<AuthResponse> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        // Mock user lookup and password verification
-        // In real implementation, this would query the database
-        let user_id = Uuid::new_v4();
-        let user = User {
-            id: user_id,
-            email: input.email.clone(),
-            name: "Mock User".to_string(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+        // The same lockout and rate limit as the REST login
+        context.auth_service.check_login_allowed(&input.email)
+            .map_err(|e| GraphQLError::from(e).extend())?;
+        // Keyed like the lockout, so other spellings of the email share its attempts
+        let rate_limit_key = email_key(&input.email);
+        if !context.login_rate_limiter.check_rate_limit(&rate_limit_key) {
+            warn!("Too many failed login attempts for {}", input.email);
+            return Err(GraphQLError::TooManyRequests.extend());
+        }
+
+        let user = match context.users.verify_credentials(&input.email, &input.password).await {
+            Err(AuthError::InvalidCredentials) => {
+                context.login_rate_limiter.record_attempt(&rate_limit_key);
+                context.auth_service.record_failed_login(&input.email);
+                return Err(GraphQLError::from(AuthError::InvalidCredentials).extend());
+            }
+            result => result.map_err(|e| GraphQLError::from(e).extend())?,
         };
 
         // Generate JWT token
-        let claims = Claims::new(user_id, input.email, "Mock User".to_string(), 24);
+        let claims = Claims::new(user.id, user.email.clone(), user.name.clone(), context.auth_service.token_expiry_hours());
         let token = context.auth_service.generate_token(&claims)
-            .map_err(|e| async_graphql::Error::new(format!("Token generation failed: {}", e)))?;
+            .map_err(|e| GraphQLError::Internal(format!("Token generation failed: {}", e)).extend())?;
+        let refresh_token = context.auth_service.generate_refresh_token(&claims)
+            .map_err(|e| GraphQLError::Internal(format!("Token generation failed: {}", e)).extend())?;
 
-        Ok(AuthResponse { token, user })
+        context.login_rate_limiter.reset(&rate_limit_key);
+        context.auth_service.record_successful_login(&input.email);
+        Ok(AuthResponse { token, refresh_token, user })
     }
 
     /// Create a new product
@@ -230,7 +561,7 @@ impl MutationRoot {
         let context = ctx.data::<GraphQLContext>()?;
         
         if context.current_user.is_none() {
-            return Err(async_graphql::Error::new("Authentication required"));
+            return Err(GraphQLError::Unauthenticated.extend());
         }
 
         // Create Shopify product
@@ -248,71 +579,191 @@ impl MutationRoot {
             published_scope: "web".to_string(),
             tags: "".to_string(),
             admin_graphql_api_id: None,
-            variants: vec![],
+            variants: vec![ShopifyVariant::priced(input.price)],
             options: vec![],
             images: vec![],
         };
 
         let created_shopify_product = context.shopify_client.create_product(&shopify_product).await
-            .map_err(|e| async_graphql::Error::new(format!("Shopify error: {}", e)))?;
+            .map_err(|e| GraphQLError::from(e).extend())?;
 
-        // Create local product
-        let product = Product {
-            id: Uuid::new_v4(),
-            name: input.name,
-            description: input.description,
-            price: input.price,
-            shopify_id: created_shopify_product.id.map(|id| id.to_string()),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        Ok(product_from_shopify(created_shopify_product))
+    }
 
-        Ok(product)
+    /// Update a product, applying only the provided fields
+    async fn update_product(&self, ctx: &Context<'_>, id: Uuid, input: UpdateProductInput) -> Result<Product> {
+        let context = ctx.data::<GraphQLContext>()?;
+        
+        if context.current_user.is_none() {
+            return Err(GraphQLError::Unauthenticated.extend());
+        }
+
+        let shopify_id = shopify_id_from_product_uuid(id)
+            .ok_or_else(|| GraphQLError::NotFound("Product not found".to_string()).extend())?;
+
+        let mut shopify_product = context.shopify_client.get_product(shopify_id).await
+            .map_err(|e| GraphQLError::from(e).extend())?;
+
+        if !input.is_empty() {
+            shopify_product.apply_update(&input)
+                .map_err(|e| GraphQLError::Validation(e).extend())?;
+            shopify_product = context.shopify_client.update_product(shopify_id, &shopify_product).await
+                .map_err(|e| GraphQLError::from(e).extend())?;
+        }
+
+        Ok(product_from_shopify(shopify_product))
     }
 
-    /// Create a new order
-    async fn create_order(&self, ctx: &Context<'_>, product_ids: Vec<Uuid>) -> Result<Order> {
+    /// Delete a product
+    async fn delete_product(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let context = ctx.data::<GraphQLContext>()?;
+        
+        if context.current_user.is_none() {
+            return Err(GraphQLError::Unauthenticated.extend());
+        }
+
+        let shopify_id = shopify_id_from_product_uuid(id)
+            .ok_or_else(|| GraphQLError::NotFound("Product not found".to_string()).extend())?;
+
+        context.shopify_client.delete_product(shopify_id).await
+            .map_err(|e| GraphQLError::from(e).extend())?;
+
+        Ok(true)
+    }
+
+    /// Archive a product: it's kept, but hidden from `products` unless
+    /// `includeArchived` is set
+    async fn archive_product(&self, ctx: &Context<'_>, id: Uuid) -> Result<Product> {
+        let context = ctx.data::<GraphQLContext>()?;
+
+        if context.current_user.is_none() {
+            return Err(GraphQLError::Unauthenticated.extend());
+        }
+
+        let shopify_id = shopify_id_from_product_uuid(id)
+            .ok_or_else(|| GraphQLError::NotFound("Product not found".to_string()).extend())?;
+
+        let shopify_product = context.shopify_client.archive_product(shopify_id).await
+            .map_err(|e| GraphQLError::from(e).extend())?;
+
+        Ok(product_from_shopify(shopify_product))
+    }
+
+    /// Create a new order, pricing each line at the product's current price
+    async fn create_order(&self, ctx: &Context<'_>, lines: Vec<OrderLineInput>) -> Result<Order> {
         let context = ctx.data::<GraphQLContext>()?;
         
         let current_user = context.current_user.as_ref()
-            .ok_or_else(|| async_graphql::Error::new("Authentication required"))?;
+            .ok_or_else(|| GraphQLError::Unauthenticated.extend())?;
 
-        // Mock order creation
-        let total_amount = product_ids.len() as f64 * 99.99; // Mock calculation
+        OrderLineInput::validate_all(&lines).map_err(|e| GraphQLError::Validation(e).extend())?;
 
-        let order = Order {
-            id: Uuid::new_v4(),
-            user_id: current_user.id,
-            total_amount,
-            status: OrderStatus::Pending,
-            shopify_order_id: Some(format!("order_{}", Uuid::new_v4())),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        let mut line_items = Vec::with_capacity(lines.len());
+        for line in lines {
+            let product = context.product_loader.load_one(line.product_id).await
+                .map_err(|e| GraphQLError::from(e).extend())?
+                .ok_or_else(|| GraphQLError::NotFound(format!("Product {} not found", line.product_id)).extend())?;
+
+            line_items.push(LineItem {
+                product_id: line.product_id,
+                quantity: line.quantity,
+                unit_price: product.price,
+            });
+        }
+
+        let mut order = Order::new(current_user.id, line_items);
+        place_order(context.shopify_client.as_ref(), &mut order, context.sync_orders_to_shopify).await
+            .map_err(|e| GraphQLError::from(e).extend())?;
+
+        // Sending only fails when nobody is subscribed
+        let _ = context.order_events.send(order.clone());
 
         Ok(order)
     }
 
-    /// Update order status
+    /// Update order status (admin only)
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
     async fn update_order_status(&self, ctx: &Context<'_>, order_id: Uuid, status: OrderStatus) -> Result<Order> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        if context.current_user.is_none() {
-            return Err(async_graphql::Error::new("Authentication required"));
-        }
+        let current_user = context.current_user.as_ref()
+            .ok_or_else(|| GraphQLError::Unauthenticated.extend())?;
 
-        // Mock order update
-        let order = Order {
-            id: order_id,
-            user_id: Uuid::new_v4(),
-            total_amount: 99.99,
+        transition_order(context, order_id, current_user.id, status)
+    }
+
+    /// Cancel one of the current user's orders, if it hasn't shipped yet
+    async fn cancel_order(&self, ctx: &Context<'_>, id: Uuid) -> Result<Order> {
+        let context = ctx.data::<GraphQLContext>()?;
+        
+        let current_user = context.current_user.as_ref()
+            .ok_or_else(|| GraphQLError::Unauthenticated.extend())?;
+
+        transition_order(context, id, current_user.id, OrderStatus::Cancelled)
+    }
+}
+
+/// Moves an order to `next`, rejecting transitions `OrderStatus` doesn't allow
+/// with an `INVALID_STATUS_TRANSITION` extension code
+fn transition_order(context: &GraphQLContext, order_id: Uuid, user_id: Uuid, next: OrderStatus) -> Result<Order> {
+    // Mock order lookup, assumed to belong to the caller
+    let mut order = mock_order(order_id, user_id);
+
+    if !order.status.can_transition_to(next) {
+        return Err(GraphQLError::InvalidStatusTransition { from: order.status, to: next }.extend());
+    }
+
+    order.status = next;
+    order.updated_at = Utc::now();
+
+    // Sending only fails when nobody is subscribed
+    let _ = context.order_events.send(order.clone());
+
+    Ok(order)
+}
+
+// Demo order data, shared with the REST order controllers
+pub fn mock_orders_for_user(user_id: Uuid) -> Vec<Order> {
+    let order = |status, quantity, age_days, shopify_order_id: &str| {
+        let line_items = vec![LineItem {
+            product_id: product_uuid_from_shopify_id(1),
+            quantity,
+            unit_price: 99.99,
+        }];
+        let created_at = Utc::now() - Duration::days(age_days);
+
+        Order {
+            id: Uuid::new_v4(),
+            user_id,
+            total_amount: Order::calculate_total(&line_items),
+            line_items,
             status,
-            shopify_order_id: Some("1003".to_string()),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+            shopify_order_id: Some(shopify_order_id.to_string()),
+            created_at,
+            updated_at: created_at,
+        }
+    };
 
-        Ok(order)
+    vec![
+        order(OrderStatus::Processing, 2, 1, "1001"),
+        order(OrderStatus::Delivered, 1, 30, "1000"),
+    ]
+}
+
+pub fn mock_order(id: Uuid, user_id: Uuid) -> Order {
+    Order {
+        id,
+        user_id,
+        line_items: vec![LineItem {
+            product_id: product_uuid_from_shopify_id(2),
+            quantity: 1,
+            unit_price: 99.99,
+        }],
+        total_amount: 99.99,
+        status: OrderStatus::Processing,
+        shopify_order_id: Some("1002".to_string()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
     }
 }
 
@@ -321,37 +772,25 @@ pub struct SubscriptionRoot;
 
 #[Subscription]
 impl SubscriptionRoot {
-    /// Subscribe to order status updates
+    /// Subscribe to the current user's order updates as they happen
     async fn order_updates(&self, ctx: &Context<'_>) -> Result<impl Stream<Item = Order>> {
         let context = ctx.data::<GraphQLContext>()?;
         
-        if context.current_user.is_none() {
-            return Err(async_graphql::Error::new("Authentication required"));
-        }
+        let user_id = context.current_user.as_ref()
+            .ok_or_else(|| GraphQLError::Unauthenticated.extend())?
+            .id;
 
-        // Mock subscription - in real implementation, this would connect to a message queue
-        let orders = vec![
-            Order {
-                id: Uuid::new_v4(),
-                user_id: Uuid::new_v4(),
-                total_amount: 99.99,
-                status: OrderStatus::Processing,
-                shopify_order_id: Some("sub_1".to_string()),
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-            },
-            Order {
-                id: Uuid::new_v4(),
-                user_id: Uuid::new_v4(),
-                total_amount: 199.98,
-                status: OrderStatus::Shipped,
-                shopify_order_id: Some("sub_2".to_string()),
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-            },
-        ];
+        let orders = BroadcastStream::new(context.order_events.subscribe())
+            .filter_map(move |event| match event {
+                Ok(order) if order.user_id == user_id => Some(order),
+                Ok(_) => None,
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    warn!("Order subscriber lagged, skipped {} updates", skipped);
+                    None
+                }
+            });
 
-        Ok(stream::iter(orders))
+        Ok(orders)
     }
 
     /// Subscribe to new products
@@ -364,6 +803,7 @@ impl SubscriptionRoot {
                 description: Some("A brand new product".to_string()),
                 price: 149.99,
                 shopify_id: Some("new_1".to_string()),
+                archived: false,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             },
@@ -376,19 +816,71 @@ impl SubscriptionRoot {
 // GraphQL Schema type
 pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
+pub const GRAPHQL_DISABLE_INTROSPECTION_ENV: &str = "GRAPHQL_DISABLE_INTROSPECTION";
+// Registered persisted queries kept, least recently used evicted first
+pub const PERSISTED_QUERY_CACHE_SIZE: usize = 1024;
+
+// Queries beyond the depth and complexity limits are rejected during
+// validation, before any resolver runs
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaConfig {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+    // Rejects `__schema` and `__type` queries, so production doesn't publish the schema
+    pub disable_introspection: bool,
+}
+
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 15,
+            max_complexity: 1000,
+            disable_introspection: false,
+        }
+    }
+}
+
+impl SchemaConfig {
+    /// Defaults, with introspection disabled when `GRAPHQL_DISABLE_INTROSPECTION` is "true" or "1"
+    pub fn from_env() -> Self {
+        Self::from_env_value(std::env::var(GRAPHQL_DISABLE_INTROSPECTION_ENV).ok().as_deref())
+    }
+
+    pub fn from_env_value(disable_introspection: Option<&str>) -> Self {
+        let disable_introspection = matches!(
+            disable_introspection.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+            Some("true" | "1")
+        );
+        Self { disable_introspection, ..Self::default() }
+    }
+}
+
 // Schema builder
 pub fn create_schema() -> AppSchema {
-    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
-        .finish()
+    create_schema_with_config(SchemaConfig::default())
+}
+
+pub fn create_schema_with_config(config: SchemaConfig) -> AppSchema {
+    // Automatic persisted queries: a client registers a query by sending it with
+    // its SHA-256 hash, then sends only the hash
+    let builder = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .limit_depth(config.max_depth)
+        .limit_complexity(config.max_complexity)
+        .extension(ApolloPersistedQueries::new(LruCacheStorage::new(PERSISTED_QUERY_CACHE_SIZE)));
+
+    if config.disable_introspection {
+        builder.disable_introspection().finish()
+    } else {
+        builder.finish()
+    }
 }
 
 // Helper function to create schema with context
 pub fn create_schema_with_context(
     _auth_service: Arc<AuthService>,
-    _shopify_client: Arc<MockShopifyClient>,
+    _shopify_client: Arc<dyn ShopifyBackend>,
 ) -> AppSchema {
-    let schema = create_schema();
-    schema
+    create_schema()
 }
 
 // GraphQL playground HTML
@@ -418,3 +910,612 @@ pub fn graphql_playground() -> &'static str {
     </html>
     "#
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn execute(query: &str) -> serde_json::Value {
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            Arc::new(MockShopifyClient::new()),
+        );
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    struct UnavailableShopifyClient;
+
+    #[async_trait::async_trait]
+    impl ShopifyBackend for UnavailableShopifyClient {
+        async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn get_products_paginated(
+            &self,
+            _limit: u32,
+            _page_info: Option<String>,
+        ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn get_product(&self, _product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn get_products_by_ids(&self, _product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn create_product(&self, _product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn update_product(&self, _product_id: i64, _product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn delete_product(&self, _product_id: i64) -> Result<(), ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+
+        async fn create_order(&self, _order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+            Err(ShopifyError::RateLimitExceeded)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_products_returns_partial_data_when_shopify_fails() {
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            Arc::new(UnavailableShopifyClient),
+        );
+        let response = create_schema()
+            .execute(async_graphql::Request::new("{ health products { totalCount edges { cursor } } }").data(context))
+            .await;
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].path, vec![async_graphql::PathSegment::Field("products".to_string())]);
+        let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&async_graphql::Value::from("SHOPIFY_ERROR")));
+
+        let data = response.data.into_json().unwrap();
+        assert!(data["health"].is_string());
+        assert_eq!(data["products"]["totalCount"], 0);
+        assert_eq!(data["products"]["edges"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_products_connection_shape() {
+        let data = execute("{ products { totalCount edges { cursor node { name } } pageInfo { hasNextPage endCursor } } }").await;
+        let connection = &data["products"];
+
+        assert_eq!(connection["totalCount"], 2);
+        assert_eq!(connection["edges"].as_array().unwrap().len(), 2);
+        assert_eq!(connection["pageInfo"]["hasNextPage"], false);
+        assert_eq!(connection["pageInfo"]["endCursor"], connection["edges"][1]["cursor"]);
+    }
+
+    #[tokio::test]
+    async fn test_products_paging_yields_disjoint_sets() {
+        let first_page = execute("{ products(first: 1) { edges { node { shopifyId } } pageInfo { hasNextPage endCursor } } }").await;
+        assert_eq!(first_page["products"]["pageInfo"]["hasNextPage"], true);
+        let cursor = first_page["products"]["pageInfo"]["endCursor"].as_str().unwrap();
+
+        let second_page = execute(&format!(
+            r#"{{ products(first: 1, after: "{}") {{ edges {{ node {{ shopifyId }} }} pageInfo {{ hasNextPage endCursor }} }} }}"#,
+            cursor
+        ))
+        .await;
+        assert_eq!(second_page["products"]["pageInfo"]["hasNextPage"], false);
+
+        let first_id = &first_page["products"]["edges"][0]["node"]["shopifyId"];
+        let second_id = &second_page["products"]["edges"][0]["node"]["shopifyId"];
+        assert_ne!(first_id, second_id);
+    }
+
+    #[tokio::test]
+    async fn test_products_after_last_cursor_is_empty() {
+        let data = execute(&format!(
+            r#"{{ products(after: "{}") {{ edges {{ cursor }} pageInfo {{ hasNextPage endCursor }} }} }}"#,
+            encode_cursor(10)
+        ))
+        .await;
+
+        assert!(data["products"]["edges"].as_array().unwrap().is_empty());
+        assert_eq!(data["products"]["pageInfo"]["hasNextPage"], false);
+        assert!(data["products"]["pageInfo"]["endCursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_product_lookups_are_batched_and_deduped() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            shopify_client.clone(),
+        );
+        let id = product_uuid_from_shopify_id(1);
+        let query = format!(
+            r#"{{ first: product(id: "{id}") {{ name }} second: product(id: "{id}") {{ name }} }}"#
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["first"]["name"], "Demo Product 1");
+        assert_eq!(data["second"]["name"], "Demo Product 1");
+        assert_eq!(shopify_client.api_call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_over_depth_query_is_rejected_before_resolution() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            shopify_client.clone(),
+        );
+        let schema = create_schema_with_config(SchemaConfig { max_depth: 3, ..SchemaConfig::default() });
+
+        let response = schema
+            .execute(async_graphql::Request::new("{ products { edges { node { name } } } }").data(context))
+            .await;
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, "Query is nested too deep.");
+        assert_eq!(shopify_client.api_call_count(), 0);
+    }
+
+    #[test]
+    fn test_websocket_init_payload_authenticates_the_connection() {
+        let auth_service = Arc::new(AuthService::new("test-secret".to_string()));
+        let user_id = Uuid::new_v4();
+        let claims = Claims::new(user_id, "ws@example.com".to_string(), "WS User".to_string(), 1);
+        let token = auth_service.generate_token(&claims).unwrap();
+        let context = GraphQLContext::new(auth_service, Arc::new(MockShopifyClient::new()));
+
+        let payload = serde_json::json!({ "Authorization": format!("Bearer {}", token) });
+        let authenticated = context.clone().with_init_payload(&payload).unwrap();
+        assert_eq!(authenticated.current_user.map(|user| user.id), Some(user_id));
+
+        let anonymous = context.clone().with_init_payload(&serde_json::Value::Null).unwrap();
+        assert!(anonymous.current_user.is_none());
+
+        assert!(context.with_init_payload(&serde_json::json!({ "authorization": "Bearer nope" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_products_complexity_scales_with_page_size() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            shopify_client.clone(),
+        );
+        let schema = create_schema();
+
+        let response = schema
+            .execute(async_graphql::Request::new("{ products(first: 1000) { edges { node { name } } } }").data(context))
+            .await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, "Query is too complex.");
+        assert_eq!(shopify_client.api_call_count(), 0);
+
+        execute("{ products(first: 5) { edges { node { name } } } }").await;
+    }
+
+    #[tokio::test]
+    async fn test_login_mutation_checks_registered_password() {
+        let auth_service = AuthService::with_bcrypt_cost("test-secret".to_string(), 4).unwrap();
+        let context = GraphQLContext::new(Arc::new(auth_service), Arc::new(MockShopifyClient::new()));
+        let schema = create_schema();
+        let login = |password: &str| {
+            format!(
+                r#"mutation {{ login(input: {{ email: "test@example.com", password: "{}" }}) {{ user {{ name }} }} }}"#,
+                password
+            )
+        };
+
+        let register = r#"mutation { register(input: { email: "test@example.com", name: "Test User", password: "TestPassword123!" }) { token } }"#;
+        let response = schema.execute(async_graphql::Request::new(register).data(context.clone())).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let response = schema
+            .execute(async_graphql::Request::new(login("TestPassword123!")).data(context.clone()))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["login"]["user"]["name"], "Test User");
+
+        let response = schema
+            .execute(async_graphql::Request::new(login("WrongPassword123!")).data(context))
+            .await;
+        assert_eq!(response.errors.len(), 1);
+        let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&async_graphql::Value::from("INVALID_CREDENTIALS")));
+    }
+
+    #[tokio::test]
+    async fn test_introspection_is_enabled_by_default() {
+        let response = create_schema()
+            .execute(async_graphql::Request::new("{ __schema { queryType { name } } }"))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["__schema"]["queryType"]["name"], "QueryRoot");
+    }
+
+    #[tokio::test]
+    async fn test_introspection_can_be_disabled() {
+        let config = SchemaConfig::from_env_value(Some("true"));
+        assert!(config.disable_introspection);
+        assert!(!SchemaConfig::from_env_value(None).disable_introspection);
+
+        let response = create_schema_with_config(config)
+            .execute(async_graphql::Request::new("{ __schema { queryType { name } } }"))
+            .await;
+
+        // async-graphql leaves `__schema` out of the data rather than reporting an error
+        assert!(response.data.into_json().unwrap()["__schema"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_default_limits_allow_regular_queries() {
+        execute("{ products { edges { node { name description price } } pageInfo { hasNextPage } } }").await;
+    }
+
+    fn authenticated_context(shopify_client: Arc<MockShopifyClient>) -> GraphQLContext {
+        context_with_role(shopify_client, Role::User)
+    }
+
+    fn context_with_role(shopify_client: Arc<MockShopifyClient>, role: Role) -> GraphQLContext {
+        GraphQLContext::new(Arc::new(AuthService::new("test-secret".to_string())), shopify_client)
+            .with_user(AuthenticatedUser {
+                id: Uuid::new_v4(),
+                email: "test@example.com".to_string(),
+                name: "Test User".to_string(),
+                role,
+            })
+    }
+
+    #[tokio::test]
+    async fn test_archived_products_are_hidden_unless_requested() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let mutation = format!(r#"mutation {{ archiveProduct(id: "{}") {{ archived }} }}"#, product_uuid_from_shopify_id(1));
+        let response = create_schema()
+            .execute(async_graphql::Request::new(mutation).data(authenticated_context(shopify_client.clone())))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["archiveProduct"]["archived"], true);
+
+        for (query, expected) in [
+            ("{ products { totalCount } }", 1),
+            ("{ products(includeArchived: true) { totalCount } }", 2),
+        ] {
+            let response = create_schema()
+                .execute(async_graphql::Request::new(query).data(authenticated_context(shopify_client.clone())))
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(response.data.into_json().unwrap()["products"]["totalCount"], expected, "{}", query);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_can_list_users() {
+        let context = context_with_role(Arc::new(MockShopifyClient::new()), Role::Admin);
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new("{ users { email } }").data(context))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["users"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_regular_user_is_denied_admin_fields() {
+        let queries = [
+            "{ users { email } }".to_string(),
+            format!(r#"mutation {{ updateOrderStatus(orderId: "{}", status: SHIPPED) {{ id }} }}"#, Uuid::new_v4()),
+        ];
+
+        for query in queries {
+            let context = authenticated_context(Arc::new(MockShopifyClient::new()));
+            let response = create_schema()
+                .execute(async_graphql::Request::new(query).data(context))
+                .await;
+
+            assert_eq!(response.errors.len(), 1);
+            assert_eq!(response.errors[0].message, "Insufficient permissions");
+            let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+            assert_eq!(code, Some(&async_graphql::Value::from("UNAUTHORIZED")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_illegal_order_status_transition_is_rejected() {
+        let context = context_with_role(Arc::new(MockShopifyClient::new()), Role::Admin);
+        let query = format!(r#"mutation {{ updateOrderStatus(orderId: "{}", status: PENDING) {{ status }} }}"#, Uuid::new_v4());
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, "Cannot change order status from Processing to Pending");
+        let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&async_graphql::Value::from("INVALID_STATUS_TRANSITION")));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_order_mutation() {
+        let context = authenticated_context(Arc::new(MockShopifyClient::new()));
+        let query = format!(r#"mutation {{ cancelOrder(id: "{}") {{ status }} }}"#, Uuid::new_v4());
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["cancelOrder"]["status"], "CANCELLED");
+    }
+
+    async fn my_order_statuses(arguments: &str) -> Vec<String> {
+        let context = authenticated_context(Arc::new(MockShopifyClient::new()));
+        let query = format!("{{ myOrders{} {{ status }} }}", arguments);
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        data["myOrders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| order["status"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_my_orders_status_filter() {
+        assert_eq!(my_order_statuses("").await.len(), 2);
+        assert_eq!(my_order_statuses("(status: DELIVERED)").await, vec!["DELIVERED"]);
+        assert_eq!(my_order_statuses("(first: 1)").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_my_orders_date_range() {
+        let week_ago = (Utc::now() - Duration::days(7)).to_rfc3339();
+        let recent = format!(r#"(createdAfter: "{}")"#, week_ago);
+        assert_eq!(my_order_statuses(&recent).await, vec!["PROCESSING"]);
+
+        let older = format!(r#"(createdBefore: "{}")"#, week_ago);
+        assert_eq!(my_order_statuses(&older).await, vec!["DELIVERED"]);
+    }
+
+    #[tokio::test]
+    async fn test_order_updates_subscription_receives_created_order() {
+        let context = authenticated_context(Arc::new(MockShopifyClient::new()));
+        let order_events = context.order_events.clone();
+        let schema = create_schema();
+
+        let subscription = async_graphql::Request::new("subscription { orderUpdates { id status } }").data(context.clone());
+        let subscriber = tokio::spawn({
+            let schema = schema.clone();
+            async move { schema.execute_stream(subscription).next().await }
+        });
+
+        // Wait for the resolver to subscribe before publishing
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while order_events.receiver_count() == 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // Another user's order must not reach this subscriber
+        let _ = order_events.send(Order {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            line_items: vec![],
+            total_amount: 10.0,
+            status: OrderStatus::Pending,
+            shopify_order_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        });
+
+        let response = schema
+            .execute(async_graphql::Request::new(format!(
+                r#"mutation {{ createOrder(lines: [{{ productId: "{}", quantity: 1 }}]) {{ id }} }}"#,
+                product_uuid_from_shopify_id(1)
+            )).data(context))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let created_id = response.data.into_json().unwrap()["createOrder"]["id"].clone();
+
+        let update = tokio::time::timeout(std::time::Duration::from_secs(5), subscriber)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(update.errors.is_empty(), "{:?}", update.errors);
+
+        let data = update.data.into_json().unwrap();
+        assert_eq!(data["orderUpdates"]["id"], created_id);
+        assert_eq!(data["orderUpdates"]["status"], "PENDING");
+    }
+
+    #[tokio::test]
+    async fn test_create_order_total_matches_line_items() {
+        let query = format!(
+            r#"mutation {{ createOrder(lines: [{{ productId: "{}", quantity: 2 }}, {{ productId: "{}", quantity: 3 }}]) {{ totalAmount lineItems {{ quantity unitPrice }} }} }}"#,
+            product_uuid_from_shopify_id(1),
+            product_uuid_from_shopify_id(2)
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(authenticated_context(Arc::new(MockShopifyClient::new()))))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let order = &response.data.into_json().unwrap()["createOrder"];
+        let line_items = order["lineItems"].as_array().unwrap();
+        assert_eq!(line_items.len(), 2);
+
+        let summed: f64 = line_items
+            .iter()
+            .map(|item| item["quantity"].as_f64().unwrap() * item["unitPrice"].as_f64().unwrap())
+            .sum();
+        assert!((order["totalAmount"].as_f64().unwrap() - summed).abs() < 1e-9);
+        assert!((summed - 5.0 * 99.99).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_create_order_syncs_to_shopify_when_enabled() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let query = format!(
+            r#"mutation {{ createOrder(lines: [{{ productId: "{}", quantity: 2 }}]) {{ shopifyOrderId }} }}"#,
+            product_uuid_from_shopify_id(1)
+        );
+
+        let context = authenticated_context(shopify_client.clone()).with_order_sync(true);
+        let response = create_schema().execute(async_graphql::Request::new(query.clone()).data(context)).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(shopify_client.get_orders().await.unwrap().len(), 1);
+
+        // A rejected order gives its stock back
+        shopify_client.set_reject_orders(true);
+        let context = authenticated_context(shopify_client.clone()).with_order_sync(true);
+        let response = create_schema().execute(async_graphql::Request::new(query).data(context)).await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(shopify_client.inventory_quantity(1), DEFAULT_MOCK_INVENTORY - 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_orders_cannot_oversell() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        shopify_client.set_inventory(1, 5);
+        let schema = create_schema();
+        let query = format!(
+            r#"mutation {{ createOrder(lines: [{{ productId: "{}", quantity: 3 }}]) {{ id }} }}"#,
+            product_uuid_from_shopify_id(1)
+        );
+        let place_order = || {
+            schema.execute(async_graphql::Request::new(query.clone()).data(authenticated_context(shopify_client.clone())))
+        };
+
+        let (first, second) = tokio::join!(place_order(), place_order());
+
+        let succeeded = [&first, &second].iter().filter(|response| response.errors.is_empty()).count();
+        assert_eq!(succeeded, 1);
+        let rejected = if first.errors.is_empty() { &second } else { &first };
+        assert_eq!(
+            rejected.errors[0].extensions.as_ref().and_then(|ext| ext.get("code")),
+            Some(&async_graphql::Value::from("INSUFFICIENT_INVENTORY"))
+        );
+        assert_eq!(shopify_client.inventory_quantity(1), 2);
+    }
+
+    #[tokio::test]
+    async fn test_create_order_rejects_unknown_product() {
+        let query = format!(
+            r#"mutation {{ createOrder(lines: [{{ productId: "{}", quantity: 1 }}]) {{ id }} }}"#,
+            Uuid::new_v4()
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(authenticated_context(Arc::new(MockShopifyClient::new()))))
+            .await;
+        assert!(response.errors[0].message.ends_with("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_product_mutation() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let query = format!(r#"mutation {{ deleteProduct(id: "{}") }}"#, product_uuid_from_shopify_id(1));
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query.clone()).data(authenticated_context(shopify_client.clone())))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["deleteProduct"], true);
+
+        // Deleting the same product again hits the not-found path
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(authenticated_context(shopify_client)))
+            .await;
+        assert_eq!(response.errors[0].message, "Shopify error: Product not found");
+        let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&async_graphql::Value::from("NOT_FOUND")));
+    }
+
+    #[tokio::test]
+    async fn test_delete_product_requires_authentication() {
+        let query = format!(r#"mutation {{ deleteProduct(id: "{}") }}"#, product_uuid_from_shopify_id(1));
+        let context = GraphQLContext::new(
+            Arc::new(AuthService::new("test-secret".to_string())),
+            Arc::new(MockShopifyClient::new()),
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(context))
+            .await;
+        assert_eq!(response.errors[0].message, "Authentication required");
+        let code = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("code"));
+        assert_eq!(code, Some(&async_graphql::Value::from("UNAUTHENTICATED")));
+
+        let response = with_request_id(async_graphql::BatchResponse::Single(response), &RequestId("req-1".to_string()));
+        let async_graphql::BatchResponse::Single(response) = response else {
+            panic!("expected a single response");
+        };
+        let request_id = response.errors[0].extensions.as_ref().and_then(|ext| ext.get("request_id"));
+        assert_eq!(request_id, Some(&async_graphql::Value::from("req-1")));
+    }
+
+    #[tokio::test]
+    async fn test_update_product_mutation_partial_update() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let query = format!(
+            r#"mutation {{ updateProduct(id: "{}", input: {{ description: "Updated" }}) {{ name description }} }}"#,
+            product_uuid_from_shopify_id(2)
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(authenticated_context(shopify_client)))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["updateProduct"]["name"], "Demo Product 2");
+        assert_eq!(data["updateProduct"]["description"], "Updated");
+    }
+
+    #[tokio::test]
+    async fn test_update_product_mutation_without_fields_skips_update() {
+        let shopify_client = Arc::new(MockShopifyClient::new());
+        let query = format!(
+            r#"mutation {{ updateProduct(id: "{}", input: {{}}) {{ name }} }}"#,
+            product_uuid_from_shopify_id(1)
+        );
+
+        let response = create_schema()
+            .execute(async_graphql::Request::new(query).data(authenticated_context(shopify_client.clone())))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap()["updateProduct"]["name"], "Demo Product 1");
+
+        // Only the lookup reached Shopify
+        assert_eq!(shopify_client.api_call_count(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+pub use metrics_exporter_prometheus::PrometheusHandle;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+// Latency histogram buckets, in seconds
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Handle for rendering the Prometheus text exposition. The recorder is global
+/// to the process, so it's installed on first use and shared afterwards.
+pub fn prometheus_handle() -> PrometheusHandle {
+    PROMETHEUS_HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()), LATENCY_BUCKETS)
+                .expect("latency buckets must not be empty")
+                .install_recorder()
+                .expect("failed to install Prometheus recorder")
+        })
+        .clone()
+}
+
+/// Counts every request and records its latency, labelled by method, route
+/// and status code.
+pub async fn track_http_metrics(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    // Label by route template rather than raw path to keep cardinality bounded
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(req).await;
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    metrics::histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels).record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// In-process request counters behind the JSON `/metrics` endpoint.
+#[derive(Debug, Default)]
+pub struct RequestStats {
+    total_requests: AtomicU64,
+    total_latency_micros: AtomicU64,
+    active_connections: AtomicUsize,
+}
+
+impl RequestStats {
+    /// Requests that have completed so far
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::Relaxed)
+    }
+
+    /// Mean latency over all completed requests, or 0 before the first one
+    pub fn average_response_time_ms(&self) -> f64 {
+        let total = self.total_requests();
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.total_latency_micros.load(Ordering::Relaxed) as f64 / total as f64 / 1000.0
+    }
+
+    /// Requests currently being handled, including the caller's own
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+}
+
+// Decrements the active count even if the request future is dropped mid-flight
+struct ActiveGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub async fn track_request_stats(
+    State(stats): State<Arc<RequestStats>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    stats.active_connections.fetch_add(1, Ordering::Relaxed);
+    let _active = ActiveGuard(&stats.active_connections);
+    let start = Instant::now();
+
+    let response = next.run(req).await;
+
+    stats.total_latency_micros.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    stats.total_requests.fetch_add(1, Ordering::Relaxed);
+
+    response
+}
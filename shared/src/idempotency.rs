@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+// A key's stored result, locked while a request with that key is running
+type Slot<T> = Arc<tokio::sync::Mutex<Option<(Instant, T)>>>;
+
+/// Remembers the result of a request by its `Idempotency-Key`, so a retried
+/// request can be answered with the original result instead of repeating it.
+/// Requests with the same key run one at a time: a retry that arrives while the
+/// first is still running waits for its result.
+#[derive(Debug)]
+pub struct IdempotencyCache<T> {
+    ttl: Duration,
+    // In a real implementation, this would use Redis or similar
+    entries: Mutex<HashMap<String, Slot<T>>>,
+}
+
+/// What `IdempotencyCache::reserve` found for a key
+#[derive(Debug)]
+pub enum Idempotent<T> {
+    /// The result of an earlier request with the same key
+    Cached(T),
+    /// No result yet; the key is held until the reservation is completed or dropped
+    Reserved(IdempotencyReservation<T>),
+}
+
+#[derive(Debug)]
+pub struct IdempotencyReservation<T> {
+    slot: OwnedMutexGuard<Option<(Instant, T)>>,
+}
+
+impl<T> IdempotencyReservation<T> {
+    /// Stores the result for later requests with the key. Dropping the
+    /// reservation instead, e.g. when the request failed, lets the next one run.
+    pub fn complete(mut self, value: T) {
+        *self.slot = Some((Instant::now(), value));
+    }
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The stored result for `key` unless it has expired, otherwise a
+    /// reservation for it. Waits while another request holds the key.
+    pub async fn reserve(&self, key: &str) -> Idempotent<T> {
+        let slot = {
+            let mut entries = self.entries.lock().unwrap();
+            // Keys someone is holding or waiting on are kept whatever their age
+            entries.retain(|_, slot| {
+                Arc::strong_count(slot) > 1
+                    || slot
+                        .try_lock()
+                        .is_ok_and(|stored| stored.as_ref().is_some_and(|(stored_at, _)| self.is_fresh(*stored_at)))
+            });
+            entries.entry(key.to_string()).or_default().clone()
+        };
+
+        let stored = slot.lock_owned().await;
+        if let Some((_, value)) = stored.as_ref().filter(|(stored_at, _)| self.is_fresh(*stored_at)) {
+            return Idempotent::Cached(value.clone());
+        }
+        Idempotent::Reserved(IdempotencyReservation { slot: stored })
+    }
+
+    fn is_fresh(&self, stored_at: Instant) -> bool {
+        stored_at.elapsed() < self.ttl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn run_once(cache: &IdempotencyCache<u32>, key: &str, value: u32) -> u32 {
+        match cache.reserve(key).await {
+            Idempotent::Cached(value) => value,
+            Idempotent::Reserved(reservation) => {
+                reservation.complete(value);
+                value
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_value_is_returned_until_expiry() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        assert_eq!(run_once(&cache, "key-1", 42).await, 42);
+
+        assert_eq!(run_once(&cache, "key-1", 7).await, 42);
+        assert_eq!(run_once(&cache, "key-2", 7).await, 7);
+
+        let expired = IdempotencyCache::new(Duration::ZERO);
+        run_once(&expired, "key-1", 42).await;
+        assert_eq!(run_once(&expired, "key-1", 7).await, 7);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_with_one_key_run_once() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let runs = AtomicUsize::new(0);
+        let (cache, runs) = (&cache, &runs);
+        let request = move || async move {
+            match cache.reserve("key-1").await {
+                Idempotent::Cached(value) => value,
+                Idempotent::Reserved(reservation) => {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    // Still running when the second request arrives
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    reservation.complete(42);
+                    42
+                }
+            }
+        };
+
+        assert_eq!(tokio::join!(request(), request()), (42, 42));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_reservation_lets_the_key_be_retried() {
+        let cache = IdempotencyCache::<u32>::new(Duration::from_secs(60));
+        assert!(matches!(cache.reserve("key-1").await, Idempotent::Reserved(_)));
+
+        assert_eq!(run_once(&cache, "key-1", 7).await, 7);
+    }
+}
@@ -1,11 +1,64 @@
+// `serde_json::json!` needs more than the default for the full Shopify order fixtures
+#![recursion_limit = "256"]
+
 pub mod models;
 pub mod shopify;
 pub mod auth;
 pub mod graphql;
 pub mod benchmarks;
+pub mod system_metrics;
+pub mod errors;
+pub mod request_id;
+pub mod shutdown;
+pub mod logging;
+pub mod http_metrics;
+pub mod api_version;
+pub mod idempotency;
+pub mod check_cache;
+pub mod bind_address;
+pub mod response_format;
+pub mod rate_limit;
+pub mod version;
+pub mod circuit_breaker;
+pub mod json_body;
+pub mod concurrency_limit;
+pub mod gid;
+pub mod pagination_headers;
+pub mod compression;
+pub mod server_config;
+pub mod tls;
+pub mod users;
+pub mod slow_request;
+pub mod api_doc;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 
 pub use models::*;
 pub use shopify::*;
 pub use auth::*;
 pub use graphql::*;
 pub use benchmarks::*;
+pub use system_metrics::*;
+pub use errors::*;
+pub use request_id::*;
+pub use shutdown::*;
+pub use logging::*;
+pub use http_metrics::*;
+pub use api_version::*;
+pub use idempotency::*;
+pub use check_cache::*;
+pub use bind_address::*;
+pub use response_format::*;
+pub use rate_limit::*;
+pub use version::*;
+pub use circuit_breaker::*;
+pub use json_body::*;
+pub use concurrency_limit::*;
+pub use gid::*;
+pub use pagination_headers::*;
+pub use compression::*;
+pub use server_config::*;
+pub use tls::*;
+pub use users::*;
+pub use slow_request::*;
+pub use api_doc::*;
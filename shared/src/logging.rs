@@ -0,0 +1,101 @@
+use axum::extract::MatchedPath;
+use axum::http::{Request, Response};
+use std::time::Duration;
+use tower_http::classify::{ServerErrorsAsFailures, SharedClassifier};
+use tower_http::trace::{DefaultOnRequest, MakeSpan, OnResponse, TraceLayer};
+use tracing::{field, info, info_span, Span};
+use tracing_subscriber::fmt;
+
+pub const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Compact, // Human-readable, for local development
+    Json,    // One JSON object per line, for log aggregators
+}
+
+impl LogFormat {
+    /// Reads `LOG_FORMAT`, falling back to compact when it's unset or unrecognised
+    pub fn from_env() -> Self {
+        Self::from_env_value(std::env::var(LOG_FORMAT_ENV).ok().as_deref())
+    }
+
+    pub fn from_env_value(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("json") => LogFormat::Json,
+            _ => LogFormat::Compact,
+        }
+    }
+}
+
+/// Installs the global tracing subscriber. JSON lines carry the timestamp,
+/// level, target and enclosing spans (e.g. the request ID) as fields.
+pub fn init_tracing(format: LogFormat) {
+    match format {
+        LogFormat::Compact => fmt().with_target(false).compact().init(),
+        LogFormat::Json => fmt()
+            .json()
+            .with_target(true)
+            .with_level(true)
+            .with_current_span(true)
+            .init(),
+    }
+}
+
+pub type HttpTraceLayer = TraceLayer<SharedClassifier<ServerErrorsAsFailures>, HttpRequestSpan, DefaultOnRequest, RecordHttpResponse>;
+
+/// `TraceLayer` that opens an `http_request` span per request carrying the
+/// method and route, and records the status and latency once it completes.
+pub fn http_trace_layer() -> HttpTraceLayer {
+    TraceLayer::new_for_http()
+        .make_span_with(HttpRequestSpan)
+        .on_response(RecordHttpResponse)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpRequestSpan;
+
+impl<B> MakeSpan<B> for HttpRequestSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        // Route template when one matched, so `/products/{id}` spans group together
+        let path = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| request.uri().path());
+
+        info_span!(
+            "http_request",
+            method = %request.method(),
+            path = %path,
+            status = field::Empty,
+            latency_ms = field::Empty,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordHttpResponse;
+
+impl<B> OnResponse<B> for RecordHttpResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        span.record("status", response.status().as_u16());
+        span.record("latency_ms", latency.as_millis() as u64);
+        info!("request completed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_from_env_value() {
+        assert_eq!(LogFormat::from_env_value(Some("json")), LogFormat::Json);
+        assert_eq!(LogFormat::from_env_value(Some(" JSON ")), LogFormat::Json);
+        assert_eq!(LogFormat::from_env_value(Some("compact")), LogFormat::Compact);
+        assert_eq!(LogFormat::from_env_value(Some("pretty")), LogFormat::Compact);
+        assert_eq!(LogFormat::from_env_value(None), LogFormat::Compact);
+    }
+}
@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use async_graphql::{SimpleObject, InputObject};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+use crate::auth::{EmailValidator, PasswordValidator};
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
@@ -12,47 +16,175 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject, ToSchema)]
 pub struct CreateUserInput {
     pub email: String,
     pub name: String,
     pub password: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+impl CreateUserInput {
+    /// Checks the email and password, returning every problem found rather than
+    /// stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if let Err(error) = EmailValidator::validate(&self.email) {
+            errors.push(error);
+        }
+
+        if let Err(password_errors) = PasswordValidator::validate(&self.password) {
+            errors.extend(password_errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject, ToSchema)]
 pub struct LoginInput {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
+    pub refresh_token: String,
     pub user: User,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject, ToSchema)]
+pub struct RefreshTokenInput {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
+pub struct RefreshTokenResponse {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct Product {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub price: f64,
     pub shopify_id: Option<String>,
+    // Archived products are kept, but left out of listings unless asked for
+    #[serde(default)]
+    pub archived: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSortField {
+    Name,
+    Price,
+    CreatedAt,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// Filter and sort query parameters for the REST product listing
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProductQuery {
+    pub name_contains: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub sort_by: Option<ProductSortField>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+impl ProductQuery {
+    pub fn apply(&self, products: Vec<Product>) -> Vec<Product> {
+        let name_contains = self.name_contains.as_ref().map(|name| name.to_lowercase());
+
+        let mut products: Vec<Product> = products
+            .into_iter()
+            .filter(|p| name_contains.as_ref().is_none_or(|name| p.name.to_lowercase().contains(name)))
+            .filter(|p| self.min_price.is_none_or(|min| p.price >= min))
+            .filter(|p| self.max_price.is_none_or(|max| p.price <= max))
+            .filter(|p| self.include_archived || !p.archived)
+            .collect();
+
+        if let Some(sort_by) = self.sort_by {
+            products.sort_by(|a, b| match sort_by {
+                ProductSortField::Name => a.name.cmp(&b.name),
+                ProductSortField::Price => a.price.total_cmp(&b.price),
+                ProductSortField::CreatedAt => a.created_at.cmp(&b.created_at),
+            });
+
+            if self.order.unwrap_or_default() == SortOrder::Desc {
+                products.reverse();
+            }
+        }
+
+        products
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject, ToSchema)]
 pub struct CreateProductInput {
     pub name: String,
     pub description: Option<String>,
     pub price: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+impl CreateProductInput {
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("Product name must not be empty".to_string());
+        }
+
+        if !self.price.is_finite() || self.price < 0.0 {
+            errors.push("Product price must be a non-negative number".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, InputObject, ToSchema)]
+pub struct UpdateProductInput {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<f64>,
+}
+
+impl UpdateProductInput {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.price.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct Order {
     pub id: Uuid,
     pub user_id: Uuid,
+    pub line_items: Vec<LineItem>,
     pub total_amount: f64,
     pub status: OrderStatus,
     pub shopify_order_id: Option<String>,
@@ -60,7 +192,84 @@ pub struct Order {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::Enum, Copy, PartialEq, Eq)]
+impl Order {
+    /// A new pending order whose total is derived from its line items
+    pub fn new(user_id: Uuid, line_items: Vec<LineItem>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            total_amount: Self::calculate_total(&line_items),
+            line_items,
+            status: OrderStatus::Pending,
+            shopify_order_id: Some(format!("order_{}", Uuid::new_v4())),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn calculate_total(line_items: &[LineItem]) -> f64 {
+        line_items.iter().map(LineItem::subtotal).sum()
+    }
+}
+
+// Narrows an order listing. The date range is half-open: `created_after` is
+// inclusive and `created_before` exclusive, so back-to-back ranges never
+// both contain the same order.
+#[derive(Debug, Clone, Default)]
+pub struct OrderFilter {
+    pub status: Option<OrderStatus>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl OrderFilter {
+    pub fn matches(&self, order: &Order) -> bool {
+        self.status.is_none_or(|status| order.status == status)
+            && self.created_after.is_none_or(|after| order.created_at >= after)
+            && self.created_before.is_none_or(|before| order.created_at < before)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, ToSchema)]
+pub struct LineItem {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_price: f64,
+}
+
+impl LineItem {
+    pub fn subtotal(&self) -> f64 {
+        self.unit_price * self.quantity as f64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject, ToSchema)]
+pub struct OrderLineInput {
+    pub product_id: Uuid,
+    pub quantity: i32,
+}
+
+impl OrderLineInput {
+    pub fn validate_all(lines: &[OrderLineInput]) -> Result<(), String> {
+        if lines.is_empty() {
+            return Err("An order needs at least one line item".to_string());
+        }
+
+        if lines.iter().any(|line| line.quantity < 1) {
+            return Err("Quantity must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+// REST body for `POST /api/orders`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateOrderInput {
+    pub lines: Vec<OrderLineInput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::Enum, Copy, PartialEq, Eq, ToSchema)]
 pub enum OrderStatus {
     Pending,
     Processing,
@@ -69,24 +278,97 @@ pub enum OrderStatus {
     Cancelled,
 }
 
+impl OrderStatus {
+    /// Whether an order may move from this status to `next`. Orders only move
+    /// forward, can be cancelled until they ship, and never leave Delivered or
+    /// Cancelled.
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Processing)
+                | (Pending, Cancelled)
+                | (Processing, Shipped)
+                | (Processing, Cancelled)
+                | (Shipped, Delivered)
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct BenchmarkResult {
     pub framework: String,
     pub test_name: String,
     pub requests_per_second: f64,
     pub average_response_time_ms: f64,
+    pub min_response_time_ms: f64,
+    pub max_response_time_ms: f64,
+    pub stddev_response_time_ms: f64,
     pub p95_response_time_ms: f64,
     pub p99_response_time_ms: f64,
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f64,
+    #[serde(default)]
+    pub response_time_histogram: Vec<HistogramBucket>,
+    // Keyed by endpoint path, so a slow endpoint isn't hidden in a mixed run
+    #[serde(default)]
+    #[graphql(skip)]
+    pub endpoint_stats: HashMap<String, EndpointStats>,
+    // Most common failure responses, to explain a low success rate
+    #[serde(default)]
+    #[graphql(skip)]
+    pub top_errors: Vec<ErrorSummary>,
+    // Percentage of requests that got a 2xx; results saved before this was
+    // recorded are assumed to have had no failures
+    #[serde(default = "full_success_rate")]
+    pub success_rate: f64,
+    // Failed requests by status, keyed like "HTTP_500" ("HTTP_0" when no
+    // response came back)
+    #[serde(default)]
+    #[graphql(skip)]
+    pub error_counts: HashMap<String, u32>,
+    // The run was stopped early, so this only covers part of the duration
+    #[serde(default)]
+    pub interrupted: bool,
     pub timestamp: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn full_success_rate() -> f64 {
+    100.0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorSummary {
+    pub status_code: u16,
+    // Truncated response body
+    pub body: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointStats {
+    pub count: u64,
+    pub average_response_time_ms: f64,
+    pub p95_response_time_ms: f64,
+    pub p99_response_time_ms: f64,
+    pub error_rate_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct HistogramBucket {
+    pub low_ms: f64,
+    pub high_ms: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -96,6 +378,7 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_code: None,
             timestamp: Utc::now(),
         }
     }
@@ -105,9 +388,120 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(error),
+            error_code: None,
             timestamp: Utc::now(),
         }
     }
+
+    pub fn error_with_code(error_code: &str, error: String) -> Self {
+        Self {
+            error_code: Some(error_code.to_string()),
+            ..Self::error(error)
+        }
+    }
+}
+
+pub const DEFAULT_PER_PAGE: u32 = 20;
+pub const MAX_PER_PAGE: u32 = 100;
+
+// `?page=&per_page=` query parameters for REST list endpoints
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaginationParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl PaginationParams {
+    /// 1-based page number, treating a missing or zero page as the first
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+    pub total_pages: u64,
+}
+
+impl<T> PaginatedResponse<T> {
+    pub fn new(items: Vec<T>, page: u32, per_page: u32, total: u64) -> Self {
+        Self {
+            items,
+            page,
+            per_page,
+            total,
+            total_pages: total.div_ceil(per_page.max(1) as u64),
+        }
+    }
+
+    /// Slices the requested page out of a fully loaded collection
+    pub fn from_all(all: Vec<T>, params: &PaginationParams) -> Self {
+        let (page, per_page) = (params.page(), params.per_page());
+        let total = all.len() as u64;
+        let offset = (page as usize - 1).saturating_mul(per_page as usize);
+        let items = all.into_iter().skip(offset).take(per_page as usize).collect();
+
+        Self::new(items, page, per_page, total)
+    }
+}
+
+// Outcome of one item in a bulk request; `index` is its position in the input
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<String>,
+}
+
+impl<T> BulkItemResult<T> {
+    pub fn success(index: usize, data: T) -> Self {
+        Self {
+            index,
+            success: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    pub fn failure(index: usize, error: String) -> Self {
+        Self {
+            index,
+            success: false,
+            data: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkResponse<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult<T>>,
+}
+
+impl<T> BulkResponse<T> {
+    /// Orders results by input position and tallies successes and failures
+    pub fn new(mut results: Vec<BulkItemResult<T>>) -> Self {
+        results.sort_by_key(|result| result.index);
+        let succeeded = results.iter().filter(|result| result.success).count();
+
+        Self {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +515,32 @@ pub struct HealthCheck {
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessCheck {
+    pub status: String,
+    pub framework: String,
+    // Dependency name -> "ok" or a description of the failure
+    pub checks: HashMap<String, String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ReadinessCheck {
+    pub fn new(framework: &str, checks: HashMap<String, String>) -> Self {
+        let ready = checks.values().all(|status| status == "ok");
+
+        Self {
+            status: if ready { "ready" } else { "not_ready" }.to_string(),
+            framework: framework.to_string(),
+            checks,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
     pub framework: String,
@@ -130,5 +550,128 @@ pub struct PerformanceMetrics {
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f64,
     pub active_connections: u32,
+    #[serde(default)]
+    pub total_requests: u64,
     pub timestamp: DateTime<Utc>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(name: &str, price: f64) -> Product {
+        Product {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            price,
+            shopify_id: None,
+            archived: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn names(products: &[Product]) -> Vec<&str> {
+        products.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_order_total_sums_line_items() {
+        let line_items = vec![
+            LineItem { product_id: Uuid::new_v4(), quantity: 2, unit_price: 10.5 },
+            LineItem { product_id: Uuid::new_v4(), quantity: 3, unit_price: 4.0 },
+        ];
+
+        assert_eq!(Order::calculate_total(&line_items), 33.0);
+        assert_eq!(Order::calculate_total(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_product_query_price_range() {
+        let products = vec![product("Cheap", 5.0), product("Mid", 50.0), product("Pricey", 500.0)];
+        let query = ProductQuery {
+            min_price: Some(10.0),
+            max_price: Some(100.0),
+            ..ProductQuery::default()
+        };
+
+        assert_eq!(names(&query.apply(products)), vec!["Mid"]);
+    }
+
+    #[test]
+    fn test_product_query_sorts_by_price_descending() {
+        let products = vec![product("Mid", 50.0), product("Cheap", 5.0), product("Pricey", 500.0)];
+        let query = ProductQuery {
+            sort_by: Some(ProductSortField::Price),
+            order: Some(SortOrder::Desc),
+            ..ProductQuery::default()
+        };
+
+        assert_eq!(names(&query.apply(products)), vec!["Pricey", "Mid", "Cheap"]);
+    }
+
+    #[test]
+    fn test_product_query_name_filter_is_case_insensitive() {
+        let products = vec![product("Blue Shirt", 20.0), product("Red Hat", 15.0)];
+        let query = ProductQuery {
+            name_contains: Some("shirt".to_string()),
+            ..ProductQuery::default()
+        };
+
+        assert_eq!(names(&query.apply(products)), vec!["Blue Shirt"]);
+    }
+
+    #[test]
+    fn test_product_query_hides_archived_unless_requested() {
+        let archived = Product { archived: true, ..product("Old Hat", 15.0) };
+        let products = vec![product("Blue Shirt", 20.0), archived];
+
+        assert_eq!(names(&ProductQuery::default().apply(products.clone())), vec!["Blue Shirt"]);
+
+        let query = ProductQuery {
+            include_archived: true,
+            ..ProductQuery::default()
+        };
+        assert_eq!(names(&query.apply(products)), vec!["Blue Shirt", "Old Hat"]);
+    }
+
+    #[test]
+    fn test_order_status_transitions() {
+        use OrderStatus::*;
+
+        let all = [Pending, Processing, Shipped, Delivered, Cancelled];
+        let allowed = [
+            (Pending, Processing),
+            (Pending, Cancelled),
+            (Processing, Shipped),
+            (Processing, Cancelled),
+            (Shipped, Delivered),
+        ];
+
+        for from in all {
+            for to in all {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_order_filter_date_range_is_half_open() {
+        let order = Order::new(Uuid::new_v4(), vec![]);
+        let filter = |created_after, created_before| OrderFilter {
+            created_after,
+            created_before,
+            ..OrderFilter::default()
+        };
+
+        assert!(filter(Some(order.created_at), None).matches(&order));
+        assert!(!filter(None, Some(order.created_at)).matches(&order));
+    }
+}
@@ -0,0 +1,90 @@
+use axum::http::{header::LINK, HeaderMap, HeaderName, HeaderValue, Uri};
+
+use crate::models::PaginatedResponse;
+
+pub const X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+pub const X_PAGE: HeaderName = HeaderName::from_static("x-page");
+
+/// `X-Total-Count`, `X-Page` and `Link` headers for `page`, following GitHub's
+/// convention, so clients can page without reading the body. Links are `uri`
+/// with only its `page` parameter changed.
+pub fn pagination_headers<T>(uri: &Uri, page: &PaginatedResponse<T>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(X_TOTAL_COUNT, HeaderValue::from(page.total));
+    headers.insert(X_PAGE, HeaderValue::from(page.page));
+
+    // An empty listing still has a first page
+    let current = page.page as u64;
+    let last = page.total_pages.max(1);
+    let mut links = Vec::new();
+    if current < last {
+        links.push(link(uri, current + 1, "next"));
+    }
+    if current > 1 {
+        links.push(link(uri, (current - 1).min(last), "prev"));
+    }
+    links.push(link(uri, 1, "first"));
+    links.push(link(uri, last, "last"));
+
+    // Query strings are already percent-encoded, so this only fails on a
+    // malformed request URI
+    if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+        headers.insert(LINK, value);
+    }
+    headers
+}
+
+fn link(uri: &Uri, page: u64, rel: &str) -> String {
+    let page_param = format!("page={}", page);
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && param.split('=').next() != Some("page"))
+        .collect();
+    params.push(&page_param);
+
+    format!("<{}?{}>; rel=\"{}\"", uri.path(), params.join("&"), rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(page: u32, total: u64) -> PaginatedResponse<()> {
+        PaginatedResponse::new(Vec::new(), page, 2, total)
+    }
+
+    #[test]
+    fn test_middle_page_links_both_ways() {
+        let uri: Uri = "/api/v1/products?per_page=2&page=2&sort_by=price".parse().unwrap();
+        let headers = pagination_headers(&uri, &listing(2, 5));
+
+        assert_eq!(headers[X_TOTAL_COUNT], "5");
+        assert_eq!(headers[X_PAGE], "2");
+        assert_eq!(
+            headers[LINK],
+            "</api/v1/products?per_page=2&sort_by=price&page=3>; rel=\"next\", \
+             </api/v1/products?per_page=2&sort_by=price&page=1>; rel=\"prev\", \
+             </api/v1/products?per_page=2&sort_by=price&page=1>; rel=\"first\", \
+             </api/v1/products?per_page=2&sort_by=price&page=3>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_last_page_has_no_next_link() {
+        let uri: Uri = "/api/v1/products?page=3".parse().unwrap();
+        let link = pagination_headers(&uri, &listing(3, 5))[LINK].to_str().unwrap().to_string();
+
+        assert!(!link.contains("rel=\"next\""));
+        assert!(link.contains("</api/v1/products?page=2>; rel=\"prev\""));
+    }
+
+    #[test]
+    fn test_first_page_without_query() {
+        let uri: Uri = "/api/v1/products".parse().unwrap();
+        let link = pagination_headers(&uri, &listing(1, 0))[LINK].to_str().unwrap().to_string();
+
+        assert_eq!(link, "</api/v1/products?page=1>; rel=\"first\", </api/v1/products?page=1>; rel=\"last\"");
+    }
+}
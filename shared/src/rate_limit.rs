@@ -0,0 +1,180 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::errors::AppError;
+
+// Past this many clients, buckets that have refilled are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64,
+    // Requests a client can make at once before being held to the steady rate
+    pub burst: u32,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket per client IP: each client can make `burst` requests at once,
+/// then one more every `1 / requests_per_second` seconds.
+#[derive(Debug)]
+pub struct IpRateLimiter {
+    config: RateLimitConfig,
+    // Proxies whose `X-Forwarded-For` is believed; see `client_ip`
+    trusted_proxies: Vec<IpAddr>,
+    // In a real implementation, this would use Redis or similar
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl IpRateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            trusted_proxies: Vec::new(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Identifies clients by `X-Forwarded-For` on requests from these proxies
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Takes a token for `ip`, or returns how long until one is available
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let burst = f64::from(self.config.burst);
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| self.refill(bucket, now) < burst);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: burst, refilled_at: now });
+        bucket.tokens = self.refill(bucket, now);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.config.requests_per_second))
+        }
+    }
+
+    fn refill(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.config.requests_per_second).min(f64::from(self.config.burst))
+    }
+}
+
+/// The peer address, unless the peer is one of `trusted_proxies`. Then it's the
+/// rightmost `X-Forwarded-For` address that isn't a trusted proxy: everything to
+/// its left was written by the client and can't be believed.
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = peer.map(|peer| peer.ip())?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let hops = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>();
+    for hop in hops.into_iter().rev() {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) if trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return Some(ip),
+            // A hop our proxies wouldn't have written; stop at the last known address
+            Err(_) => break,
+        }
+    }
+    Some(peer)
+}
+
+/// Answers `429 Too Many Requests` with a `Retry-After` header once a client
+/// runs out of tokens. Passes everything through when no limiter is configured.
+pub async fn rate_limit_middleware(
+    State(limiter): State<Option<Arc<IpRateLimiter>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = limiter else {
+        return next.run(req).await;
+    };
+
+    let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    let Some(ip) = client_ip(req.headers(), peer, &limiter.trusted_proxies) else {
+        return next.run(req).await;
+    };
+
+    match limiter.check(ip) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let mut response = AppError::TooManyRequests.into_response();
+            // Whole seconds, rounded up so a client that waits this long gets through
+            let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits_each_ip() {
+        let limiter = IpRateLimiter::new(RateLimitConfig { requests_per_second: 1.0, burst: 3 });
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        for _ in 0..3 {
+            assert!(limiter.check(client).is_ok());
+        }
+        let retry_after = limiter.check(client).unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+
+        assert!(limiter.check("203.0.113.8".parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_client_ip_ignores_forwarded_for_from_untrusted_peers() {
+        let peer = Some("198.51.100.4:5000".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("203.0.113.7"));
+
+        assert_eq!(client_ip(&headers, peer, &[]), Some("198.51.100.4".parse().unwrap()));
+        assert_eq!(client_ip(&headers, None, &[]), None);
+    }
+
+    #[test]
+    fn test_client_ip_takes_rightmost_untrusted_hop_from_trusted_proxy() {
+        let trusted: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+        let peer = Some("10.0.0.1:5000".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers, peer, &trusted), Some("10.0.0.1".parse().unwrap()));
+
+        // The client made up 192.0.2.1; the proxies appended 203.0.113.7 and 10.0.0.2
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("192.0.2.1, 203.0.113.7, 10.0.0.2"));
+        assert_eq!(client_ip(&headers, peer, &trusted), Some("203.0.113.7".parse().unwrap()));
+
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("203.0.113.7, garbage"));
+        assert_eq!(client_ip(&headers, peer, &trusted), Some("10.0.0.1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use std::convert::Infallible;
+use tracing::error;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+// Older name some clients still send
+const LEGACY_MSGPACK_CONTENT_TYPE: &str = "application/x-msgpack";
+
+/// Encoding for a response body, picked from the request's `Accept` header.
+/// JSON unless the client lists MessagePack.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let accepts_msgpack = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|media_type| media_type.split(';').next().unwrap_or_default().trim())
+            .any(|media_type| {
+                media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE)
+                    || media_type.eq_ignore_ascii_case(LEGACY_MSGPACK_CONTENT_TYPE)
+            });
+
+        if accepts_msgpack {
+            ResponseFormat::MessagePack
+        } else {
+            ResponseFormat::Json
+        }
+    }
+
+    pub fn respond<T>(self, body: T) -> Negotiated<T> {
+        Negotiated { format: self, body }
+    }
+}
+
+impl<S> FromRequestParts<S> for ResponseFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// Response body serialized in the format the client asked for
+#[derive(Debug)]
+pub struct Negotiated<T> {
+    pub format: ResponseFormat,
+    pub body: T,
+}
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let mut response = match self.format {
+            ResponseFormat::Json => Json(self.body).into_response(),
+            // Structs are encoded as maps, so the shape matches the JSON one
+            ResponseFormat::MessagePack => match rmp_serde::to_vec_named(&self.body) {
+                Ok(bytes) => ([(header::CONTENT_TYPE, HeaderValue::from_static(MSGPACK_CONTENT_TYPE))], bytes).into_response(),
+                Err(e) => {
+                    error!("Failed to encode MessagePack response: {}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            },
+        };
+
+        // The body depends on Accept, so caches must key on it
+        response.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_response_format_from_accept() {
+        assert_eq!(ResponseFormat::from_headers(&HeaderMap::new()), ResponseFormat::Json);
+        assert_eq!(ResponseFormat::from_headers(&accept("application/json")), ResponseFormat::Json);
+        assert_eq!(ResponseFormat::from_headers(&accept("application/msgpack")), ResponseFormat::MessagePack);
+        assert_eq!(
+            ResponseFormat::from_headers(&accept("text/html, application/x-msgpack;q=0.9")),
+            ResponseFormat::MessagePack
+        );
+    }
+}
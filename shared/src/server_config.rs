@@ -0,0 +1,494 @@
+use axum::http::HeaderValue;
+use figment::{
+    providers::{Env, Format, Serialized, Toml, Yaml},
+    Figment,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::auth::AuthConfig;
+use crate::bind_address::{parse_bind_address, DEFAULT_HOST};
+use crate::rate_limit::{IpRateLimiter, RateLimitConfig};
+use crate::shopify::{parse_sync_orders, select_shopify_backend, ShopifyBackend, ShopifyClient, ShopifyConfig};
+use crate::slow_request::DEFAULT_SLOW_REQUEST_THRESHOLD;
+use crate::tls::TlsPaths;
+
+pub const CONFIG_FILE_ENV: &str = "CONFIG_FILE";
+// APP_JWT__SECRET sets `jwt.secret`, APP_RATE_LIMIT__BURST `rate_limit.burst`
+pub const CONFIG_ENV_PREFIX: &str = "APP_";
+pub const MIN_JWT_SECRET_LEN: usize = 32;
+
+// Variables the servers read before there was a config file, still honoured
+// so existing scripts keep working
+const LEGACY_ENV_KEYS: [(&str, &str); 9] = [
+    ("host", "host"),
+    ("port", "port"),
+    ("rate_limit_rps", "rate_limit.requests_per_second"),
+    ("rate_limit_burst", "rate_limit.burst"),
+    ("shopify_backend", "shopify.backend"),
+    ("shopify_sync_orders", "shopify.sync_orders"),
+    ("tls_cert_path", "tls.cert_path"),
+    ("tls_key_path", "tls.key_path"),
+    ("slow_request_threshold_ms", "slow_request_threshold_ms"),
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("Config file {0} not found")]
+    MissingFile(String),
+    #[error("Config file {0} must end in .toml, .yaml or .yml")]
+    UnsupportedFormat(String),
+    #[error("Invalid configuration: {0}")]
+    Parse(String),
+    #[error("Invalid configuration for {key}: {reason}")]
+    Invalid { key: &'static str, reason: String },
+}
+
+fn invalid(key: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid { key, reason: reason.into() }
+}
+
+/// Settings both servers start from: defaults, then an optional TOML or YAML
+/// file named by `CONFIG_FILE`, then `APP_`-prefixed environment variables
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // Requests slower than this are logged as a warning
+    pub slow_request_threshold_ms: u64,
+    pub tls: TlsSettings,
+    pub cors: CorsSettings,
+    pub jwt: JwtSettings,
+    pub shopify: ShopifySettings,
+    pub rate_limit: RateLimitSettings,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlsSettings {
+    // Both set serves HTTPS, neither plain HTTP
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorsSettings {
+    // Origins allowed to make cross-origin requests; empty allows any
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtSettings {
+    pub secret: String,
+    pub token_expiry_hours: i64,
+    pub refresh_token_expiry_days: i64,
+    pub issuer: String,
+    pub audience: String,
+    pub leeway_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShopifySettings {
+    // "mock" for the in-memory backend or "live" for the real API
+    pub backend: String,
+    #[serde(deserialize_with = "deserialize_flag")]
+    pub sync_orders: bool,
+    pub shop_domain: String,
+    pub access_token: String,
+    pub webhook_secret: String,
+    pub api_version: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitSettings {
+    // Per-client requests per second; unset turns rate limiting off
+    pub requests_per_second: Option<f64>,
+    // Defaults to one second's worth
+    pub burst: Option<u32>,
+    // Proxies whose X-Forwarded-For identifies the client; otherwise it's ignored
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+// Accepts `true`, `1` and their string forms, as SHOPIFY_SYNC_ORDERS always has
+fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(i64),
+        Text(String),
+    }
+
+    Ok(match Flag::deserialize(deserializer)? {
+        Flag::Bool(flag) => flag,
+        Flag::Number(number) => number == 1,
+        Flag::Text(text) => parse_sync_orders(Some(&text)),
+    })
+}
+
+impl ServerConfig {
+    pub fn new(default_port: u16) -> Self {
+        let auth = AuthConfig::default();
+        let shopify = ShopifyConfig::default();
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            port: default_port,
+            slow_request_threshold_ms: DEFAULT_SLOW_REQUEST_THRESHOLD.as_millis() as u64,
+            tls: TlsSettings::default(),
+            cors: CorsSettings::default(),
+            jwt: JwtSettings {
+                secret: auth.jwt_secret,
+                token_expiry_hours: auth.token_expiry_hours,
+                refresh_token_expiry_days: auth.refresh_token_expiry_days,
+                issuer: auth.issuer,
+                audience: auth.audience,
+                leeway_seconds: auth.leeway_seconds,
+            },
+            shopify: ShopifySettings {
+                backend: "mock".to_string(),
+                sync_orders: false,
+                shop_domain: shopify.shop_domain,
+                access_token: shopify.access_token,
+                webhook_secret: shopify.webhook_secret,
+                api_version: shopify.api_version,
+            },
+            rate_limit: RateLimitSettings::default(),
+        }
+    }
+
+    /// Loads the config file named by `CONFIG_FILE`, if any, applies
+    /// environment overrides and validates the result
+    pub fn load(default_port: u16) -> Result<Self, ConfigError> {
+        let file = std::env::var(CONFIG_FILE_ENV).ok().filter(|path| !path.trim().is_empty());
+        Self::load_from(default_port, file.as_deref().map(Path::new))
+    }
+
+    pub fn load_from(default_port: u16, file: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut figment = Figment::from(Serialized::defaults(Self::new(default_port)));
+
+        if let Some(path) = file {
+            if !path.is_file() {
+                return Err(ConfigError::MissingFile(path.display().to_string()));
+            }
+            figment = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("toml") => figment.merge(Toml::file(path)),
+                Some("yaml" | "yml") => figment.merge(Yaml::file(path)),
+                _ => return Err(ConfigError::UnsupportedFormat(path.display().to_string())),
+            };
+        }
+
+        let config: Self = figment
+            .merge(Env::raw().filter_map(|key| {
+                LEGACY_ENV_KEYS
+                    .iter()
+                    .find(|(legacy, _)| key == *legacy)
+                    .map(|(_, nested)| (*nested).into())
+            }))
+            .merge(Env::prefixed(CONFIG_ENV_PREFIX).split("__"))
+            .extract()
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.bind_address()?;
+        self.tls_paths()?;
+
+        if self.slow_request_threshold_ms == 0 {
+            return Err(invalid("slow_request_threshold_ms", "must be positive"));
+        }
+
+        for origin in &self.cors.allowed_origins {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) || HeaderValue::from_str(origin).is_err() {
+                return Err(invalid("cors.allowed_origins", format!("{:?} is not an http(s) origin", origin)));
+            }
+        }
+
+        if self.jwt.secret.len() < MIN_JWT_SECRET_LEN {
+            return Err(invalid("jwt.secret", format!("must be at least {} characters", MIN_JWT_SECRET_LEN)));
+        }
+        if self.jwt.token_expiry_hours <= 0 {
+            return Err(invalid("jwt.token_expiry_hours", "must be positive"));
+        }
+        if self.jwt.refresh_token_expiry_days <= 0 {
+            return Err(invalid("jwt.refresh_token_expiry_days", "must be positive"));
+        }
+
+        if !matches!(self.shopify.backend.as_str(), "mock" | "live") {
+            return Err(invalid("shopify.backend", format!("expected \"mock\" or \"live\", got {:?}", self.shopify.backend)));
+        }
+        if self.shopify.backend == "live" && self.shopify.shop_domain.trim().is_empty() {
+            return Err(invalid("shopify.shop_domain", "is required for the live backend"));
+        }
+
+        if let Some(rps) = self.rate_limit.requests_per_second {
+            if !rps.is_finite() || rps <= 0.0 {
+                return Err(invalid("rate_limit.requests_per_second", "must be a positive number"));
+            }
+        }
+        if self.rate_limit.burst == Some(0) {
+            return Err(invalid("rate_limit.burst", "must be positive"));
+        }
+
+        Ok(())
+    }
+
+    pub fn bind_address(&self) -> Result<SocketAddr, ConfigError> {
+        parse_bind_address(Some(&self.host), Some(&self.port.to_string()), self.port).map_err(|e| {
+            let key = if self.port == 0 { "port" } else { "host" };
+            invalid(key, e.to_string())
+        })
+    }
+
+    /// Certificate and key to serve HTTPS with, or `None` for plain HTTP
+    pub fn tls_paths(&self) -> Result<Option<TlsPaths>, ConfigError> {
+        TlsPaths::from_values(self.tls.cert_path.as_deref(), self.tls.key_path.as_deref())
+            .map_err(|e| invalid("tls", e.to_string()))
+    }
+
+    /// Where the server can reach itself, e.g. `https://127.0.0.1:4000`. A
+    /// wildcard host, which also listens on loopback, becomes `localhost`.
+    pub fn self_url(&self) -> String {
+        let scheme = if matches!(self.tls_paths(), Ok(Some(_))) { "https" } else { "http" };
+        match self.bind_address() {
+            Ok(addr) if !addr.ip().is_unspecified() => format!("{}://{}", scheme, addr),
+            _ => format!("{}://localhost:{}", scheme, self.port),
+        }
+    }
+
+    pub fn slow_request_threshold(&self) -> Duration {
+        Duration::from_millis(self.slow_request_threshold_ms)
+    }
+
+    pub fn auth_config(&self) -> AuthConfig {
+        AuthConfig {
+            jwt_secret: self.jwt.secret.clone(),
+            token_expiry_hours: self.jwt.token_expiry_hours,
+            refresh_token_expiry_days: self.jwt.refresh_token_expiry_days,
+            issuer: self.jwt.issuer.clone(),
+            audience: self.jwt.audience.clone(),
+            leeway_seconds: self.jwt.leeway_seconds,
+            ..AuthConfig::default()
+        }
+    }
+
+    pub fn shopify_config(&self) -> ShopifyConfig {
+        ShopifyConfig {
+            shop_domain: self.shopify.shop_domain.clone(),
+            access_token: self.shopify.access_token.clone(),
+            webhook_secret: self.shopify.webhook_secret.clone(),
+            api_version: self.shopify.api_version.clone(),
+            ..ShopifyConfig::default()
+        }
+    }
+
+    /// The configured backend: `live`, or the in-memory mock
+    pub fn shopify_backend(&self, live: Arc<ShopifyClient>) -> Arc<dyn ShopifyBackend> {
+        select_shopify_backend(Some(&self.shopify.backend), live)
+    }
+
+    pub fn rate_limit_config(&self) -> Option<RateLimitConfig> {
+        self.rate_limit.requests_per_second.map(|requests_per_second| RateLimitConfig {
+            requests_per_second,
+            burst: self.rate_limit.burst.unwrap_or(requests_per_second.ceil() as u32),
+        })
+    }
+
+    /// Per-client rate limiter, or `None` when rate limiting is off
+    pub fn ip_rate_limiter(&self) -> Option<Arc<IpRateLimiter>> {
+        self.rate_limit_config().map(|config| {
+            Arc::new(IpRateLimiter::new(config).with_trusted_proxies(self.rate_limit.trusted_proxies.clone()))
+        })
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer {
+        if self.cors.allowed_origins.is_empty() {
+            return CorsLayer::permissive();
+        }
+        // Validated on load, so nothing is dropped here
+        let origins = self
+            .cors
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok());
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+#[cfg(test)]
+// `Jail::expect_with` closures return figment's large `Error`
+#[allow(clippy::result_large_err)]
+mod tests {
+    use super::*;
+    use figment::Jail;
+
+    fn load(jail: &Jail, file: Option<&str>) -> Result<ServerConfig, ConfigError> {
+        ServerConfig::load_from(3000, file.map(|file| jail.directory().join(file)).as_deref())
+    }
+
+    #[test]
+    fn test_defaults_without_file_or_env() {
+        Jail::expect_with(|jail| {
+            let config = load(jail, None).unwrap();
+            assert_eq!(config, ServerConfig::new(3000));
+            assert_eq!(config.bind_address().unwrap(), "0.0.0.0:3000".parse().unwrap());
+            assert!(config.rate_limit_config().is_none());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_env_overrides_file_values() {
+        Jail::expect_with(|jail| {
+            jail.create_file(
+                "server.toml",
+                r#"
+                port = 4000
+
+                [jwt]
+                secret = "a-file-secret-that-is-long-enough-to-use"
+                token_expiry_hours = 2
+
+                [rate_limit]
+                requests_per_second = 10.0
+                trusted_proxies = ["10.0.0.1"]
+                "#,
+            )?;
+            jail.set_env("APP_JWT__TOKEN_EXPIRY_HOURS", "6");
+            jail.set_env("APP_CORS__ALLOWED_ORIGINS", "[\"https://shop.example.com\"]");
+            jail.set_env("PORT", "4001");
+            jail.set_env("RATE_LIMIT_BURST", "25");
+            jail.set_env("SHOPIFY_SYNC_ORDERS", "1");
+            jail.set_env("SLOW_REQUEST_THRESHOLD_MS", "250");
+
+            let config = load(jail, Some("server.toml")).unwrap();
+            assert_eq!(config.port, 4001);
+            assert_eq!(config.jwt.secret, "a-file-secret-that-is-long-enough-to-use");
+            assert_eq!(config.jwt.token_expiry_hours, 6);
+            assert_eq!(config.cors.allowed_origins, vec!["https://shop.example.com"]);
+            assert!(config.shopify.sync_orders);
+            assert_eq!(config.slow_request_threshold(), Duration::from_millis(250));
+            assert_eq!(
+                config.rate_limit_config(),
+                Some(RateLimitConfig { requests_per_second: 10.0, burst: 25 })
+            );
+            assert_eq!(config.rate_limit.trusted_proxies, vec!["10.0.0.1".parse::<IpAddr>().unwrap()]);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_yaml_file() {
+        Jail::expect_with(|jail| {
+            jail.create_file("server.yaml", "host: 127.0.0.1\nshopify:\n  backend: live\n")?;
+
+            let config = load(jail, Some("server.yaml")).unwrap();
+            assert_eq!(config.bind_address().unwrap(), "127.0.0.1:3000".parse().unwrap());
+            assert_eq!(config.shopify.backend, "live");
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_rate_limit_from_env() {
+        Jail::expect_with(|jail| {
+            jail.set_env("RATE_LIMIT_BURST", "10");
+            assert_eq!(load(jail, None).unwrap().rate_limit_config(), None);
+            Ok(())
+        });
+
+        Jail::expect_with(|jail| {
+            jail.set_env("RATE_LIMIT_RPS", "2.5");
+            assert_eq!(
+                load(jail, None).unwrap().rate_limit_config(),
+                Some(RateLimitConfig { requests_per_second: 2.5, burst: 3 })
+            );
+
+            jail.set_env("RATE_LIMIT_RPS", "5");
+            jail.set_env("RATE_LIMIT_BURST", "20");
+            assert_eq!(
+                load(jail, None).unwrap().rate_limit_config(),
+                Some(RateLimitConfig { requests_per_second: 5.0, burst: 20 })
+            );
+
+            jail.set_env("RATE_LIMIT_RPS", "0");
+            assert!(matches!(
+                load(jail, None),
+                Err(ConfigError::Invalid { key: "rate_limit.requests_per_second", .. })
+            ));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_self_url_follows_host_port_and_tls() {
+        let mut config = ServerConfig::new(3000);
+        assert_eq!(config.self_url(), "http://localhost:3000");
+
+        config.host = "127.0.0.1".to_string();
+        config.port = 4000;
+        assert_eq!(config.self_url(), "http://127.0.0.1:4000");
+
+        config.tls = TlsSettings {
+            cert_path: Some("cert.pem".to_string()),
+            key_path: Some("key.pem".to_string()),
+        };
+        assert_eq!(config.self_url(), "https://127.0.0.1:4000");
+    }
+
+    #[test]
+    fn test_invalid_config_fails_with_readable_error() {
+        Jail::expect_with(|jail| {
+            jail.create_file("short-secret.toml", "[jwt]\nsecret = \"short\"\n")?;
+            assert_eq!(
+                load(jail, Some("short-secret.toml")).unwrap_err().to_string(),
+                "Invalid configuration for jwt.secret: must be at least 32 characters"
+            );
+
+            jail.create_file("server.json", "{}")?;
+            assert!(matches!(load(jail, Some("server.json")), Err(ConfigError::UnsupportedFormat(_))));
+            assert!(matches!(load(jail, Some("missing.toml")), Err(ConfigError::MissingFile(_))));
+
+            jail.set_env("APP_SHOPIFY__BACKEND", "staging");
+            assert!(matches!(
+                load(jail, None),
+                Err(ConfigError::Invalid { key: "shopify.backend", .. })
+            ));
+
+            jail.set_env("APP_SHOPIFY__BACKEND", "mock");
+            jail.set_env("SLOW_REQUEST_THRESHOLD_MS", "0");
+            assert!(matches!(
+                load(jail, None),
+                Err(ConfigError::Invalid { key: "slow_request_threshold_ms", .. })
+            ));
+
+            jail.set_env("SLOW_REQUEST_THRESHOLD_MS", "500");
+            jail.set_env("APP_PORT", "not-a-port");
+            let error = load(jail, None).unwrap_err();
+            assert!(matches!(error, ConfigError::Parse(_)));
+            assert!(error.to_string().contains("port"), "{}", error);
+
+            jail.set_env("APP_PORT", "3000");
+            jail.set_env("TLS_CERT_PATH", "cert.pem");
+            assert!(matches!(load(jail, None), Err(ConfigError::Invalid { key: "tls", .. })));
+
+            jail.set_env("TLS_KEY_PATH", "key.pem");
+            jail.set_env("APP_CORS__ALLOWED_ORIGINS", "[\"shop.example.com\"]");
+            assert!(matches!(
+                load(jail, None),
+                Err(ConfigError::Invalid { key: "cors.allowed_origins", .. })
+            ));
+            Ok(())
+        });
+    }
+}
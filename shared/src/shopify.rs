@@ -1,8 +1,23 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use axum::http::HeaderMap;
+use chrono::{DateTime, Duration, Utc};
 use anyhow::Result;
+use async_trait::async_trait;
 use thiserror::Error;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::gid::Gid;
+use crate::models::{LineItem, Order, OrderStatus, Product, UpdateProductInput};
+use axum::body::Bytes;
+use futures_util::{stream, Stream, TryStreamExt};
 
 #[derive(Debug, Error)]
 pub enum ShopifyError {
@@ -16,8 +31,17 @@ pub enum ShopifyError {
     OrderNotFound,
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
+    #[error("Insufficient inventory for product {product_id}: {requested} requested, {available} available")]
+    InsufficientInventory { product_id: i64, requested: i64, available: i64 },
     #[error("Invalid webhook signature")]
     InvalidWebhookSignature,
+    #[error("Invalid webhook payload: {0}")]
+    InvalidWebhookPayload(String),
+    // Triggered too long ago, or claiming to be triggered in the future
+    #[error("Webhook timestamp is outside the accepted window")]
+    StaleWebhook,
+    #[error("Invalid Shopify GID {gid:?}: {reason}")]
+    InvalidGid { gid: String, reason: String },
     #[error("Shopify API error: {0}")]
     ApiError(String),
 }
@@ -28,6 +52,20 @@ pub struct ShopifyConfig {
     pub access_token: String,
     pub webhook_secret: String,
     pub api_version: String,
+    // Webhooks triggered longer ago than this are rejected as possible replays
+    pub webhook_tolerance_seconds: i64,
+    // Retries for rate-limited (429) requests before giving up
+    pub max_retries: u32,
+    // First backoff delay when a 429 has no `Retry-After` header; doubles per retry
+    pub retry_base_delay_ms: u64,
+    // Per-request timeout, so a hung Shopify call can't block a handler forever
+    pub request_timeout_ms: u64,
+    // Idle connections kept open per host for reuse by later requests
+    pub pool_max_idle_per_host: usize,
+    // How long an idle pooled connection is kept before being closed
+    pub pool_idle_timeout_secs: u64,
+    // TCP keepalive interval for pooled connections
+    pub tcp_keepalive_secs: u64,
 }
 
 impl Default for ShopifyConfig {
@@ -37,6 +75,13 @@ impl Default for ShopifyConfig {
             access_token: "your-access-token".to_string(),
             webhook_secret: "your-webhook-secret".to_string(),
             api_version: "2023-10".to_string(),
+            webhook_tolerance_seconds: 300,
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            request_timeout_ms: 30_000,
+            pool_max_idle_per_host: 10,
+            pool_idle_timeout_secs: 90,
+            tcp_keepalive_secs: 60,
         }
     }
 }
@@ -61,6 +106,36 @@ pub struct ShopifyProduct {
     pub images: Vec<ShopifyImage>,
 }
 
+pub const ARCHIVED_PRODUCT_STATUS: &str = "archived";
+
+impl ShopifyProduct {
+    pub fn is_archived(&self) -> bool {
+        self.status == ARCHIVED_PRODUCT_STATUS
+    }
+
+    /// Applies only the fields that are set on `input`. The price is written to
+    /// every variant, so it can't be set on a product without any; nothing is
+    /// changed then.
+    pub fn apply_update(&mut self, input: &UpdateProductInput) -> Result<(), String> {
+        if input.price.is_some() && self.variants.is_empty() {
+            return Err("Product has no variants to set the price on".to_string());
+        }
+
+        if let Some(name) = &input.name {
+            self.title = name.clone();
+        }
+        if let Some(description) = &input.description {
+            self.body_html = Some(description.clone());
+        }
+        if let Some(price) = input.price {
+            for variant in &mut self.variants {
+                variant.price = format!("{:.2}", price);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShopifyVariant {
     pub id: Option<i64>,
@@ -91,6 +166,40 @@ pub struct ShopifyVariant {
     pub admin_graphql_api_id: Option<String>,
 }
 
+impl ShopifyVariant {
+    /// The single default variant of a product with no options, selling at `price`
+    pub fn priced(price: f64) -> Self {
+        Self {
+            id: None,
+            product_id: None,
+            title: "Default Title".to_string(),
+            price: format!("{:.2}", price),
+            sku: None,
+            position: 1,
+            inventory_policy: "deny".to_string(),
+            compare_at_price: None,
+            fulfillment_service: "manual".to_string(),
+            inventory_management: None,
+            option1: Some("Default Title".to_string()),
+            option2: None,
+            option3: None,
+            created_at: None,
+            updated_at: None,
+            taxable: true,
+            barcode: None,
+            grams: 0,
+            image_id: None,
+            weight: 0.0,
+            weight_unit: "kg".to_string(),
+            inventory_item_id: None,
+            inventory_quantity: 0,
+            old_inventory_quantity: 0,
+            requires_shipping: true,
+            admin_graphql_api_id: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShopifyOption {
     pub id: Option<i64>,
@@ -202,42 +311,489 @@ pub struct ShopifyOrder {
     pub shipping_lines: Vec<serde_json::Value>,
 }
 
+/// A webhook body, typed according to its `X-Shopify-Topic`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WebhookPayload {
+    Product(Box<ShopifyProduct>),
+    Order(Box<ShopifyOrder>),
+    // Topics we don't model are passed through as plain JSON
+    Other(serde_json::Value),
+}
+
+impl WebhookPayload {
+    /// Checks `body` against the shape Shopify sends for `topic`
+    pub fn parse(topic: &str, body: &str) -> Result<Self, ShopifyError> {
+        let invalid = |expected: &str, e: serde_json::Error| {
+            ShopifyError::InvalidWebhookPayload(format!("{} payload is not a valid {}: {}", topic, expected, e))
+        };
+
+        match topic {
+            "products/create" | "products/update" => serde_json::from_str(body)
+                .map(|product| Self::Product(Box::new(product)))
+                .map_err(|e| invalid("product", e)),
+            "orders/create" | "orders/updated" | "orders/paid" | "orders/cancelled" => serde_json::from_str(body)
+                .map(|order| Self::Order(Box::new(order)))
+                .map_err(|e| invalid("order", e)),
+            _ => serde_json::from_str(body)
+                .map(Self::Other)
+                .map_err(|e| ShopifyError::InvalidWebhookPayload(e.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShopifyWebhook {
     pub topic: String,
     pub shop_domain: String,
-    pub payload: serde_json::Value,
+    pub payload: WebhookPayload,
     pub created_at: DateTime<Utc>,
 }
 
+impl ShopifyWebhook {
+    /// Builds a webhook from the `X-Shopify-*` headers and the JSON body, which
+    /// must match the topic's payload shape. The HMAC signature must be checked
+    /// separately with `ShopifyClient::verify_webhook`.
+    pub fn parse(headers: &HeaderMap, body: &str) -> Result<Self, ShopifyError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| ShopifyError::InvalidWebhookPayload(format!("missing {} header", name)))
+        };
+
+        let topic = header("X-Shopify-Topic")?.to_string();
+        let shop_domain = header("X-Shopify-Shop-Domain")?.to_string();
+        let created_at = DateTime::parse_from_rfc3339(header("X-Shopify-Triggered-At")?)
+            .map_err(|_| ShopifyError::InvalidWebhookPayload("invalid X-Shopify-Triggered-At header".to_string()))?
+            .with_timezone(&Utc);
+        let payload = WebhookPayload::parse(&topic, body)?;
+
+        Ok(Self {
+            topic,
+            shop_domain,
+            payload,
+            created_at,
+        })
+    }
+
+    /// Rejects webhooks triggered more than `tolerance` ago, since a captured
+    /// request with a valid signature could otherwise be replayed forever.
+    /// Timestamps more than `tolerance` ahead are rejected too: the header
+    /// isn't signed, so a replay could otherwise post-date itself.
+    pub fn ensure_fresh(&self, tolerance: Duration) -> Result<(), ShopifyError> {
+        let age = Utc::now() - self.created_at;
+        if age > tolerance || -age > tolerance {
+            return Err(ShopifyError::StaleWebhook);
+        }
+        Ok(())
+    }
+}
+
+/// Receives webhooks once they have been verified and parsed. The default
+/// implementation only logs them; override `handle` to act on specific topics.
+pub trait WebhookHandler: Send + Sync {
+    fn handle(&self, webhook: &ShopifyWebhook) {
+        info!("Received Shopify webhook {} from {}", webhook.topic, webhook.shop_domain);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LoggingWebhookHandler;
+
+impl WebhookHandler for LoggingWebhookHandler {}
+
+pub const WEBHOOK_QUEUE_CAPACITY: usize = 1024;
+
+/// Sending half of the in-process webhook queue. Verified webhooks are queued
+/// here so Shopify gets its ack without waiting on `WebhookHandler::handle`.
+#[derive(Debug, Clone)]
+pub struct WebhookQueue {
+    sender: mpsc::Sender<ShopifyWebhook>,
+}
+
+impl WebhookQueue {
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<ShopifyWebhook>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self { sender }, receiver)
+    }
+
+    /// Queues a webhook without waiting. When the queue is full the webhook is
+    /// dropped and logged rather than holding up the request.
+    pub fn enqueue(&self, webhook: ShopifyWebhook) -> bool {
+        match self.sender.try_send(webhook) {
+            Ok(()) => true,
+            Err(TrySendError::Full(webhook)) => {
+                warn!("Webhook queue full, dropping {} webhook from {}", webhook.topic, webhook.shop_domain);
+                false
+            }
+            Err(TrySendError::Closed(webhook)) => {
+                warn!("Webhook queue closed, dropping {} webhook from {}", webhook.topic, webhook.shop_domain);
+                false
+            }
+        }
+    }
+}
+
+/// Dispatches queued webhooks to `handler` until every `WebhookQueue` is
+/// dropped or `cancel` fires. Once cancelled the queue is closed, so further
+/// `enqueue` calls fail, and the webhooks already in it are still handled.
+pub async fn process_webhooks(
+    mut receiver: mpsc::Receiver<ShopifyWebhook>,
+    handler: Arc<dyn WebhookHandler>,
+    cancel: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            webhook = receiver.recv() => match webhook {
+                Some(webhook) => handler.handle(&webhook),
+                None => return,
+            },
+            _ = cancel.cancelled() => break,
+        }
+    }
+
+    receiver.close();
+    while let Some(webhook) = receiver.recv().await {
+        handler.handle(&webhook);
+    }
+}
+
+/// The background task running `process_webhooks`. Spawned from `main` once
+/// the runtime is up, and shut down after the server has drained.
+pub struct WebhookWorker {
+    cancel: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl WebhookWorker {
+    pub fn spawn(receiver: mpsc::Receiver<ShopifyWebhook>, handler: Arc<dyn WebhookHandler>) -> Self {
+        let cancel = CancellationToken::new();
+        let task = tokio::spawn(process_webhooks(receiver, handler, cancel.clone()));
+        Self { cancel, task }
+    }
+
+    /// Closes the queue and waits for the webhooks already in it to be handled
+    pub async fn shutdown(self) {
+        self.cancel.cancel();
+        if let Err(e) = self.task.await {
+            warn!("Webhook worker failed: {}", e);
+        }
+    }
+}
+
+/// The Shopify operations the servers depend on, so handlers work the same
+/// against the real API and the in-memory mock.
+#[async_trait]
+pub trait ShopifyBackend: Send + Sync {
+    async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError>;
+
+    /// One page of products plus the cursor for the next page, if any
+    async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError>;
+
+    async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError>;
+
+    async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError>;
+
+    async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError>;
+
+    /// Creates products one at a time; backends with a batch call override this
+    async fn create_products(&self, products: Vec<ShopifyProduct>) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        let mut created = Vec::with_capacity(products.len());
+        for product in &products {
+            created.push(self.create_product(product).await?);
+        }
+        Ok(created)
+    }
+
+    async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError>;
+
+    async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError>;
+
+    /// Archives rather than deletes, keeping the product and its order history.
+    /// Shopify marks archived products by their status.
+    async fn archive_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        let mut product = self.get_product(product_id).await?;
+        product.status = ARCHIVED_PRODUCT_STATUS.to_string();
+        self.update_product(product_id, &product).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError>;
+
+    async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError>;
+
+    /// Takes `(product_id, quantity)` units out of stock for an order, all or
+    /// nothing. Backends that don't track inventory accept every reservation.
+    async fn reserve_inventory(&self, _items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        Ok(())
+    }
+
+    /// Puts back units taken by `reserve_inventory`, when the order they were
+    /// for couldn't be placed after all
+    async fn release_inventory(&self, _items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        Ok(())
+    }
+}
+
+/// Takes the order's stock and, when `sync` is set, also creates the order in
+/// Shopify and records its Shopify ID. If that fails the stock is put back, so
+/// orders that were never placed don't keep inventory reserved.
+pub async fn place_order(backend: &dyn ShopifyBackend, order: &mut Order, sync: bool) -> Result<(), ShopifyError> {
+    let reservations: Vec<(i64, i64)> = order
+        .line_items
+        .iter()
+        .filter_map(|item| shopify_id_from_product_uuid(item.product_id).map(|id| (id, i64::from(item.quantity))))
+        .collect();
+    backend.reserve_inventory(&reservations).await?;
+
+    if !sync {
+        return Ok(());
+    }
+    match backend.create_order(&order.to_shopify_order()).await {
+        Ok(shopify_order) => {
+            order.shopify_order_id = shopify_order.id.map(|id| id.to_string());
+            Ok(())
+        }
+        Err(e) => {
+            if let Err(release_error) = backend.release_inventory(&reservations).await {
+                warn!("Failed to release inventory for order {}: {}", order.id, release_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+pub const SHOPIFY_BACKEND_ENV: &str = "SHOPIFY_BACKEND";
+pub const SHOPIFY_SYNC_ORDERS_ENV: &str = "SHOPIFY_SYNC_ORDERS";
+
+/// Whether new orders are also created in Shopify; off unless
+/// `SHOPIFY_SYNC_ORDERS` is "true" or "1"
+pub fn sync_orders_from_env() -> bool {
+    parse_sync_orders(std::env::var(SHOPIFY_SYNC_ORDERS_ENV).ok().as_deref())
+}
+
+pub fn parse_sync_orders(value: Option<&str>) -> bool {
+    matches!(value.map(|v| v.trim().to_ascii_lowercase()).as_deref(), Some("true" | "1"))
+}
+
+/// Reads `SHOPIFY_BACKEND`: "live" uses `live`, anything else the in-memory mock
+pub fn shopify_backend_from_env(live: Arc<ShopifyClient>) -> Arc<dyn ShopifyBackend> {
+    select_shopify_backend(std::env::var(SHOPIFY_BACKEND_ENV).ok().as_deref(), live)
+}
+
+pub fn select_shopify_backend(name: Option<&str>, live: Arc<ShopifyClient>) -> Arc<dyn ShopifyBackend> {
+    match name.map(str::trim) {
+        Some("live") => live,
+        None | Some("") | Some("mock") => Arc::new(MockShopifyClient::new()),
+        Some(other) => {
+            warn!("Unknown {} {:?}, using the mock Shopify backend", SHOPIFY_BACKEND_ENV, other);
+            Arc::new(MockShopifyClient::new())
+        }
+    }
+}
+
+/// Calls made to one Shopify endpoint, e.g. `GET /products/:id.json`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ShopifyEndpointMetrics {
+    pub calls: u64,
+    // Calls that couldn't be sent or got a non-2xx response
+    pub errors: u64,
+    pub total_duration_ms: f64,
+}
+
+impl ShopifyEndpointMetrics {
+    pub fn average_duration_ms(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_duration_ms / self.calls as f64
+        }
+    }
+}
+
 pub struct ShopifyClient {
     client: Client,
     config: ShopifyConfig,
+    base_url: String,
+    // Keyed by "METHOD /path", with numeric IDs in the path replaced by ":id"
+    metrics: Mutex<HashMap<String, ShopifyEndpointMetrics>>,
 }
 
 impl ShopifyClient {
     pub fn new(config: ShopifyConfig) -> Self {
-        let client = Client::new();
-        Self { client, config }
+        let base_url = format!("https://{}/admin/api/{}", config.shop_domain, config.api_version);
+        Self::with_base_url(config, base_url)
+    }
+
+    /// Creates a client that talks to `base_url` instead of the shop's admin API,
+    /// e.g. a local mock server.
+    ///
+    /// Each client owns a connection pool, so build one and share it rather than
+    /// creating a client per request.
+    pub fn with_base_url(config: ShopifyConfig, base_url: String) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_millis(config.request_timeout_ms))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(std::time::Duration::from_secs(config.pool_idle_timeout_secs))
+            .tcp_keepalive(std::time::Duration::from_secs(config.tcp_keepalive_secs))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            config,
+            base_url,
+            metrics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ShopifyConfig {
+        &self.config
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Call counts and latency per endpoint since the client was created. Each
+    /// retry of a rate-limited request counts as a call.
+    pub fn metrics(&self) -> HashMap<String, ShopifyEndpointMetrics> {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    fn endpoint_name(&self, method: &reqwest::Method, url: &reqwest::Url) -> String {
+        let path = url
+            .as_str()
+            .strip_prefix(self.base_url.trim_end_matches('/'))
+            .map(|path| path.split(['?', '#']).next().unwrap_or_default())
+            .unwrap_or_else(|| url.path());
+
+        // One entry per endpoint rather than per product or order
+        let path: Vec<String> = path
+            .split('/')
+            .map(|segment| {
+                let (stem, extension) = segment.split_once('.').unwrap_or((segment, ""));
+                if stem.is_empty() || !stem.bytes().all(|b| b.is_ascii_digit()) {
+                    segment.to_string()
+                } else if extension.is_empty() {
+                    ":id".to_string()
+                } else {
+                    format!(":id.{}", extension)
+                }
+            })
+            .collect();
+
+        format!("{} {}", method, path.join("/"))
+    }
+
+    /// Sends a single attempt, logging it at debug level and recording it in `metrics()`
+    async fn send_timed(&self, request: RequestBuilder) -> Result<Response, ShopifyError> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let endpoint = self.endpoint_name(request.method(), request.url());
+
+        let started = std::time::Instant::now();
+        let result = client.execute(request).await;
+        let elapsed = started.elapsed();
+
+        let failed = match &result {
+            Ok(response) => {
+                debug!("Shopify {} -> {} in {:?}", endpoint, response.status(), elapsed);
+                !response.status().is_success()
+            }
+            Err(e) => {
+                debug!("Shopify {} failed after {:?}: {}", endpoint, elapsed, e);
+                true
+            }
+        };
+
+        let mut metrics = self.metrics.lock().unwrap();
+        let endpoint_metrics = metrics.entry(endpoint).or_default();
+        endpoint_metrics.calls += 1;
+        endpoint_metrics.errors += u64::from(failed);
+        endpoint_metrics.total_duration_ms += elapsed.as_secs_f64() * 1000.0;
+
+        result.map_err(ShopifyError::from)
     }
 
-    fn base_url(&self) -> String {
-        format!("https://{}/admin/api/{}", self.config.shop_domain, self.config.api_version)
+    /// Sends `request`, retrying 429 responses up to `max_retries` times. Waits as
+    /// long as `Retry-After` asks, or backs off exponentially when it's missing.
+    async fn send_with_retry(&self, request: RequestBuilder) -> Result<Response, ShopifyError> {
+        let mut attempt = 0;
+
+        loop {
+            let attempt_request = request
+                .try_clone()
+                .ok_or_else(|| ShopifyError::ApiError("Request can't be retried".to_string()))?;
+            let response = self.send_timed(attempt_request).await?;
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+            if attempt >= self.config.max_retries {
+                return Err(ShopifyError::RateLimitExceeded);
+            }
+
+            let delay = retry_delay(retry_after(&response), self.config.retry_base_delay_ms, attempt);
+            warn!("Shopify rate limit hit, retrying in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
+    /// Fetches every product by following the pagination cursor until the last page.
     pub async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        let mut result = Vec::new();
+        let mut page_info = None;
+
+        loop {
+            let (products, next_page_info) = self.get_products_paginated(250, page_info).await?;
+            result.extend(products);
+
+            match next_page_info {
+                Some(next) => page_info = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Fetches a single page of products. The second element is the `page_info`
+    /// cursor for the next page, or `None` on the last page.
+    pub async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
         let url = format!("{}/products.json", self.base_url());
+
+        let mut query = vec![("limit", limit.to_string())];
+        if let Some(page_info) = page_info {
+            query.push(("page_info", page_info));
+        }
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .header("X-Shopify-Access-Token", &self.config.access_token)
-            .send()
-            .await?;
+            .query(&query)
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
 
         if !response.status().is_success() {
             return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
         }
 
+        let next_page_info = response
+            .headers()
+            .get("Link")
+            .and_then(|link| link.to_str().ok())
+            .and_then(parse_next_page_info);
+
         let json: serde_json::Value = response.json().await?;
         let products = json["products"].as_array()
             .ok_or_else(|| ShopifyError::ApiError("Invalid response format".to_string()))?;
@@ -249,17 +805,16 @@ impl ShopifyClient {
             }
         }
 
-        Ok(result)
+        Ok((result, next_page_info))
     }
 
     pub async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
         let url = format!("{}/products/{}.json", self.base_url(), product_id);
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .header("X-Shopify-Access-Token", &self.config.access_token)
-            .send()
-            .await?;
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
 
         if response.status() == 404 {
             return Err(ShopifyError::ProductNotFound);
@@ -276,6 +831,38 @@ impl ShopifyClient {
         Ok(product)
     }
 
+    pub async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        let url = format!("{}/products.json", self.base_url());
+        let ids = product_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let request = self.client
+            .get(&url)
+            .query(&[("ids", ids)])
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
+
+        if !response.status().is_success() {
+            return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
+        }
+
+        let json: serde_json::Value = response.json().await?;
+        let products = json["products"].as_array()
+            .ok_or_else(|| ShopifyError::ApiError("Invalid response format".to_string()))?;
+
+        let mut result = Vec::new();
+        for product_json in products {
+            if let Ok(product) = serde_json::from_value::<ShopifyProduct>(product_json.clone()) {
+                result.push(product);
+            }
+        }
+
+        Ok(result)
+    }
+
     pub async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
         let url = format!("{}/products.json", self.base_url());
         
@@ -283,13 +870,41 @@ impl ShopifyClient {
             "product": product
         });
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .header("X-Shopify-Access-Token", &self.config.access_token)
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+            .json(&payload);
+        let response = self.send_with_retry(request).await?;
+
+        if !response.status().is_success() {
+            return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
+        }
+
+        let json: serde_json::Value = response.json().await?;
+        let product = serde_json::from_value(json["product"].clone())
+            .map_err(|e| ShopifyError::ApiError(e.to_string()))?;
+
+        Ok(product)
+    }
+
+    pub async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        let url = format!("{}/products/{}.json", self.base_url(), product_id);
+
+        let payload = serde_json::json!({
+            "product": product
+        });
+
+        let request = self.client
+            .put(&url)
+            .header("X-Shopify-Access-Token", &self.config.access_token)
+            .header("Content-Type", "application/json")
+            .json(&payload);
+        let response = self.send_with_retry(request).await?;
+
+        if response.status() == 404 {
+            return Err(ShopifyError::ProductNotFound);
+        }
 
         if !response.status().is_success() {
             return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
@@ -302,14 +917,32 @@ impl ShopifyClient {
         Ok(product)
     }
 
+    pub async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError> {
+        let url = format!("{}/products/{}.json", self.base_url(), product_id);
+
+        let request = self.client
+            .delete(&url)
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
+
+        if response.status() == 404 {
+            return Err(ShopifyError::ProductNotFound);
+        }
+
+        if !response.status().is_success() {
+            return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
+        }
+
+        Ok(())
+    }
+
     pub async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
         let url = format!("{}/orders.json", self.base_url());
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .header("X-Shopify-Access-Token", &self.config.access_token)
-            .send()
-            .await?;
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
 
         if !response.status().is_success() {
             return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
@@ -329,14 +962,38 @@ impl ShopifyClient {
         Ok(result)
     }
 
+    pub async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+        let url = format!("{}/orders.json", self.base_url());
+
+        let payload = serde_json::json!({
+            "order": order
+        });
+
+        let request = self.client
+            .post(&url)
+            .header("X-Shopify-Access-Token", &self.config.access_token)
+            .header("Content-Type", "application/json")
+            .json(&payload);
+        let response = self.send_with_retry(request).await?;
+
+        if !response.status().is_success() {
+            return Err(ShopifyError::ApiError(format!("HTTP {}", response.status())));
+        }
+
+        let json: serde_json::Value = response.json().await?;
+        let order = serde_json::from_value(json["order"].clone())
+            .map_err(|e| ShopifyError::ApiError(e.to_string()))?;
+
+        Ok(order)
+    }
+
     pub async fn get_order(&self, order_id: i64) -> Result<ShopifyOrder, ShopifyError> {
         let url = format!("{}/orders/{}.json", self.base_url(), order_id);
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .header("X-Shopify-Access-Token", &self.config.access_token)
-            .send()
-            .await?;
+            .header("X-Shopify-Access-Token", &self.config.access_token);
+        let response = self.send_with_retry(request).await?;
 
         if response.status() == 404 {
             return Err(ShopifyError::OrderNotFound);
@@ -365,42 +1022,425 @@ impl ShopifyClient {
         
         mac.update(payload.as_bytes());
         
-        let expected = mac.finalize().into_bytes();
-        let expected_b64 = base64::engine::general_purpose::STANDARD.encode(expected);
+        let provided = base64::engine::general_purpose::STANDARD
+            .decode(signature)
+            .map_err(|_| ShopifyError::InvalidWebhookSignature)?;
         
-        Ok(signature == expected_b64)
+        // verify_slice compares in constant time
+        Ok(mac.verify_slice(&provided).is_ok())
     }
 }
 
-// Utility functions for Shopify integration
-pub fn extract_shopify_id_from_gid(gid: &str) -> Option<i64> {
-    gid.split('/').last()?.parse().ok()
-}
+#[async_trait]
+impl ShopifyBackend for ShopifyClient {
+    async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        ShopifyClient::get_products(self).await
+    }
 
-pub fn create_shopify_gid(resource_type: &str, id: i64) -> String {
-    format!("gid://shopify/{}/{}", resource_type, id)
-}
+    async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
+        ShopifyClient::get_products_paginated(self, limit, page_info).await
+    }
 
-// Mock Shopify client for testing and demo purposes
-pub struct MockShopifyClient {
-    products: Vec<ShopifyProduct>,
-    orders: Vec<ShopifyOrder>,
-}
+    async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        ShopifyClient::get_product(self, product_id).await
+    }
 
-impl MockShopifyClient {
-    pub fn new() -> Self {
-        Self {
-            products: Self::create_mock_products(),
-            orders: Self::create_mock_orders(),
-        }
+    async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        ShopifyClient::get_products_by_ids(self, product_ids).await
     }
 
-    fn create_mock_products() -> Vec<ShopifyProduct> {
-        vec![
-            ShopifyProduct {
-                id: Some(1),
-                title: "Demo Product 1".to_string(),
-                body_html: Some("<p>This is a demo product</p>".to_string()),
+    async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        ShopifyClient::create_product(self, product).await
+    }
+
+    async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        ShopifyClient::update_product(self, product_id, product).await
+    }
+
+    async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError> {
+        ShopifyClient::delete_product(self, product_id).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
+        ShopifyClient::get_orders(self).await
+    }
+
+    async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+        ShopifyClient::create_order(self, order).await
+    }
+}
+
+// Utility functions for Shopify integration
+
+// Longest wait before retrying a rate-limited request, whatever Shopify asks for
+const MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Wait before retry number `attempt`, counting from 0: `retry_after` if Shopify
+/// sent one, otherwise `base_delay_ms` doubled per attempt. Capped at
+/// `MAX_RETRY_DELAY` either way.
+fn retry_delay(retry_after: Option<std::time::Duration>, base_delay_ms: u64, attempt: u32) -> std::time::Duration {
+    let delay = retry_after.unwrap_or_else(|| {
+        let factor = 2u64.checked_pow(attempt).unwrap_or(u64::MAX);
+        std::time::Duration::from_millis(base_delay_ms.saturating_mul(factor))
+    });
+    delay.min(MAX_RETRY_DELAY)
+}
+
+/// Reads the delay from a `Retry-After` header. Shopify sends it as a number of
+/// seconds, possibly fractional (e.g. `2.0`).
+fn retry_after(response: &Response) -> Option<std::time::Duration> {
+    let seconds: f64 = response
+        .headers()
+        .get("Retry-After")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // Clamped first, since a huge value would overflow `Duration`
+    (seconds.is_finite() && seconds >= 0.0)
+        .then(|| std::time::Duration::from_secs_f64(seconds.min(MAX_RETRY_DELAY.as_secs_f64())))
+}
+
+/// Extracts the `page_info` cursor from the `rel="next"` entry of a Shopify `Link` header, e.g.
+/// `<https://shop.myshopify.com/admin/api/2023-10/products.json?limit=50&page_info=abc>; rel="next"`.
+pub fn parse_next_page_info(link_header: &str) -> Option<String> {
+    link_header
+        .split(',')
+        .find(|part| part.contains(r#"rel="next""#))
+        .and_then(|part| {
+            let url = part.split(';').next()?.trim().trim_start_matches('<').trim_end_matches('>');
+            reqwest::Url::parse(url).ok()
+        })
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(key, _)| key == "page_info")
+                .map(|(_, value)| value.into_owned())
+        })
+}
+
+/// Derives a stable local product ID from a Shopify product ID, so the same
+/// Shopify product always maps to the same `Uuid`.
+pub fn product_uuid_from_shopify_id(shopify_id: i64) -> Uuid {
+    Uuid::from_u64_pair(0, shopify_id as u64)
+}
+
+/// Inverse of `product_uuid_from_shopify_id`. Returns `None` for IDs that
+/// weren't derived from a Shopify product.
+pub fn shopify_id_from_product_uuid(id: Uuid) -> Option<i64> {
+    let (high, low) = id.as_u64_pair();
+    (high == 0).then_some(low as i64)
+}
+
+/// Price of the product's first variant. Products without variants, or whose
+/// price Shopify sent as something other than a number, are priced at 0.0.
+pub fn product_price(sp: &ShopifyProduct) -> f64 {
+    let Some(variant) = sp.variants.first() else {
+        return 0.0;
+    };
+
+    match variant.price.trim().parse::<f64>() {
+        Ok(price) if price.is_finite() => price,
+        _ => {
+            warn!("Product {:?} has an unparseable price {:?}", sp.id, variant.price);
+            0.0
+        }
+    }
+}
+
+pub fn product_from_shopify(sp: ShopifyProduct) -> Product {
+    Product {
+        id: sp.id.map(product_uuid_from_shopify_id).unwrap_or_else(Uuid::new_v4),
+        price: product_price(&sp),
+        archived: sp.is_archived(),
+        name: sp.title,
+        description: sp.body_html,
+        shopify_id: sp.id.map(|id| id.to_string()),
+        created_at: sp.created_at.unwrap_or_else(Utc::now),
+        updated_at: sp.updated_at.unwrap_or_else(Utc::now),
+    }
+}
+
+// Orders are created in this currency, the shop's default
+pub const DEFAULT_ORDER_CURRENCY: &str = "USD";
+const ORDER_ID_NOTE: &str = "order_id";
+const USER_ID_NOTE: &str = "user_id";
+
+// The subset of a Shopify line item an `Order` round-trips through
+#[derive(Debug, Serialize, Deserialize)]
+struct ShopifyLineItem {
+    product_id: Option<i64>,
+    quantity: i32,
+    price: String,
+}
+
+fn format_amount(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+fn parse_amount(amount: &str) -> Option<f64> {
+    amount.trim().parse::<f64>().ok().filter(|amount| amount.is_finite())
+}
+
+impl Order {
+    /// The order as Shopify represents it, with amounts as two-decimal strings
+    /// in `DEFAULT_ORDER_CURRENCY`. The local order and user IDs travel in
+    /// `note_attributes` so `from_shopify_order` can restore them; everything
+    /// Shopify fills in itself (numbers, customer, taxes) is left empty.
+    pub fn to_shopify_order(&self) -> ShopifyOrder {
+        let total = format_amount(self.total_amount);
+        let zero = format_amount(0.0);
+        let financial_status = match self.status {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Cancelled => "voided",
+            OrderStatus::Processing | OrderStatus::Shipped | OrderStatus::Delivered => "paid",
+        };
+        let fulfillment_status = matches!(self.status, OrderStatus::Shipped | OrderStatus::Delivered)
+            .then(|| "fulfilled".to_string());
+        let total_outstanding = if self.status == OrderStatus::Pending { total.clone() } else { zero.clone() };
+
+        let line_items = self
+            .line_items
+            .iter()
+            .map(|item| {
+                serde_json::to_value(ShopifyLineItem {
+                    product_id: shopify_id_from_product_uuid(item.product_id),
+                    quantity: item.quantity,
+                    price: format_amount(item.unit_price),
+                })
+                .unwrap_or_default()
+            })
+            .collect();
+        let note_attributes = vec![
+            serde_json::json!({ "name": ORDER_ID_NOTE, "value": self.id }),
+            serde_json::json!({ "name": USER_ID_NOTE, "value": self.user_id }),
+        ];
+
+        ShopifyOrder {
+            id: self.shopify_order_id.as_deref().and_then(|id| id.parse().ok()),
+            admin_graphql_api_id: None,
+            app_id: None,
+            browser_ip: None,
+            buyer_accepts_marketing: false,
+            cancel_reason: (self.status == OrderStatus::Cancelled).then(|| "customer".to_string()),
+            cancelled_at: (self.status == OrderStatus::Cancelled).then_some(self.updated_at),
+            cart_token: None,
+            checkout_id: None,
+            checkout_token: None,
+            closed_at: (self.status == OrderStatus::Delivered).then_some(self.updated_at),
+            confirmed: true,
+            contact_email: None,
+            created_at: Some(self.created_at),
+            currency: DEFAULT_ORDER_CURRENCY.to_string(),
+            current_subtotal_price: total.clone(),
+            current_subtotal_price_set: None,
+            current_total_discounts: zero.clone(),
+            current_total_discounts_set: None,
+            current_total_duties_set: None,
+            current_total_price: total.clone(),
+            current_total_price_set: None,
+            current_total_tax: zero.clone(),
+            current_total_tax_set: None,
+            customer_locale: None,
+            device_id: None,
+            discount_codes: vec![],
+            email: String::new(),
+            estimated_taxes: false,
+            financial_status: financial_status.to_string(),
+            fulfillment_status,
+            gateway: "manual".to_string(),
+            landing_site: None,
+            landing_site_ref: None,
+            location_id: None,
+            name: String::new(),
+            note: None,
+            note_attributes,
+            number: 0,
+            order_number: 0,
+            order_status_url: String::new(),
+            original_total_duties_set: None,
+            payment_gateway_names: vec!["manual".to_string()],
+            phone: None,
+            presentment_currency: DEFAULT_ORDER_CURRENCY.to_string(),
+            processed_at: Some(self.created_at),
+            processing_method: "manual".to_string(),
+            reference: None,
+            referring_site: None,
+            source_identifier: None,
+            source_name: "web".to_string(),
+            source_url: None,
+            subtotal_price: total.clone(),
+            subtotal_price_set: None,
+            tags: String::new(),
+            tax_lines: vec![],
+            taxes_included: false,
+            test: false,
+            token: self.id.simple().to_string(),
+            total_discounts: zero.clone(),
+            total_discounts_set: None,
+            total_line_items_price: total.clone(),
+            total_line_items_price_set: None,
+            total_outstanding,
+            total_price: total.clone(),
+            total_price_set: None,
+            total_price_usd: total,
+            total_shipping_price_set: None,
+            total_tax: zero.clone(),
+            total_tax_set: None,
+            total_tip_received: zero,
+            total_weight: 0,
+            updated_at: Some(self.updated_at),
+            user_id: None,
+            billing_address: None,
+            customer: None,
+            discount_applications: vec![],
+            fulfillments: vec![],
+            line_items,
+            payment_terms: None,
+            refunds: vec![],
+            shipping_address: None,
+            shipping_lines: vec![],
+        }
+    }
+
+    /// Reverse of `to_shopify_order`. Orders that didn't start out locally get
+    /// a fresh ID and a nil user ID; line items without a product are skipped.
+    pub fn from_shopify_order(order: &ShopifyOrder) -> Self {
+        let note = |name: &str| {
+            order
+                .note_attributes
+                .iter()
+                .find(|attribute| attribute["name"] == name)
+                .and_then(|attribute| attribute["value"].as_str())
+                .and_then(|value| Uuid::parse_str(value).ok())
+        };
+
+        let line_items: Vec<LineItem> = order
+            .line_items
+            .iter()
+            .filter_map(|item| match serde_json::from_value::<ShopifyLineItem>(item.clone()) {
+                Ok(ShopifyLineItem { product_id: Some(product_id), quantity, price }) => Some(LineItem {
+                    product_id: product_uuid_from_shopify_id(product_id),
+                    quantity,
+                    unit_price: parse_amount(&price).unwrap_or(0.0),
+                }),
+                _ => {
+                    warn!("Skipping unrecognised line item on Shopify order {:?}", order.id);
+                    None
+                }
+            })
+            .collect();
+
+        let status = if order.cancelled_at.is_some() {
+            OrderStatus::Cancelled
+        } else if order.fulfillment_status.as_deref() == Some("fulfilled") {
+            if order.closed_at.is_some() { OrderStatus::Delivered } else { OrderStatus::Shipped }
+        } else if order.financial_status == "paid" {
+            OrderStatus::Processing
+        } else {
+            OrderStatus::Pending
+        };
+        let created_at = order.created_at.unwrap_or_else(Utc::now);
+
+        Self {
+            id: note(ORDER_ID_NOTE).unwrap_or_else(Uuid::new_v4),
+            user_id: note(USER_ID_NOTE).unwrap_or(Uuid::nil()),
+            total_amount: parse_amount(&order.total_price).unwrap_or_else(|| Order::calculate_total(&line_items)),
+            line_items,
+            status,
+            shopify_order_id: order.id.map(|id| id.to_string()),
+            created_at,
+            updated_at: order.updated_at.unwrap_or(created_at),
+        }
+    }
+}
+
+/// Streams every product as newline-delimited JSON, fetching one page of
+/// `page_size` at a time as the stream is polled rather than loading the whole
+/// catalog up front.
+pub fn products_ndjson(client: Arc<dyn ShopifyBackend>, page_size: u32) -> impl Stream<Item = Result<Bytes, ShopifyError>> {
+    // Cursor for the next page: `Some(None)` is the first page, `None` means done
+    let pages = stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+        let client = client.clone();
+        async move {
+            let Some(page_info) = cursor else {
+                return Ok::<_, ShopifyError>(None);
+            };
+            let (products, next_page_info) = client.get_products_paginated(page_size, page_info).await?;
+            Ok(Some((products, next_page_info.map(Some))))
+        }
+    });
+
+    pages
+        .map_ok(|products| {
+            stream::iter(products.into_iter().map(|product| Ok::<_, ShopifyError>(ndjson_line(&product_from_shopify(product)))))
+        })
+        .try_flatten()
+}
+
+fn ndjson_line(product: &Product) -> Bytes {
+    let mut line = serde_json::to_vec(product).expect("Product serializes to JSON");
+    line.push(b'\n');
+    Bytes::from(line)
+}
+
+// Units in stock for each mock product, including ones created later
+pub const DEFAULT_MOCK_INVENTORY: i64 = 100;
+// Shopify order numbers start at 1001
+const MOCK_FIRST_ORDER_ID: i64 = 1001;
+
+// Mock Shopify client for testing and demo purposes
+pub struct MockShopifyClient {
+    products: RwLock<Vec<ShopifyProduct>>,
+    inventory: Mutex<HashMap<i64, i64>>,
+    orders: RwLock<Vec<ShopifyOrder>>,
+    next_product_id: AtomicI64,
+    next_order_id: AtomicI64,
+    api_calls: AtomicUsize,
+    available: AtomicBool,
+    reject_orders: AtomicBool,
+    latency_ms: AtomicU64,
+}
+
+impl Default for MockShopifyClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockShopifyClient {
+    pub fn new() -> Self {
+        let products = Self::create_mock_products();
+        let next_product_id = products.iter().filter_map(|p| p.id).max().unwrap_or(0) + 1;
+        let inventory = products
+            .iter()
+            .filter_map(|p| p.id)
+            .map(|id| (id, DEFAULT_MOCK_INVENTORY))
+            .collect();
+
+        Self {
+            products: RwLock::new(products),
+            inventory: Mutex::new(inventory),
+            orders: RwLock::new(Self::create_mock_orders()),
+            next_product_id: AtomicI64::new(next_product_id),
+            next_order_id: AtomicI64::new(MOCK_FIRST_ORDER_ID),
+            api_calls: AtomicUsize::new(0),
+            available: AtomicBool::new(true),
+            reject_orders: AtomicBool::new(false),
+            latency_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn create_mock_products() -> Vec<ShopifyProduct> {
+        vec![
+            ShopifyProduct {
+                id: Some(1),
+                title: "Demo Product 1".to_string(),
+                body_html: Some("<p>This is a demo product</p>".to_string()),
                 vendor: "Demo Vendor".to_string(),
                 product_type: "Demo Type".to_string(),
                 created_at: Some(Utc::now()),
@@ -410,8 +1450,8 @@ impl MockShopifyClient {
                 status: "active".to_string(),
                 published_scope: "web".to_string(),
                 tags: "demo,test".to_string(),
-                admin_graphql_api_id: Some("gid://shopify/Product/1".to_string()),
-                variants: vec![],
+                admin_graphql_api_id: Some(Gid::new("Product", 1).to_string()),
+                variants: vec![ShopifyVariant::priced(99.99)],
                 options: vec![],
                 images: vec![],
             },
@@ -428,8 +1468,8 @@ impl MockShopifyClient {
                 status: "active".to_string(),
                 published_scope: "web".to_string(),
                 tags: "demo,test,featured".to_string(),
-                admin_graphql_api_id: Some("gid://shopify/Product/2".to_string()),
-                variants: vec![],
+                admin_graphql_api_id: Some(Gid::new("Product", 2).to_string()),
+                variants: vec![ShopifyVariant::priced(99.99)],
                 options: vec![],
                 images: vec![],
             },
@@ -440,27 +1480,838 @@ impl MockShopifyClient {
         vec![]
     }
 
+    /// Number of simulated Shopify API calls made through this client
+    pub fn api_call_count(&self) -> usize {
+        self.api_calls.load(Ordering::Relaxed)
+    }
+
+    /// Simulates a Shopify outage: while unavailable every call fails
+    pub fn set_available(&self, available: bool) {
+        self.available.store(available, Ordering::Relaxed);
+    }
+
+    /// Makes `create_order` fail while every other call still works, as when
+    /// Shopify rejects an order
+    pub fn set_reject_orders(&self, reject: bool) {
+        self.reject_orders.store(reject, Ordering::Relaxed);
+    }
+
+    /// Adds a delay to every call, to simulate a slow Shopify
+    pub fn set_latency(&self, latency: std::time::Duration) {
+        self.latency_ms.store(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Units of `product_id` currently in stock
+    pub fn inventory_quantity(&self, product_id: i64) -> i64 {
+        self.inventory.lock().unwrap().get(&product_id).copied().unwrap_or(0)
+    }
+
+    pub fn set_inventory(&self, product_id: i64, quantity: i64) {
+        self.inventory.lock().unwrap().insert(product_id, quantity);
+    }
+
+    async fn record_api_call(&self) -> Result<(), ShopifyError> {
+        self.api_calls.fetch_add(1, Ordering::Relaxed);
+
+        let latency_ms = self.latency_ms.load(Ordering::Relaxed);
+        if latency_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(latency_ms)).await;
+        }
+
+        if self.available.load(Ordering::Relaxed) {
+            Ok(())
+        } else {
+            Err(ShopifyError::ApiError("Service unavailable".to_string()))
+        }
+    }
+
     pub async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
-        Ok(self.products.clone())
+        self.record_api_call().await?;
+        Ok(self.products.read().unwrap().clone())
+    }
+
+    /// Same shape as `ShopifyClient::get_products_paginated`; the cursor is the
+    /// last product ID of the previous page.
+    pub async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
+        self.record_api_call().await?;
+
+        let after = match page_info {
+            Some(page_info) => page_info
+                .parse::<i64>()
+                .map_err(|_| ShopifyError::ApiError("Invalid page_info".to_string()))?,
+            None => 0,
+        };
+        let remaining: Vec<ShopifyProduct> = self
+            .products
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|p| p.id.is_some_and(|id| id > after))
+            .cloned()
+            .collect();
+
+        let has_more = remaining.len() > limit as usize;
+        let page: Vec<ShopifyProduct> = remaining.into_iter().take(limit as usize).collect();
+        let next_page_info = if has_more {
+            page.last().and_then(|p| p.id).map(|id| id.to_string())
+        } else {
+            None
+        };
+
+        Ok((page, next_page_info))
     }
 
     pub async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        self.record_api_call().await?;
         self.products
+            .read()
+            .unwrap()
             .iter()
             .find(|p| p.id == Some(product_id))
             .cloned()
             .ok_or(ShopifyError::ProductNotFound)
     }
 
+    pub async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        self.record_api_call().await?;
+        Ok(self.products
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|p| p.id.is_some_and(|id| product_ids.contains(&id)))
+            .cloned()
+            .collect())
+    }
+
     pub async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
-        let mut new_product = product.clone();
-        new_product.id = Some(999);
-        new_product.created_at = Some(Utc::now());
-        new_product.updated_at = Some(Utc::now());
-        Ok(new_product)
+        self.record_api_call().await?;
+        Ok(self.insert_product(product.clone()))
+    }
+
+    /// Creates several products with a single simulated API call
+    pub async fn create_products(&self, products: Vec<ShopifyProduct>) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        self.record_api_call().await?;
+        Ok(products.into_iter().map(|product| self.insert_product(product)).collect())
+    }
+
+    fn insert_product(&self, mut product: ShopifyProduct) -> ShopifyProduct {
+        let id = self.next_product_id.fetch_add(1, Ordering::Relaxed);
+
+        product.id = Some(id);
+        product.admin_graphql_api_id = Some(Gid::new("Product", id).to_string());
+        product.created_at = Some(Utc::now());
+        product.updated_at = Some(Utc::now());
+        self.products.write().unwrap().push(product.clone());
+        self.inventory.lock().unwrap().insert(id, DEFAULT_MOCK_INVENTORY);
+
+        product
+    }
+
+    pub async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        self.record_api_call().await?;
+        let mut products = self.products.write().unwrap();
+        let existing = products
+            .iter_mut()
+            .find(|p| p.id == Some(product_id))
+            .ok_or(ShopifyError::ProductNotFound)?;
+
+        let mut updated_product = product.clone();
+        updated_product.id = existing.id;
+        updated_product.created_at = existing.created_at;
+        updated_product.updated_at = Some(Utc::now());
+        *existing = updated_product.clone();
+
+        Ok(updated_product)
+    }
+
+    pub async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError> {
+        self.record_api_call().await?;
+        let mut products = self.products.write().unwrap();
+        let index = products
+            .iter()
+            .position(|p| p.id == Some(product_id))
+            .ok_or(ShopifyError::ProductNotFound)?;
+        products.remove(index);
+        self.inventory.lock().unwrap().remove(&product_id);
+        Ok(())
     }
 
     pub async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
-        Ok(self.orders.clone())
+        self.record_api_call().await?;
+        Ok(self.orders.read().unwrap().clone())
+    }
+
+    pub async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+        self.record_api_call().await?;
+        if self.reject_orders.load(Ordering::Relaxed) {
+            return Err(ShopifyError::ApiError("Order rejected".to_string()));
+        }
+        let id = self.next_order_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut order = order.clone();
+        order.id = Some(id);
+        order.admin_graphql_api_id = Some(Gid::new("Order", id).to_string());
+        order.name = format!("#{}", id);
+        order.number = (id - MOCK_FIRST_ORDER_ID + 1) as i32;
+        order.order_number = id as i32;
+        self.orders.write().unwrap().push(order.clone());
+
+        Ok(order)
+    }
+
+    pub async fn reserve_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        self.record_api_call().await?;
+
+        // A product on several lines is checked against its total quantity
+        let mut requested: HashMap<i64, i64> = HashMap::new();
+        for &(product_id, quantity) in items {
+            *requested.entry(product_id).or_default() += quantity;
+        }
+
+        // Check and decrement under one lock, so concurrent orders can't both
+        // take the last units
+        let mut inventory = self.inventory.lock().unwrap();
+        for (&product_id, &quantity) in &requested {
+            let available = inventory.get(&product_id).copied().unwrap_or(0);
+            if available < quantity {
+                return Err(ShopifyError::InsufficientInventory { product_id, requested: quantity, available });
+            }
+        }
+        for (product_id, quantity) in requested {
+            *inventory.entry(product_id).or_default() -= quantity;
+        }
+
+        Ok(())
+    }
+
+    pub async fn release_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        self.record_api_call().await?;
+
+        let mut inventory = self.inventory.lock().unwrap();
+        for &(product_id, quantity) in items {
+            *inventory.entry(product_id).or_default() += quantity;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ShopifyBackend for MockShopifyClient {
+    async fn get_products(&self) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        MockShopifyClient::get_products(self).await
+    }
+
+    async fn get_products_paginated(
+        &self,
+        limit: u32,
+        page_info: Option<String>,
+    ) -> Result<(Vec<ShopifyProduct>, Option<String>), ShopifyError> {
+        MockShopifyClient::get_products_paginated(self, limit, page_info).await
+    }
+
+    async fn get_product(&self, product_id: i64) -> Result<ShopifyProduct, ShopifyError> {
+        MockShopifyClient::get_product(self, product_id).await
+    }
+
+    async fn get_products_by_ids(&self, product_ids: &[i64]) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        MockShopifyClient::get_products_by_ids(self, product_ids).await
+    }
+
+    async fn create_product(&self, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        MockShopifyClient::create_product(self, product).await
+    }
+
+    async fn create_products(&self, products: Vec<ShopifyProduct>) -> Result<Vec<ShopifyProduct>, ShopifyError> {
+        MockShopifyClient::create_products(self, products).await
+    }
+
+    async fn update_product(&self, product_id: i64, product: &ShopifyProduct) -> Result<ShopifyProduct, ShopifyError> {
+        MockShopifyClient::update_product(self, product_id, product).await
+    }
+
+    async fn delete_product(&self, product_id: i64) -> Result<(), ShopifyError> {
+        MockShopifyClient::delete_product(self, product_id).await
+    }
+
+    async fn get_orders(&self) -> Result<Vec<ShopifyOrder>, ShopifyError> {
+        MockShopifyClient::get_orders(self).await
+    }
+
+    async fn create_order(&self, order: &ShopifyOrder) -> Result<ShopifyOrder, ShopifyError> {
+        MockShopifyClient::create_order(self, order).await
+    }
+
+    async fn reserve_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        MockShopifyClient::reserve_inventory(self, items).await
+    }
+
+    async fn release_inventory(&self, items: &[(i64, i64)]) -> Result<(), ShopifyError> {
+        MockShopifyClient::release_inventory(self, items).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_client() -> ShopifyClient {
+        ShopifyClient::new(ShopifyConfig {
+            webhook_secret: "test-webhook-secret".to_string(),
+            ..ShopifyConfig::default()
+        })
+    }
+
+    const TEST_PAYLOAD: &str = r#"{"id":123,"title":"Demo Product"}"#;
+    const TEST_SIGNATURE: &str = "X2dTxD5hoKQIZpS6bRCe86F4F+RdeJP9N+7gNUGf8fA=";
+
+    #[test]
+    fn test_verify_webhook_valid_signature() {
+        assert!(test_client().verify_webhook(TEST_PAYLOAD, TEST_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn test_verify_webhook_wrong_signature() {
+        let tampered_payload = r#"{"id":124,"title":"Demo Product"}"#;
+        assert!(!test_client().verify_webhook(tampered_payload, TEST_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn test_verify_webhook_malformed_base64() {
+        assert!(matches!(
+            test_client().verify_webhook(TEST_PAYLOAD, "not base64!"),
+            Err(ShopifyError::InvalidWebhookSignature)
+        ));
+    }
+
+    #[test]
+    fn test_parse_next_page_info() {
+        let link = r#"<https://demo.myshopify.com/admin/api/2023-10/products.json?limit=2&page_info=prev123>; rel="previous", <https://demo.myshopify.com/admin/api/2023-10/products.json?limit=2&page_info=next456>; rel="next""#;
+        assert_eq!(parse_next_page_info(link), Some("next456".to_string()));
+
+        let last_page = r#"<https://demo.myshopify.com/admin/api/2023-10/products.json?limit=2&page_info=prev123>; rel="previous""#;
+        assert_eq!(parse_next_page_info(last_page), None);
+    }
+
+    #[test]
+    fn test_product_price_comes_from_first_variant() {
+        let mut product = MockShopifyClient::create_mock_products().remove(0);
+        product.variants = vec![ShopifyVariant::priced(19.5), ShopifyVariant::priced(5.0)];
+        assert_eq!(product_from_shopify(product.clone()).price, 19.5);
+
+        product.variants[0].price = "not a price".to_string();
+        assert_eq!(product_price(&product), 0.0);
+
+        product.variants.clear();
+        assert_eq!(product_price(&product), 0.0);
+    }
+
+    fn product_json(id: i64) -> serde_json::Value {
+        let mut product = MockShopifyClient::create_mock_products().remove(0);
+        product.id = Some(id);
+        serde_json::to_value(product).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_api_calls_are_counted_per_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/products.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "products": [product_json(1)] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/products/7.json"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = ShopifyClient::with_base_url(ShopifyConfig::default(), server.uri());
+        assert!(client.metrics().is_empty());
+
+        client.get_products().await.unwrap();
+        let products = client.metrics()["GET /products.json"];
+        assert_eq!(products.calls, 1);
+        assert_eq!(products.errors, 0);
+        assert!(products.total_duration_ms > 0.0);
+
+        assert!(client.get_product(7).await.is_err());
+        let product = client.metrics()["GET /products/:id.json"];
+        assert_eq!(product.calls, 1);
+        assert_eq!(product.errors, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_products_paginated_follows_link_header() {
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let next_link = format!(r#"<{}/products.json?limit=1&page_info=cursor2>; rel="next""#, server.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/products.json"))
+            .and(query_param("limit", "1"))
+            .and(query_param_is_missing("page_info"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", next_link.as_str())
+                    .set_body_json(serde_json::json!({ "products": [product_json(1)] })),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/products.json"))
+            .and(query_param("page_info", "cursor2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "products": [product_json(2)] })),
+            )
+            .mount(&server)
+            .await;
+
+        let client = ShopifyClient::with_base_url(ShopifyConfig::default(), server.uri());
+
+        let (products, next) = client.get_products_paginated(1, None).await.unwrap();
+        assert_eq!(products.len(), 1);
+        assert_eq!(next, Some("cursor2".to_string()));
+
+        let (products, next) = client.get_products_paginated(1, next).await.unwrap();
+        assert_eq!(products[0].id, Some(2));
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_is_retried() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/products/1.json"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/products/1.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "product": product_json(1) })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ShopifyClient::with_base_url(ShopifyConfig::default(), server.uri());
+
+        let product = client.get_product(1).await.unwrap();
+        assert_eq!(product.id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_times_out() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/products/1.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "product": product_json(1) }))
+                    .set_delay(std::time::Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+
+        let config = ShopifyConfig {
+            request_timeout_ms: 100,
+            ..ShopifyConfig::default()
+        };
+        let client = ShopifyClient::with_base_url(config, server.uri());
+
+        match client.get_product(1).await {
+            Err(ShopifyError::HttpError(e)) => assert!(e.is_timeout()),
+            other => panic!("expected a timeout, got {:?}", other.map(|p| p.id)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_exceeded_after_max_retries() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/products/1.json"))
+            .respond_with(ResponseTemplate::new(429))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let config = ShopifyConfig {
+            max_retries: 2,
+            retry_base_delay_ms: 1,
+            ..ShopifyConfig::default()
+        };
+        let client = ShopifyClient::with_base_url(config, server.uri());
+
+        assert!(matches!(client.get_product(1).await, Err(ShopifyError::RateLimitExceeded)));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        use std::time::Duration;
+
+        assert_eq!(retry_delay(None, 500, 0), Duration::from_millis(500));
+        assert_eq!(retry_delay(None, 500, 2), Duration::from_millis(2000));
+        // Would overflow u64 without saturating
+        assert_eq!(retry_delay(None, 500, 64), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(None, u64::MAX, 1), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(Some(Duration::from_secs(2)), 500, 5), Duration::from_secs(2));
+        assert_eq!(retry_delay(Some(Duration::from_secs(3600)), 500, 0), MAX_RETRY_DELAY);
+    }
+
+    fn webhook_headers(topic: &str, triggered_at: DateTime<Utc>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Shopify-Topic", topic.parse().unwrap());
+        headers.insert("X-Shopify-Shop-Domain", "demo.myshopify.com".parse().unwrap());
+        headers.insert("X-Shopify-Triggered-At", triggered_at.to_rfc3339().parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_products_create_webhook() {
+        let webhook = ShopifyWebhook::parse(&webhook_headers("products/create", Utc::now()), &product_json(1).to_string()).unwrap();
+
+        assert_eq!(webhook.topic, "products/create");
+        assert_eq!(webhook.shop_domain, "demo.myshopify.com");
+        assert!(matches!(&webhook.payload, WebhookPayload::Product(product) if product.title == "Demo Product 1"));
+        assert!(webhook.ensure_fresh(Duration::seconds(300)).is_ok());
+    }
+
+    #[test]
+    fn test_stale_webhook_is_rejected() {
+        let headers = webhook_headers("products/create", Utc::now() - Duration::minutes(10));
+        let webhook = ShopifyWebhook::parse(&headers, &product_json(1).to_string()).unwrap();
+
+        assert!(matches!(
+            webhook.ensure_fresh(Duration::seconds(300)),
+            Err(ShopifyError::StaleWebhook)
+        ));
+    }
+
+    #[test]
+    fn test_future_dated_webhook_is_rejected() {
+        let headers = webhook_headers("products/create", Utc::now() + Duration::days(365 * 75));
+        let webhook = ShopifyWebhook::parse(&headers, &product_json(1).to_string()).unwrap();
+
+        assert!(matches!(
+            webhook.ensure_fresh(Duration::seconds(300)),
+            Err(ShopifyError::StaleWebhook)
+        ));
+
+        // A little clock skew is still fine
+        let headers = webhook_headers("products/create", Utc::now() + Duration::seconds(30));
+        let webhook = ShopifyWebhook::parse(&headers, &product_json(1).to_string()).unwrap();
+        assert!(webhook.ensure_fresh(Duration::seconds(300)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_webhook_requires_topic() {
+        let mut headers = webhook_headers("products/create", Utc::now());
+        headers.remove("X-Shopify-Topic");
+
+        assert!(matches!(
+            ShopifyWebhook::parse(&headers, &product_json(1).to_string()),
+            Err(ShopifyError::InvalidWebhookPayload(_))
+        ));
+    }
+
+    fn order_json() -> serde_json::Value {
+        serde_json::json!({
+            "id": 1001,
+            "buyer_accepts_marketing": false,
+            "confirmed": true,
+            "currency": "USD",
+            "current_subtotal_price": "29.99",
+            "current_total_discounts": "0.00",
+            "current_total_price": "29.99",
+            "current_total_tax": "0.00",
+            "discount_codes": [],
+            "email": "customer@example.com",
+            "estimated_taxes": false,
+            "financial_status": "paid",
+            "gateway": "manual",
+            "name": "#1001",
+            "note_attributes": [],
+            "number": 1,
+            "order_number": 1001,
+            "order_status_url": "https://demo.myshopify.com/orders/1001",
+            "payment_gateway_names": ["manual"],
+            "presentment_currency": "USD",
+            "processing_method": "manual",
+            "source_name": "web",
+            "subtotal_price": "29.99",
+            "tags": "",
+            "tax_lines": [],
+            "taxes_included": false,
+            "test": true,
+            "token": "order-token",
+            "total_discounts": "0.00",
+            "total_line_items_price": "29.99",
+            "total_outstanding": "0.00",
+            "total_price": "29.99",
+            "total_price_usd": "29.99",
+            "total_tax": "0.00",
+            "total_tip_received": "0.00",
+            "total_weight": 0,
+            "discount_applications": [],
+            "fulfillments": [],
+            "line_items": [{"product_id": 1, "quantity": 1}],
+            "refunds": [],
+            "shipping_lines": []
+        })
+    }
+
+    #[test]
+    fn test_parse_orders_create_webhook() {
+        let headers = webhook_headers("orders/create", Utc::now());
+        let webhook = ShopifyWebhook::parse(&headers, &order_json().to_string()).unwrap();
+
+        match webhook.payload {
+            WebhookPayload::Order(order) => {
+                assert_eq!(order.id, Some(1001));
+                assert_eq!(order.total_price, "29.99");
+            }
+            other => panic!("expected an order payload, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_malformed_orders_create_webhook_is_rejected() {
+        let headers = webhook_headers("orders/create", Utc::now());
+        let mut order = order_json();
+        order.as_object_mut().unwrap().remove("total_price");
+
+        match ShopifyWebhook::parse(&headers, &order.to_string()) {
+            Err(ShopifyError::InvalidWebhookPayload(message)) => {
+                assert!(message.contains("orders/create payload is not a valid order"), "{}", message);
+                assert!(message.contains("total_price"), "{}", message);
+            }
+            other => panic!("expected InvalidWebhookPayload, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unmodelled_webhook_topic_is_passed_through() {
+        let headers = webhook_headers("app/uninstalled", Utc::now());
+        let webhook = ShopifyWebhook::parse(&headers, TEST_PAYLOAD).unwrap();
+
+        assert!(matches!(webhook.payload, WebhookPayload::Other(payload) if payload["id"] == 123));
+    }
+
+    #[test]
+    fn test_webhook_queue_drops_when_full() {
+        let (queue, mut receiver) = WebhookQueue::new(1);
+        let webhook = ShopifyWebhook::parse(&webhook_headers("products/create", Utc::now()), &product_json(1).to_string()).unwrap();
+
+        assert!(queue.enqueue(webhook.clone()));
+        assert!(!queue.enqueue(webhook));
+
+        assert_eq!(receiver.try_recv().unwrap().topic, "products/create");
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[derive(Default)]
+    struct CountingWebhookHandler(AtomicUsize);
+
+    impl WebhookHandler for CountingWebhookHandler {
+        fn handle(&self, _webhook: &ShopifyWebhook) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_worker_shutdown_handles_queued_webhooks() {
+        let (queue, receiver) = WebhookQueue::new(8);
+        let handler = Arc::new(CountingWebhookHandler::default());
+        let webhook = ShopifyWebhook::parse(&webhook_headers("products/create", Utc::now()), &product_json(1).to_string()).unwrap();
+        for _ in 0..3 {
+            assert!(queue.enqueue(webhook.clone()));
+        }
+
+        let worker = WebhookWorker::spawn(receiver, handler.clone());
+        worker.shutdown().await;
+
+        assert_eq!(handler.0.load(Ordering::SeqCst), 3);
+        // The queue is still referenced here, but closed
+        assert!(!queue.enqueue(webhook));
+    }
+
+    #[tokio::test]
+    async fn test_products_ndjson_fetches_one_page_at_a_time() {
+        let client = Arc::new(MockShopifyClient::new());
+        let product_count = client.get_products().await.unwrap().len();
+
+        let lines: Vec<Bytes> = products_ndjson(client.clone(), 1).try_collect().await.unwrap();
+
+        assert_eq!(lines.len(), product_count);
+        for line in &lines {
+            assert_eq!(line.last(), Some(&b'\n'));
+            serde_json::from_slice::<Product>(line).unwrap();
+        }
+        // One call for the count above, then one per single-product page
+        assert_eq!(client.api_call_count(), 1 + product_count);
+    }
+
+    #[test]
+    fn test_select_shopify_backend() {
+        let live = Arc::new(ShopifyClient::new(ShopifyConfig::default()));
+        let is_live = |backend: Arc<dyn ShopifyBackend>| Arc::as_ptr(&backend) as *const () == Arc::as_ptr(&live) as *const ();
+
+        assert!(is_live(select_shopify_backend(Some("live"), live.clone())));
+        assert!(!is_live(select_shopify_backend(None, live.clone())));
+        assert!(!is_live(select_shopify_backend(Some("mock"), live.clone())));
+        assert!(!is_live(select_shopify_backend(Some("staging"), live.clone())));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reserve_inventory_is_all_or_nothing() {
+        let client = MockShopifyClient::new();
+        client.set_inventory(1, 5);
+        client.set_inventory(2, 5);
+
+        client.reserve_inventory(&[(1, 2), (2, 1)]).await.unwrap();
+        assert_eq!(client.inventory_quantity(1), 3);
+        assert_eq!(client.inventory_quantity(2), 4);
+
+        // Product 1 is short once its two lines are summed, so product 2 keeps its stock too
+        assert!(matches!(
+            client.reserve_inventory(&[(1, 2), (1, 2), (2, 1)]).await,
+            Err(ShopifyError::InsufficientInventory { product_id: 1, requested: 4, available: 3 })
+        ));
+        assert_eq!(client.inventory_quantity(1), 3);
+        assert_eq!(client.inventory_quantity(2), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mock_delete_product() {
+        let client = MockShopifyClient::new();
+
+        client.delete_product(1).await.unwrap();
+
+        assert!(matches!(client.get_product(1).await, Err(ShopifyError::ProductNotFound)));
+        assert_eq!(client.get_products().await.unwrap().len(), 1);
+        assert!(matches!(client.delete_product(1).await, Err(ShopifyError::ProductNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_mock_create_product_is_retrievable() {
+        let client = MockShopifyClient::new();
+        let mut product = client.get_product(1).await.unwrap();
+        product.id = None;
+        product.title = "Created Product".to_string();
+
+        let first = client.create_product(&product).await.unwrap();
+        let second = client.create_product(&product).await.unwrap();
+        assert_eq!(first.id, Some(3));
+        assert_eq!(second.id, Some(4));
+
+        let fetched = client.get_product(first.id.unwrap()).await.unwrap();
+        assert_eq!(fetched.title, "Created Product");
+        assert_eq!(fetched.admin_graphql_api_id, Some("gid://shopify/Product/3".to_string()));
+        assert_eq!(client.get_products().await.unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_order_round_trips_through_shopify_order() {
+        let mut order = Order::new(
+            Uuid::new_v4(),
+            vec![
+                LineItem { product_id: product_uuid_from_shopify_id(1), quantity: 3, unit_price: 19.99 },
+                LineItem { product_id: product_uuid_from_shopify_id(2), quantity: 1, unit_price: 5.5 },
+            ],
+        );
+        order.status = OrderStatus::Shipped;
+
+        let shopify_order = order.to_shopify_order();
+        assert_eq!(shopify_order.total_price, "65.47");
+        assert_eq!(shopify_order.currency, DEFAULT_ORDER_CURRENCY);
+        assert_eq!(shopify_order.line_items[0]["price"], "19.99");
+
+        let restored = Order::from_shopify_order(&shopify_order);
+        assert_eq!(format!("{:.2}", restored.total_amount), format!("{:.2}", order.total_amount));
+        assert_eq!(restored.id, order.id);
+        assert_eq!(restored.user_id, order.user_id);
+        assert_eq!(restored.status, OrderStatus::Shipped);
+        assert_eq!(restored.line_items.len(), 2);
+        assert_eq!(restored.line_items[0].product_id, order.line_items[0].product_id);
+        assert_eq!(restored.line_items[0].quantity, 3);
+    }
+
+    #[tokio::test]
+    async fn test_mock_create_order_assigns_id() {
+        let client = MockShopifyClient::new();
+        let order = Order::new(
+            Uuid::new_v4(),
+            vec![LineItem { product_id: product_uuid_from_shopify_id(1), quantity: 2, unit_price: 29.99 }],
+        );
+
+        let created = client.create_order(&order.to_shopify_order()).await.unwrap();
+
+        assert_eq!(created.id, Some(MOCK_FIRST_ORDER_ID));
+        assert_eq!(created.total_price, "59.98");
+        assert_eq!(client.get_orders().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_product_applies_partial_input() {
+        let client = MockShopifyClient::new();
+        let mut product = client.get_product(1).await.unwrap();
+
+        product
+            .apply_update(&UpdateProductInput {
+                name: Some("Renamed Product".to_string()),
+                ..UpdateProductInput::default()
+            })
+            .unwrap();
+        client.update_product(1, &product).await.unwrap();
+
+        let updated = client.get_product(1).await.unwrap();
+        assert_eq!(updated.title, "Renamed Product");
+        assert_eq!(updated.body_html, Some("<p>This is a demo product</p>".to_string()));
+        assert!(matches!(
+            client.update_product(42, &product).await,
+            Err(ShopifyError::ProductNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_price_update_needs_a_variant() {
+        let mut product = MockShopifyClient::new().get_product(1).await.unwrap();
+        product.variants.clear();
+
+        let result = product.apply_update(&UpdateProductInput {
+            name: Some("Renamed Product".to_string()),
+            price: Some(12.5),
+            ..UpdateProductInput::default()
+        });
+        assert!(result.is_err());
+        assert_ne!(product.title, "Renamed Product");
     }
 }
@@ -0,0 +1,95 @@
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// Resolves when the process receives Ctrl-C or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c().await.expect("failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serves `app` until `shutdown` resolves, then stops accepting connections and
+/// waits up to `drain_timeout` for in-flight requests before dropping them.
+pub async fn serve_with_graceful_shutdown<F>(
+    listener: TcpListener,
+    app: Router,
+    shutdown: F,
+    drain_timeout: Duration,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (draining_tx, draining_rx) = oneshot::channel();
+    let signal = async move {
+        shutdown.await;
+        info!("shutting down, draining connections");
+        let _ = draining_tx.send(());
+    };
+
+    // Peer addresses are exposed as `ConnectInfo` for per-client rate limiting
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = axum::serve(listener, app).with_graceful_shutdown(signal);
+
+    tokio::select! {
+        result = server.into_future() => result,
+        _ = async {
+            // Only start the clock once shutdown has actually been requested
+            if draining_rx.await.is_ok() {
+                tokio::time::sleep(drain_timeout).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        } => {
+            warn!("Drain timeout of {:?} elapsed, dropping remaining connections", drain_timeout);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_serve_resolves_after_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (trigger_tx, trigger_rx) = oneshot::channel::<()>();
+        let shutdown = async {
+            let _ = trigger_rx.await;
+        };
+
+        let server = tokio::spawn(serve_with_graceful_shutdown(
+            listener,
+            Router::new(),
+            shutdown,
+            Duration::from_secs(5),
+        ));
+
+        trigger_tx.send(()).unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(result, Ok(Ok(Ok(())))));
+    }
+}
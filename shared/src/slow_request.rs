@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// Used when `slow_request_threshold_ms` isn't configured
+pub const DEFAULT_SLOW_REQUEST_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Logs a warning with the method, path and duration of every request that
+/// takes longer than `threshold`, to make latency regressions stand out during
+/// benchmarks. Fast requests only pay for a clock read and two cheap clones.
+pub async fn log_slow_requests(State(threshold): State<Duration>, req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().clone();
+    // `Uri` is reference counted, so this doesn't copy the path
+    let uri = req.uri().clone();
+
+    let response = next.run(req).await;
+
+    let elapsed = start.elapsed();
+    if elapsed > threshold {
+        warn!(
+            method = %method,
+            path = %uri.path(),
+            duration_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            "slow request"
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::CapturedLogs;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    // Logs captured while `path` is requested from a router whose `/slow`
+    // handler takes 100ms, with a 50ms threshold
+    async fn logs_for(path: &str) -> String {
+        let logs = CapturedLogs::default();
+        let _guard = logs.capture();
+
+        let app = Router::new()
+            .route("/slow", get(|| async { tokio::time::sleep(Duration::from_millis(100)).await }))
+            .route("/fast", get(|| async {}))
+            .layer(middleware::from_fn_with_state(Duration::from_millis(50), log_slow_requests));
+        app.oneshot(axum::http::Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+
+        logs.contents()
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_is_logged() {
+        let output = logs_for("/slow").await;
+
+        let line = output
+            .lines()
+            .find(|line| line.contains("slow request"))
+            .expect("the slow request wasn't logged");
+        assert!(line.contains("WARN"), "{}", line);
+        assert!(line.contains("method=GET"), "{}", line);
+        assert!(line.contains("path=/slow"), "{}", line);
+        assert!(line.contains("duration_ms="), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn test_fast_request_is_not_logged() {
+        assert!(!logs_for("/fast").await.contains("slow request"));
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessStats {
+    pub memory_usage_mb: f64,
+    pub cpu_usage_percent: f64,
+}
+
+// CPU usage is computed from the difference between two refreshes, so the
+// same `System` is kept around between samples.
+static SYSTEM: OnceLock<Mutex<System>> = OnceLock::new();
+
+/// Samples the resident memory and CPU usage of the current process.
+/// Falls back to zeroed stats if the process can't be inspected.
+pub fn sample_process() -> ProcessStats {
+    match try_sample_process() {
+        Ok(stats) => stats,
+        Err(e) => {
+            warn!("Failed to sample process metrics: {}", e);
+            ProcessStats::default()
+        }
+    }
+}
+
+fn try_sample_process() -> Result<ProcessStats, String> {
+    let pid: Pid = sysinfo::get_current_pid().map_err(|e| e.to_string())?;
+
+    let mut system = SYSTEM
+        .get_or_init(|| Mutex::new(System::new()))
+        .lock()
+        .map_err(|e| e.to_string())?;
+
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_memory().with_cpu(),
+    );
+
+    let process = system
+        .process(pid)
+        .ok_or_else(|| format!("process {} not found", pid))?;
+
+    Ok(ProcessStats {
+        memory_usage_mb: process.memory() as f64 / (1024.0 * 1024.0),
+        cpu_usage_percent: process.cpu_usage() as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_process_reports_memory() {
+        let stats = sample_process();
+        assert!(stats.memory_usage_mb > 0.0);
+        assert!(stats.cpu_usage_percent >= 0.0);
+    }
+}
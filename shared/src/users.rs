@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::auth::{AuthError, AuthService};
+use crate::models::{CreateUserInput, User};
+
+/// Where registered users and their password hashes are kept. Emails are
+/// matched case-insensitively.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AuthError>;
+
+    /// Stores a new user with a hash of `input.password`. Fails with
+    /// `EmailAlreadyExists` if the email is taken.
+    async fn create(&self, input: &CreateUserInput) -> Result<User, AuthError>;
+
+    /// The user with `email` if `password` matches their hash. An unknown email
+    /// is `InvalidCredentials` too, so callers can't tell which part was wrong.
+    async fn verify_credentials(&self, email: &str, password: &str) -> Result<User, AuthError>;
+}
+
+#[derive(Debug, Clone)]
+struct StoredUser {
+    user: User,
+    password_hash: String,
+}
+
+/// Users held in memory for the lifetime of the process
+pub struct InMemoryUserRepository {
+    // Hashes passwords at the configured bcrypt cost
+    auth_service: Arc<AuthService>,
+    // Keyed by lowercased email
+    users: RwLock<HashMap<String, StoredUser>>,
+    // Checked against for unknown emails, made on first use at the same cost
+    dummy_hash: OnceLock<String>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new(auth_service: Arc<AuthService>) -> Self {
+        Self {
+            auth_service,
+            users: RwLock::new(HashMap::new()),
+            dummy_hash: OnceLock::new(),
+        }
+    }
+
+    fn stored(&self, email: &str) -> Option<StoredUser> {
+        self.users.read().unwrap().get(&email_key(email)).cloned()
+    }
+
+    // bcrypt is deliberately slow, so hashing and checking run on the blocking
+    // pool instead of holding up a runtime worker
+    async fn hash_password(&self, password: &str) -> Result<String, AuthError> {
+        let auth_service = self.auth_service.clone();
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || auth_service.hash_password(&password))
+            .await
+            .map_err(|_| AuthError::PasswordHashingFailed)?
+    }
+
+    async fn verify_password(&self, password: &str, hash: String) -> Result<bool, AuthError> {
+        let auth_service = self.auth_service.clone();
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || auth_service.verify_password(&password, &hash))
+            .await
+            .map_err(|_| AuthError::PasswordHashingFailed)?
+    }
+}
+
+/// The form emails are matched in: trimmed and lowercased, so differently
+/// cased spellings of one address count as the same account
+pub fn email_key(email: &str) -> String {
+    email.trim().to_ascii_lowercase()
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AuthError> {
+        Ok(self.stored(email).map(|stored| stored.user))
+    }
+
+    async fn create(&self, input: &CreateUserInput) -> Result<User, AuthError> {
+        let key = email_key(&input.email);
+        if self.users.read().unwrap().contains_key(&key) {
+            return Err(AuthError::EmailAlreadyExists);
+        }
+
+        // Hashed before taking the write lock, since bcrypt is deliberately slow
+        let password_hash = self.hash_password(&input.password).await?;
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: input.email.trim().to_string(),
+            name: input.name.clone(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut users = self.users.write().unwrap();
+        // Someone may have registered the same email while we were hashing
+        if users.contains_key(&key) {
+            return Err(AuthError::EmailAlreadyExists);
+        }
+        users.insert(key, StoredUser { user: user.clone(), password_hash });
+        Ok(user)
+    }
+
+    async fn verify_credentials(&self, email: &str, password: &str) -> Result<User, AuthError> {
+        let Some(stored) = self.stored(email) else {
+            // Pay for a bcrypt check anyway, so response times don't reveal
+            // which emails are registered
+            let dummy_hash = match self.dummy_hash.get() {
+                Some(hash) => hash,
+                None => {
+                    let hash = self.hash_password("not-a-registered-password").await?;
+                    self.dummy_hash.get_or_init(|| hash)
+                }
+            };
+            let _ = self.verify_password(password, dummy_hash.clone()).await;
+            return Err(AuthError::InvalidCredentials);
+        };
+
+        if self.verify_password(password, stored.password_hash).await? {
+            Ok(stored.user)
+        } else {
+            Err(AuthError::InvalidCredentials)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository() -> InMemoryUserRepository {
+        let auth_service = AuthService::with_bcrypt_cost("test-secret".to_string(), 4).unwrap();
+        InMemoryUserRepository::new(Arc::new(auth_service))
+    }
+
+    fn input(email: &str, password: &str) -> CreateUserInput {
+        CreateUserInput {
+            email: email.to_string(),
+            name: "Test User".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_user_can_log_in() {
+        let users = repository();
+        let created = users.create(&input("test@example.com", "TestPassword123!")).await.unwrap();
+
+        let user = users.verify_credentials("Test@Example.com", "TestPassword123!").await.unwrap();
+        assert_eq!(user.id, created.id);
+        assert_eq!(users.find_by_email("test@example.com").await.unwrap().map(|user| user.id), Some(created.id));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_and_unknown_email_are_rejected() {
+        let users = repository();
+        users.create(&input("test@example.com", "TestPassword123!")).await.unwrap();
+
+        assert!(matches!(
+            users.verify_credentials("test@example.com", "WrongPassword123!").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            users.verify_credentials("nobody@example.com", "TestPassword123!").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_email_can_only_be_registered_once() {
+        let users = repository();
+        users.create(&input("test@example.com", "TestPassword123!")).await.unwrap();
+
+        assert!(matches!(
+            users.create(&input("TEST@example.com", "OtherPassword123!")).await,
+            Err(AuthError::EmailAlreadyExists)
+        ));
+    }
+}
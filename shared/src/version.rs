@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::benchmarks::BuildInfo;
+
+const UNKNOWN_VERSION: &str = "unknown";
+
+// Resolved from Cargo.lock by the build script
+pub const AXUM_VERSION: &str = match option_env!("LOCKED_AXUM_VERSION") {
+    Some(version) => version,
+    None => UNKNOWN_VERSION,
+};
+pub const ASYNC_GRAPHQL_VERSION: &str = match option_env!("LOCKED_ASYNC_GRAPHQL_VERSION") {
+    Some(version) => version,
+    None => UNKNOWN_VERSION,
+};
+
+/// What `/version` reports, to confirm which build is deployed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub framework: String,
+    pub version: String,
+    pub axum_version: String,
+    pub async_graphql_version: String,
+    pub build_timestamp: String,
+}
+
+impl VersionInfo {
+    /// `version` is the server crate's own, i.e. its `CARGO_PKG_VERSION`
+    pub fn new(framework: &str, version: &str) -> Self {
+        Self {
+            framework: framework.to_string(),
+            version: version.to_string(),
+            axum_version: AXUM_VERSION.to_string(),
+            async_graphql_version: ASYNC_GRAPHQL_VERSION.to_string(),
+            build_timestamp: BuildInfo::current().build_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every version of `name` in the workspace's Cargo.lock
+    fn locked_versions(name: &str) -> Vec<String> {
+        let lockfile: toml::Table = include_str!("../../Cargo.lock").parse().unwrap();
+        lockfile["package"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|package| package["name"].as_str() == Some(name))
+            .map(|package| package["version"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_versions_match_lockfile() {
+        assert!(locked_versions("axum").contains(&AXUM_VERSION.to_string()), "{}", AXUM_VERSION);
+        assert!(
+            locked_versions("async-graphql").contains(&ASYNC_GRAPHQL_VERSION.to_string()),
+            "{}",
+            ASYNC_GRAPHQL_VERSION
+        );
+    }
+}